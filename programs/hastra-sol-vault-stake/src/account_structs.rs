@@ -3,6 +3,7 @@ use crate::state::*;
 use anchor_lang::prelude::*;
 use anchor_spl::token::{Mint, Token, TokenAccount};
 
+#[allow(deprecated)]
 use anchor_lang::solana_program::bpf_loader_upgradeable::{self};
 
 #[derive(Accounts)]
@@ -95,6 +96,7 @@ pub struct UpdateConfig<'info> {
 #[derive(Accounts)]
 pub struct Deposit<'info> {
     #[account(
+        mut,
         seeds = [b"config"], 
         bump = config.bump
     )]
@@ -168,6 +170,7 @@ pub struct Unbond<'info> {
     pub mint: Account<'info, Mint>,
 
     #[account(
+        mut,
         token::mint = config.mint,
         constraint = user_mint_token_account.mint == config.mint @ CustomErrorCode::InvalidMint,
         constraint = user_mint_token_account.owner == signer.key() @ CustomErrorCode::InvalidMintAuthority
@@ -175,21 +178,41 @@ pub struct Unbond<'info> {
     )]
     pub user_mint_token_account: Account<'info, TokenAccount>,
 
+    /// Program-owned token account that holds the stake tokens (e.g. PRIME) of open tickets
+    #[account(
+        mut,
+        seeds = [b"stake_escrow"],
+        bump,
+        token::mint = config.mint,
+    )]
+    pub stake_escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = UserStakeState::LEN,
+        seeds = [b"user_state", signer.key().as_ref()],
+        bump
+    )]
+    pub user_state: Account<'info, UserStakeState>,
+
     #[account(
         init,
         payer = signer,
         space = UnbondingTicket::LEN,
-        seeds = [b"ticket", signer.key().as_ref()],
+        seeds = [b"ticket", signer.key().as_ref(), user_state.next_ticket_nonce.to_le_bytes().as_ref()],
         bump
     )]
     pub ticket: Account<'info, UnbondingTicket>,
 
+    pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
 pub struct Redeem<'info> {
     #[account(
+        mut,
         seeds = [b"config"], 
         bump = config.bump
     )]
@@ -213,14 +236,21 @@ pub struct Redeem<'info> {
     #[account(mut)]
     pub signer: Signer<'info>,
 
+    // closed by the processor once the remaining amount is fully redeemed
     #[account(
         mut,
-        close = signer, // return rent to user when done
-        seeds = [b"ticket", signer.key().as_ref()],
+        seeds = [b"ticket", signer.key().as_ref(), ticket.nonce.to_le_bytes().as_ref()],
         bump,
     )]
     pub ticket: Account<'info, UnbondingTicket>,
 
+    #[account(
+        mut,
+        seeds = [b"withdrawal_queue"],
+        bump = withdrawal_queue.bump
+    )]
+    pub withdrawal_queue: Account<'info, WithdrawalQueue>,
+
     #[account(
         mut,
         token::mint = config.vault,
@@ -229,6 +259,52 @@ pub struct Redeem<'info> {
     )]
     pub user_vault_token_account: Account<'info, TokenAccount>,
 
+    // the stake tokens already sit in the escrow, so a freeze is enforced on the owner's account here
+    #[account(
+        token::mint = config.mint,
+        constraint = user_mint_token_account.owner == signer.key() @ CustomErrorCode::InvalidTicketOwner,
+        constraint = !user_mint_token_account.is_frozen() @ CustomErrorCode::TokenAccountFrozen
+    )]
+    pub user_mint_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_escrow"],
+        bump,
+        token::mint = config.mint,
+        constraint = stake_escrow_token_account.owner == vault_authority.key() @ CustomErrorCode::InvalidStakeEscrow
+    )]
+    pub stake_escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = mint.key() == config.mint @ CustomErrorCode::InvalidMint
+    )]
+    pub mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+// owner abandons an open ticket and gets the escrowed stake tokens back
+#[derive(Accounts)]
+pub struct CancelUnbond<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        close = signer, // return rent to user when done
+        seeds = [b"ticket", signer.key().as_ref(), ticket.nonce.to_le_bytes().as_ref()],
+        bump,
+    )]
+    pub ticket: Account<'info, UnbondingTicket>,
+
     #[account(
         mut,
         token::mint = config.mint,
@@ -239,6 +315,21 @@ pub struct Redeem<'info> {
 
     #[account(
         mut,
+        seeds = [b"stake_escrow"],
+        bump,
+        token::mint = config.mint,
+        constraint = stake_escrow_token_account.owner == vault_authority.key() @ CustomErrorCode::InvalidStakeEscrow
+    )]
+    pub stake_escrow_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: This is a PDA vault authority, validated by seeds constraint
+    #[account(
+        seeds = [b"vault_authority"],
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
         constraint = mint.key() == config.mint @ CustomErrorCode::InvalidMint
     )]
     pub mint: Account<'info, Mint>,
@@ -246,77 +337,151 @@ pub struct Redeem<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+// settles several matured tickets at once, the tickets are passed as remaining accounts
 #[derive(Accounts)]
-pub struct SetFreezeAuthority<'info> {
+pub struct RedeemMany<'info> {
     #[account(
         mut,
-        constraint = mint.freeze_authority.is_some() @ CustomErrorCode::InvalidFreezeAuthority
+        seeds = [b"config"],
+        bump = config.bump
     )]
-    pub mint: Account<'info, Mint>,
+    pub config: Account<'info, Config>,
 
-    /// CHECK: This is the program data account that contains the update authority
     #[account(
-        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+        seeds = [b"withdrawal_queue"],
+        bump = withdrawal_queue.bump
     )]
-    pub program_data: UncheckedAccount<'info>,
+    pub withdrawal_queue: Account<'info, WithdrawalQueue>,
 
-    /// CHECK: Current freeze authority (could be a keypair or PDA)
-    pub current_freeze_authority: Signer<'info>,
+    #[account(
+        mut,
+        token::mint = config.vault,
+        constraint = vault_token_account.mint == config.vault @ CustomErrorCode::InvalidVaultMint
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
 
-    /// CHECK: This is the PDA that will become the freeze authority
+    /// CHECK: This is a PDA vault authority, validated by seeds and token account owner constraint
     #[account(
-        seeds = [b"freeze_authority"],
-        bump
+        seeds = [b"vault_authority"],
+        bump,
+        constraint = vault_authority.key() == vault_token_account.owner @ CustomErrorCode::InvalidVaultAuthority
     )]
-    pub freeze_authority_pda: UncheckedAccount<'info>,
+    pub vault_authority: UncheckedAccount<'info>,
 
+    #[account(mut)]
     pub signer: Signer<'info>,
-    pub token_program: Program<'info, Token>,
-}
 
-// Helper function to derive the program data address
-fn get_program_data_address(program_id: &Pubkey) -> Pubkey {
-    Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id()).0
+    #[account(
+        mut,
+        token::mint = config.vault,
+        constraint = user_vault_token_account.mint == config.vault @ CustomErrorCode::InvalidVaultMint,
+        constraint = user_vault_token_account.owner == signer.key() @ CustomErrorCode::InvalidTicketOwner
+    )]
+    pub user_vault_token_account: Account<'info, TokenAccount>,
+
+    // the stake tokens already sit in the escrow, so a freeze is enforced on the owner's account here
+    #[account(
+        token::mint = config.mint,
+        constraint = user_mint_token_account.owner == signer.key() @ CustomErrorCode::InvalidTicketOwner,
+        constraint = !user_mint_token_account.is_frozen() @ CustomErrorCode::TokenAccountFrozen
+    )]
+    pub user_mint_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_escrow"],
+        bump,
+        token::mint = config.mint,
+        constraint = stake_escrow_token_account.owner == vault_authority.key() @ CustomErrorCode::InvalidStakeEscrow
+    )]
+    pub stake_escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = mint.key() == config.mint @ CustomErrorCode::InvalidMint
+    )]
+    pub mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateFreezeAdministrators<'info> {
+pub struct InitializeStakeEscrow<'info> {
     #[account(
-        mut,
         seeds = [b"config"],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
 
+    #[account(
+        constraint = mint.key() == config.mint @ CustomErrorCode::InvalidMint
+    )]
+    pub mint: Account<'info, Mint>,
+
+    /// CHECK: This is a PDA that acts as vault authority, validated by seeds constraint
+    /// It also owns the stake escrow so that only this program can release escrowed stake tokens
+    #[account(
+        seeds = [b"vault_authority"],
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        init,
+        payer = signer,
+        seeds = [b"stake_escrow"],
+        bump,
+        token::mint = mint,
+        token::authority = vault_authority,
+    )]
+    pub stake_escrow_token_account: Account<'info, TokenAccount>,
+
     /// CHECK: This is the program data account that contains the update authority
     #[account(
         constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
     )]
     pub program_data: UncheckedAccount<'info>,
 
+    #[account(mut)]
     pub signer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
 }
 
 #[derive(Accounts)]
-pub struct UpdateRewardsAdministrators<'info> {
+pub struct InitializeWithdrawalQueue<'info> {
     #[account(
-        mut,
         seeds = [b"config"],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
 
+    #[account(
+        init,
+        payer = signer,
+        space = WithdrawalQueue::LEN,
+        seeds = [b"withdrawal_queue"],
+        bump
+    )]
+    pub withdrawal_queue: Account<'info, WithdrawalQueue>,
+
     /// CHECK: This is the program data account that contains the update authority
     #[account(
         constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
     )]
     pub program_data: UncheckedAccount<'info>,
 
+    #[account(mut)]
     pub signer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
 }
 
+// admin tops up the vault so queued withdrawals can be settled
 #[derive(Accounts)]
-pub struct FreezeTokenAccount<'info> {
+pub struct FundQueue<'info> {
     #[account(
         seeds = [b"config"],
         bump = config.bump
@@ -324,30 +489,49 @@ pub struct FreezeTokenAccount<'info> {
     pub config: Account<'info, Config>,
 
     #[account(
-        mut,
-        constraint = token_account.mint == mint.key() @ CustomErrorCode::InvalidMint
+        seeds = [b"withdrawal_queue"],
+        bump = withdrawal_queue.bump
     )]
-    pub token_account: Account<'info, TokenAccount>,
+    pub withdrawal_queue: Account<'info, WithdrawalQueue>,
 
     #[account(
-        constraint = mint.freeze_authority == Some(freeze_authority_pda.key()).into() @ CustomErrorCode::InvalidFreezeAuthority
+        mut,
+        token::mint = config.vault,
+        constraint = vault_token_account.mint == config.vault @ CustomErrorCode::InvalidVaultMint,
+        constraint = vault_token_account.owner == vault_authority.key() @ CustomErrorCode::InvalidVaultAuthority
     )]
-    pub mint: Account<'info, Mint>,
+    pub vault_token_account: Account<'info, TokenAccount>,
 
-    /// CHECK: This is the freeze authority PDA
+    /// CHECK: This is a PDA that acts as vault authority, validated by seeds constraint
     #[account(
-        seeds = [b"freeze_authority"],
+        seeds = [b"vault_authority"],
         bump
     )]
-    pub freeze_authority_pda: UncheckedAccount<'info>,
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        token::mint = config.vault,
+        constraint = funder_vault_token_account.mint == config.vault @ CustomErrorCode::InvalidVaultMint,
+        constraint = funder_vault_token_account.owner == signer.key()
+    )]
+    pub funder_vault_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
 
     pub signer: Signer<'info>,
     pub token_program: Program<'info, Token>,
 }
 
+// anyone can settle the ticket at the head of the withdrawal queue, funds go to the ticket owner
 #[derive(Accounts)]
-pub struct ThawTokenAccount<'info> {
+pub struct SettleQueue<'info> {
     #[account(
+        mut,
         seeds = [b"config"],
         bump = config.bump
     )]
@@ -355,59 +539,1118 @@ pub struct ThawTokenAccount<'info> {
 
     #[account(
         mut,
-        constraint = token_account.mint == mint.key() @ CustomErrorCode::InvalidMint
+        seeds = [b"withdrawal_queue"],
+        bump = withdrawal_queue.bump
     )]
-    pub token_account: Account<'info, TokenAccount>,
+    pub withdrawal_queue: Account<'info, WithdrawalQueue>,
 
+    // closed by the processor once the remaining amount is fully settled
     #[account(
-        constraint = mint.freeze_authority == Some(freeze_authority_pda.key()).into() @ CustomErrorCode::InvalidFreezeAuthority
+        mut,
+        seeds = [b"ticket", ticket.owner.as_ref(), ticket.nonce.to_le_bytes().as_ref()],
+        bump,
     )]
-    pub mint: Account<'info, Mint>,
+    pub ticket: Account<'info, UnbondingTicket>,
 
-    /// CHECK: This is the freeze authority PDA
+    /// CHECK: Ticket owner receiving the ticket rent once it is closed, validated against the ticket
     #[account(
-        seeds = [b"freeze_authority"],
-        bump
+        mut,
+        constraint = owner.key() == ticket.owner @ CustomErrorCode::InvalidTicketOwner
     )]
-    pub freeze_authority_pda: UncheckedAccount<'info>,
+    pub owner: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        token::mint = config.vault,
+        constraint = vault_token_account.mint == config.vault @ CustomErrorCode::InvalidVaultMint
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: This is a PDA vault authority, validated by seeds and token account owner constraint
+    #[account(
+        seeds = [b"vault_authority"],
+        bump,
+        constraint = vault_authority.key() == vault_token_account.owner @ CustomErrorCode::InvalidVaultAuthority
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        token::mint = config.vault,
+        constraint = owner_vault_token_account.mint == config.vault @ CustomErrorCode::InvalidVaultMint,
+        constraint = owner_vault_token_account.owner == ticket.owner @ CustomErrorCode::InvalidTicketOwner
+    )]
+    pub owner_vault_token_account: Account<'info, TokenAccount>,
+
+    // the stake tokens already sit in the escrow, so a freeze is enforced on the owner's account here
+    #[account(
+        token::mint = config.mint,
+        constraint = owner_mint_token_account.owner == ticket.owner @ CustomErrorCode::InvalidTicketOwner,
+        constraint = !owner_mint_token_account.is_frozen() @ CustomErrorCode::TokenAccountFrozen
+    )]
+    pub owner_mint_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_escrow"],
+        bump,
+        token::mint = config.mint,
+        constraint = stake_escrow_token_account.owner == vault_authority.key() @ CustomErrorCode::InvalidStakeEscrow
+    )]
+    pub stake_escrow_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = mint.key() == config.mint @ CustomErrorCode::InvalidMint
+    )]
+    pub mint: Account<'info, Mint>,
 
     pub signer: Signer<'info>,
     pub token_program: Program<'info, Token>,
 }
 
-// admin posts an epoch root
+// user burns stake tokens and is paid out of the vault right away, minus the exit fee
 #[derive(Accounts)]
-#[instruction(index: u64)]
-pub struct CreateRewardsEpoch<'info> {
+pub struct InstantUnbond<'info> {
     #[account(
-        seeds = [b"config"], 
+        mut,
+        seeds = [b"config"],
         bump = config.bump
     )]
     pub config: Account<'info, Config>,
 
-    #[account(mut)]
-    pub admin: Signer<'info>,
     #[account(
-        init,
-        payer=admin,
-        space=RewardsEpoch::LEN,
-        seeds=[b"epoch", index.to_le_bytes().as_ref()],
-        bump
-    )]
+        mut,
+        token::mint = config.vault,
+        constraint = vault_token_account.mint == config.vault @ CustomErrorCode::InvalidVaultMint
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: This is a PDA vault authority, validated by seeds and token account owner constraint
+    #[account(
+        seeds = [b"vault_authority"],
+        bump,
+        constraint = vault_authority.key() == vault_token_account.owner @ CustomErrorCode::InvalidVaultAuthority
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    #[account(
+        seeds = [b"withdrawal_queue"],
+        bump = withdrawal_queue.bump
+    )]
+    pub withdrawal_queue: Account<'info, WithdrawalQueue>,
+
+    #[account(
+        mut,
+        constraint = treasury_token_account.key() == config.treasury @ CustomErrorCode::InvalidTreasury
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    pub signer: Signer<'info>,
+
+    #[account(
+        mut,
+        token::mint = config.mint,
+        constraint = user_mint_token_account.mint == config.mint @ CustomErrorCode::InvalidMint,
+        constraint = user_mint_token_account.owner == signer.key() @ CustomErrorCode::InvalidMintAuthority
+    )]
+    pub user_mint_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        token::mint = config.vault,
+        constraint = user_vault_token_account.mint == config.vault @ CustomErrorCode::InvalidVaultMint,
+        constraint = user_vault_token_account.owner == signer.key()
+    )]
+    pub user_vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        constraint = mint.key() == config.mint @ CustomErrorCode::InvalidMint
+    )]
+    pub mint: Account<'info, Mint>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SetConfigChangeDelay<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeConfigChange<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    // Only one change can be pending at a time
+    #[account(
+        init,
+        payer = signer,
+        space = PendingConfigChange::LEN,
+        seeds = [b"pending_config_change"],
+        bump
+    )]
+    pub pending_config_change: Account<'info, PendingConfigChange>,
+
+    // Required when the change sets instant unbond parameters
+    #[account(
+        token::mint = config.vault,
+        constraint = treasury_token_account.mint == config.vault @ CustomErrorCode::InvalidTreasury
+    )]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteConfigChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        close = signer,
+        seeds = [b"pending_config_change"],
+        bump = pending_config_change.bump
+    )]
+    pub pending_config_change: Account<'info, PendingConfigChange>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelConfigChange<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        close = signer,
+        seeds = [b"pending_config_change"],
+        bump = pending_config_change.bump
+    )]
+    pub pending_config_change: Account<'info, PendingConfigChange>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateInstantUnbondConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        token::mint = config.vault,
+        constraint = treasury_token_account.mint == config.vault @ CustomErrorCode::InvalidTreasury
+    )]
+    pub treasury_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetExchangeRateMode<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        constraint = mint.key() == config.mint @ CustomErrorCode::InvalidMint
+    )]
+    pub mint: Account<'info, Mint>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    pub signer: Signer<'info>,
+}
+
+// admin raises total assets to pass yield on to every stake token holder
+#[derive(Accounts)]
+pub struct ReportYield<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        constraint = mint.key() == config.mint @ CustomErrorCode::InvalidMint
+    )]
+    pub mint: Account<'info, Mint>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetFundedRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    pub signer: Signer<'info>,
+}
+
+// user claims their leaf of an indexed epoch by flipping its bit, no claim record is created
+#[derive(Accounts)]
+pub struct ClaimIndexedRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    pub user: Signer<'info>,
+    #[account(mut)]
+    pub epoch: Account<'info, RewardsEpoch>,
+    #[account(
+        mut,
+        seeds = [b"claim_bitmap", epoch.key().as_ref()],
+        bump
+    )]
+    pub claim_bitmap: Account<'info, ClaimBitmap>,
+
+    #[account(
+        mut,
+        constraint = mint.key() == config.mint @ CustomErrorCode::InvalidMint
+    )]
+    pub mint: Account<'info, Mint>,
+
+    /// CHECK: This is a PDA that acts as mint authority, validated by seeds constraint
+    #[account(
+        seeds = [b"mint_authority"],
+        bump,
+        constraint = mint_authority.key() == mint.mint_authority.unwrap() @ CustomErrorCode::InvalidMintAuthority
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = user_stake_token_account.mint == mint.key() @ CustomErrorCode::InvalidMint,
+        constraint = user_stake_token_account.owner == user.key()
+    )]
+    pub user_stake_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
+// user claims the difference between their lifetime total in a cumulative epoch and what they
+// were already paid
+#[derive(Accounts)]
+pub struct ClaimCumulativeRewards<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(mut)]
+    pub epoch: Account<'info, RewardsEpoch>,
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = RewardsClaimState::LEN,
+        seeds = [b"claim_state", user.key().as_ref()],
+        bump
+    )]
+    pub claim_state: Account<'info, RewardsClaimState>,
+
+    #[account(
+        mut,
+        constraint = mint.key() == config.mint @ CustomErrorCode::InvalidMint
+    )]
+    pub mint: Account<'info, Mint>,
+
+    /// CHECK: This is a PDA that acts as mint authority, validated by seeds constraint
+    #[account(
+        seeds = [b"mint_authority"],
+        bump,
+        constraint = mint_authority.key() == mint.mint_authority.unwrap() @ CustomErrorCode::InvalidMintAuthority
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = user_stake_token_account.mint == mint.key() @ CustomErrorCode::InvalidMint,
+        constraint = user_stake_token_account.owner == user.key()
+    )]
+    pub user_stake_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+// user claims several epochs at once; each claim comes with its epoch and claim record
+// as a pair of writable remaining accounts
+#[derive(Accounts)]
+pub struct ClaimRewardsBatch<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = mint.key() == config.mint @ CustomErrorCode::InvalidMint
+    )]
+    pub mint: Account<'info, Mint>,
+
+    /// CHECK: This is a PDA that acts as mint authority, validated by seeds constraint
+    #[account(
+        seeds = [b"mint_authority"],
+        bump,
+        constraint = mint_authority.key() == mint.mint_authority.unwrap() @ CustomErrorCode::InvalidMintAuthority
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = user_stake_token_account.mint == mint.key() @ CustomErrorCode::InvalidMint,
+        constraint = user_stake_token_account.owner == user.key()
+    )]
+    pub user_stake_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+// admin grows an epoch created before claims were counted and records what was already claimed
+#[derive(Accounts)]
+pub struct MigrateRewardsEpoch<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: Deserialized by the processor, which zero-extends the older, shorter layout
+    #[account(
+        mut,
+        owner = crate::ID
+    )]
+    pub epoch: UncheckedAccount<'info>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateEpochRewardsCap<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRewardsVestingDuration<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetNextEpochIndex<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRewardsApprovalThreshold<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetRewardsHashVersion<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    pub signer: Signer<'info>,
+}
+
+// admin grows the config account to the current layout after fields were appended
+#[derive(Accounts)]
+pub struct MigrateConfig<'info> {
+    /// CHECK: Deserialized by the processor, which zero-extends older, shorter layouts
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump,
+        owner = crate::ID
+    )]
+    pub config: UncheckedAccount<'info>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+// owner moves a ticket opened before tickets were keyed by nonce onto a nonce-keyed ticket
+#[derive(Accounts)]
+pub struct MigrateTicket<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    /// CHECK: Older layouts cannot be deserialized as an UnbondingTicket; the discriminator
+    /// and ticket owner are validated in the processor
+    #[account(
+        mut,
+        owner = crate::ID,
+        seeds = [b"ticket", signer.key().as_ref()],
+        bump
+    )]
+    pub legacy_ticket: UncheckedAccount<'info>,
+
+    #[account(
+        init_if_needed,
+        payer = signer,
+        space = UserStakeState::LEN,
+        seeds = [b"user_state", signer.key().as_ref()],
+        bump
+    )]
+    pub user_state: Account<'info, UserStakeState>,
+
+    #[account(
+        init,
+        payer = signer,
+        space = UnbondingTicket::LEN,
+        seeds = [b"ticket", signer.key().as_ref(), user_state.next_ticket_nonce.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub ticket: Account<'info, UnbondingTicket>,
+
+    #[account(
+        mut,
+        token::mint = config.mint,
+        constraint = user_mint_token_account.mint == config.mint @ CustomErrorCode::InvalidMint,
+        constraint = user_mint_token_account.owner == signer.key() @ CustomErrorCode::InvalidTicketOwner
+    )]
+    pub user_mint_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"stake_escrow"],
+        bump,
+        token::mint = config.mint,
+    )]
+    pub stake_escrow_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SetFreezeAuthority<'info> {
+    #[account(
+        mut,
+        constraint = mint.freeze_authority.is_some() @ CustomErrorCode::InvalidFreezeAuthority
+    )]
+    pub mint: Account<'info, Mint>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    /// CHECK: Current freeze authority (could be a keypair or PDA)
+    pub current_freeze_authority: Signer<'info>,
+
+    /// CHECK: This is the PDA that will become the freeze authority
+    #[account(
+        seeds = [b"freeze_authority"],
+        bump
+    )]
+    pub freeze_authority_pda: UncheckedAccount<'info>,
+
+    pub signer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+// Helper function to derive the program data address
+fn get_program_data_address(program_id: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[program_id.as_ref()], &bpf_loader_upgradeable::id()).0
+}
+
+#[derive(Accounts)]
+pub struct ProposeAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAdmin<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    // Must be the pending admin; checked by the processor
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePauseGuardians<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateFreezeAdministrators<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateRewardsAdministrators<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    /// CHECK: This is the program data account that contains the update authority
+    #[account(
+        constraint = program_data.key() == get_program_data_address(&crate::id()) @ CustomErrorCode::InvalidProgramData
+    )]
+    pub program_data: UncheckedAccount<'info>,
+
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct FreezeTokenAccount<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        constraint = token_account.mint == mint.key() @ CustomErrorCode::InvalidMint
+    )]
+    pub token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        constraint = mint.freeze_authority == Some(freeze_authority_pda.key()).into() @ CustomErrorCode::InvalidFreezeAuthority
+    )]
+    pub mint: Account<'info, Mint>,
+
+    /// CHECK: This is the freeze authority PDA
+    #[account(
+        seeds = [b"freeze_authority"],
+        bump
+    )]
+    pub freeze_authority_pda: UncheckedAccount<'info>,
+
+    pub signer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ThawTokenAccount<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(
+        mut,
+        constraint = token_account.mint == mint.key() @ CustomErrorCode::InvalidMint
+    )]
+    pub token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        constraint = mint.freeze_authority == Some(freeze_authority_pda.key()).into() @ CustomErrorCode::InvalidFreezeAuthority
+    )]
+    pub mint: Account<'info, Mint>,
+
+    /// CHECK: This is the freeze authority PDA
+    #[account(
+        seeds = [b"freeze_authority"],
+        bump
+    )]
+    pub freeze_authority_pda: UncheckedAccount<'info>,
+
+    pub signer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+// admin posts an epoch root
+#[derive(Accounts)]
+#[instruction(index: u64)]
+pub struct CreateRewardsEpoch<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"], 
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        payer=admin,
+        space=RewardsEpoch::LEN,
+        seeds=[b"epoch", index.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub epoch: Account<'info, RewardsEpoch>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: This is a PDA that acts as vault authority, validated by seeds constraint
+    #[account(
+        seeds = [b"vault_authority"],
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    // The accounts below are only needed when config.funded_rewards is set
+    #[account(
+        mut,
+        token::mint = config.vault,
+        constraint = vault_token_account.owner == vault_authority.key() @ CustomErrorCode::InvalidVaultAuthority
+    )]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = config.vault,
+        constraint = admin_vault_token_account.owner == admin.key()
+    )]
+    pub admin_vault_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+// admin posts an epoch root whose leaves carry their index, along with the claim bitmap
+#[derive(Accounts)]
+#[instruction(index: u64, merkle_root: [u8; 32], total: u64, leaf_count: u64)]
+pub struct CreateIndexedRewardsEpoch<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        init,
+        payer = admin,
+        space = RewardsEpoch::LEN,
+        seeds = [b"epoch", index.to_le_bytes().as_ref()],
+        bump
+    )]
+    pub epoch: Account<'info, RewardsEpoch>,
+    #[account(
+        init,
+        payer = admin,
+        space = ClaimBitmap::space(leaf_count),
+        seeds = [b"claim_bitmap", epoch.key().as_ref()],
+        bump
+    )]
+    pub claim_bitmap: Account<'info, ClaimBitmap>,
+    pub system_program: Program<'info, System>,
+
+    /// CHECK: This is a PDA that acts as vault authority, validated by seeds constraint
+    #[account(
+        seeds = [b"vault_authority"],
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    // The accounts below are only needed when config.funded_rewards is set
+    #[account(
+        mut,
+        token::mint = config.vault,
+        constraint = vault_token_account.owner == vault_authority.key() @ CustomErrorCode::InvalidVaultAuthority
+    )]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = config.vault,
+        constraint = admin_vault_token_account.owner == admin.key()
+    )]
+    pub admin_vault_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+// rewards admin closes an expired epoch or revokes a draft, reclaiming its rent
+#[derive(Accounts)]
+pub struct CloseRewardsEpoch<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        close = admin
+    )]
+    pub epoch: Account<'info, RewardsEpoch>,
+    // only needed for indexed epochs
+    #[account(
+        mut,
+        seeds = [b"claim_bitmap", epoch.key().as_ref()],
+        bump
+    )]
+    pub claim_bitmap: Option<Account<'info, ClaimBitmap>>,
+    // closed along with the epoch when it was approved
+    #[account(
+        mut,
+        seeds = [b"epoch_approvals", epoch.key().as_ref()],
+        bump = approvals.bump
+    )]
+    pub approvals: Option<Account<'info, RewardsEpochApprovals>>,
+}
+
+// rewards admin corrects a draft epoch
+#[derive(Accounts)]
+pub struct UpdateRewardsEpoch<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    pub admin: Signer<'info>,
+    #[account(mut)]
+    pub epoch: Account<'info, RewardsEpoch>,
+}
+
+// rewards admin approves a draft epoch's current root and total
+#[derive(Accounts)]
+pub struct ApproveRewardsEpoch<'info> {
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
     pub epoch: Account<'info, RewardsEpoch>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        space = RewardsEpochApprovals::LEN,
+        seeds = [b"epoch_approvals", epoch.key().as_ref()],
+        bump
+    )]
+    pub approvals: Account<'info, RewardsEpochApprovals>,
     pub system_program: Program<'info, System>,
 }
 
+// rewards admin opens a draft epoch for claims, funding it
+#[derive(Accounts)]
+pub struct PublishRewardsEpoch<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump
+    )]
+    pub config: Account<'info, Config>,
+
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(mut)]
+    pub epoch: Account<'info, RewardsEpoch>,
+    // only needed when config.rewards_approval_threshold is above zero
+    #[account(
+        seeds = [b"epoch_approvals", epoch.key().as_ref()],
+        bump = approvals.bump
+    )]
+    pub approvals: Option<Account<'info, RewardsEpochApprovals>>,
+
+    /// CHECK: This is a PDA that acts as vault authority, validated by seeds constraint
+    #[account(
+        seeds = [b"vault_authority"],
+        bump
+    )]
+    pub vault_authority: UncheckedAccount<'info>,
+
+    // The accounts below are only needed when config.funded_rewards is set
+    #[account(
+        mut,
+        token::mint = config.vault,
+        constraint = vault_token_account.owner == vault_authority.key() @ CustomErrorCode::InvalidVaultAuthority
+    )]
+    pub vault_token_account: Option<Account<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        token::mint = config.vault,
+        constraint = admin_vault_token_account.owner == admin.key()
+    )]
+    pub admin_vault_token_account: Option<Account<'info, TokenAccount>>,
+
+    pub token_program: Option<Program<'info, Token>>,
+}
+
+// user mints the unlocked part of a vesting claim
+#[derive(Accounts)]
+pub struct WithdrawVested<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(mut)]
+    pub user: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"vesting", vesting.epoch.as_ref(), user.key().as_ref()],
+        bump = vesting.bump,
+        has_one = user
+    )]
+    pub vesting: Account<'info, VestingAccount>,
+
+    #[account(
+        mut,
+        constraint = mint.key() == config.mint @ CustomErrorCode::InvalidMint
+    )]
+    pub mint: Account<'info, Mint>,
+
+    /// CHECK: This is a PDA that acts as mint authority, validated by seeds constraint
+    #[account(
+        seeds = [b"mint_authority"],
+        bump,
+        constraint = mint_authority.key() == mint.mint_authority.unwrap() @ CustomErrorCode::InvalidMintAuthority
+    )]
+    pub mint_authority: UncheckedAccount<'info>,
+
+    #[account(
+        mut,
+        constraint = user_stake_token_account.mint == mint.key() @ CustomErrorCode::InvalidMint,
+        constraint = user_stake_token_account.owner == user.key()
+    )]
+    pub user_stake_token_account: Account<'info, TokenAccount>,
+    pub token_program: Program<'info, Token>,
+}
+
 // user claims this epoch’s amount
 #[derive(Accounts)]
 pub struct ClaimRewards<'info> {
     #[account(
-        seeds = [b"config"], 
+        mut,
+        seeds = [b"config"],
         bump = config.bump,
     )]
     pub config: Account<'info, Config>,
     #[account(mut)]
     pub user: Signer<'info>,
+    #[account(mut)]
     pub epoch: Account<'info, RewardsEpoch>,
     #[account(
         init,
@@ -417,6 +1660,9 @@ pub struct ClaimRewards<'info> {
         bump
     )]
     pub claim_record: Account<'info, ClaimRecord>,
+    /// CHECK: Vesting PDA ([b"vesting", epoch, user]) created by the instruction, only needed for vesting epochs
+    #[account(mut)]
+    pub vesting: Option<UncheckedAccount<'info>>,
 
     #[account(
         mut,
@@ -0,0 +1,116 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+
+// Deserialize an account written under an older, shorter layout. The data is zero-extended to
+// `len` bytes first, so any fields appended since then read back as their zero values.
+pub fn load_zero_extended<T: AccountDeserialize>(info: &AccountInfo, len: usize) -> Result<T> {
+    let mut data = info.try_borrow_data()?.to_vec();
+    if data.len() < len {
+        data.resize(len, 0);
+    }
+    T::try_deserialize(&mut data.as_slice())
+}
+
+// Close a program-owned account that is not held as an `Account<T>`, returning its rent
+pub fn close_account<'info>(info: &AccountInfo<'info>, destination: &AccountInfo<'info>) -> Result<()> {
+    let lamports = info.lamports();
+    **destination.try_borrow_mut_lamports()? = destination
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    **info.try_borrow_mut_lamports()? = 0;
+
+    info.assign(&system_program::ID);
+    info.resize(0)?;
+    Ok(())
+}
+
+// Grow a program-owned account to `len` bytes, topping up rent from `payer`. New bytes are zeroed.
+pub fn resize_account<'info>(
+    info: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    len: usize,
+) -> Result<()> {
+    if info.data_len() >= len {
+        return Ok(());
+    }
+    let required = Rent::get()?.minimum_balance(len);
+    let lamports = info.lamports();
+    if required > lamports {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            required - lamports,
+        )?;
+    }
+    info.resize(len)?;
+    Ok(())
+}
+
+// Create a program-owned PDA the way Anchor's `init` does. Lamports already sitting at the address
+// are kept, so pre-funding it cannot block creation.
+pub fn create_pda_account<'info>(
+    info: &AccountInfo<'info>,
+    payer: &Signer<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+    seeds: &[&[u8]],
+) -> Result<()> {
+    let required = Rent::get()?.minimum_balance(space);
+    let lamports = info.lamports();
+    let signer = &[seeds];
+    if lamports == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.to_account_info(),
+                system_program::CreateAccount {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+                signer,
+            ),
+            required,
+            space as u64,
+            &crate::ID,
+        );
+    }
+
+    if required > lamports {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.to_account_info(),
+                system_program::Transfer {
+                    from: payer.to_account_info(),
+                    to: info.clone(),
+                },
+            ),
+            required - lamports,
+        )?;
+    }
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::Allocate {
+                account_to_allocate: info.clone(),
+            },
+            signer,
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.to_account_info(),
+            system_program::Assign {
+                account_to_assign: info.clone(),
+            },
+            signer,
+        ),
+        &crate::ID,
+    )
+}
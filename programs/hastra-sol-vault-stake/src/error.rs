@@ -60,4 +60,100 @@ pub enum CustomErrorCode {
     ProtocolPaused = 27,
     #[msg("Invalid bonding period")]
     InvalidBondingPeriod = 28,
+    #[msg("Unbonding ticket must be migrated before it can be used")]
+    TicketNotMigrated = 29,
+    #[msg("The same unbonding ticket was provided more than once")]
+    DuplicateTicket = 30,
+    #[msg("Invalid stake escrow account")]
+    InvalidStakeEscrow = 31,
+    #[msg("No unbonding tickets provided")]
+    NoTicketsProvided = 32,
+    #[msg("Unbonding ticket is queued for withdrawal")]
+    TicketQueued = 33,
+    #[msg("Unbonding ticket is not at the head of the withdrawal queue")]
+    NotQueueHead = 34,
+    #[msg("Withdrawal queue must be settled first")]
+    WithdrawalQueueNotEmpty = 35,
+    #[msg("Instant unbond is disabled")]
+    InstantUnbondDisabled = 36,
+    #[msg("Instant unbond cap for this epoch exceeded")]
+    InstantUnbondCapExceeded = 37,
+    #[msg("Invalid fee")]
+    InvalidFee = 38,
+    #[msg("Invalid treasury token account")]
+    InvalidTreasury = 39,
+    #[msg("Invalid exchange rate")]
+    InvalidExchangeRate = 40,
+    #[msg("Exchange-rate mode is not enabled")]
+    ExchangeRateModeDisabled = 41,
+    #[msg("Funded rewards epochs require the vault and admin token accounts")]
+    RewardsFundingRequired = 42,
+    #[msg("Claim exceeds the rewards epoch total")]
+    RewardsEpochTotalExceeded = 43,
+    #[msg("Account already migrated")]
+    AccountAlreadyMigrated = 44,
+    #[msg("The same rewards epoch was provided more than once")]
+    DuplicateRewardsEpoch = 45,
+    #[msg("Expected an epoch and a claim record account for every claim")]
+    InvalidClaimAccounts = 46,
+    #[msg("Rewards epoch does not support this kind of claim")]
+    WrongRewardsEpochKind = 47,
+    #[msg("Nothing left to claim")]
+    NothingToClaim = 48,
+    #[msg("Invalid leaf index")]
+    InvalidLeafIndex = 49,
+    #[msg("Invalid leaf count")]
+    InvalidLeafCount = 50,
+    #[msg("Invalid merkle hash version")]
+    InvalidHashVersion = 51,
+    #[msg("Invalid claim window")]
+    InvalidClaimWindow = 52,
+    #[msg("Claim window has not opened yet")]
+    ClaimWindowNotOpen = 53,
+    #[msg("Claim window has closed")]
+    ClaimWindowClosed = 54,
+    #[msg("Rewards epoch has not expired")]
+    RewardsEpochNotExpired = 55,
+    #[msg("Rewards epoch is already published")]
+    RewardsEpochAlreadyPublished = 56,
+    #[msg("Rewards epoch is not published")]
+    RewardsEpochNotPublished = 57,
+    #[msg("Invalid approval threshold")]
+    InvalidApprovalThreshold = 58,
+    #[msg("Rewards epochs must be created as drafts and approved")]
+    RewardsApprovalRequired = 59,
+    #[msg("Rewards epoch already approved by this administrator")]
+    AlreadyApproved = 60,
+    #[msg("Not enough approvals to publish the rewards epoch")]
+    InsufficientApprovals = 61,
+    #[msg("Rewards epoch index is not the next epoch index")]
+    InvalidEpochIndex = 62,
+    #[msg("Rewards epoch exceeds the rewards cap")]
+    EpochRewardsCapExceeded = 63,
+    #[msg("The vault token account is required to check the rewards cap")]
+    VaultTokenAccountRequired = 64,
+    #[msg("Invalid rewards cap")]
+    InvalidRewardsCap = 65,
+    #[msg("Invalid vesting duration")]
+    InvalidVestingDuration = 66,
+    #[msg("Unauthorized admin")]
+    InvalidAdmin = 67,
+    #[msg("Signer is not the pending admin")]
+    InvalidPendingAdmin = 68,
+    #[msg("Config changes are timelocked; use propose_config_change")]
+    ConfigChangeTimelocked = 69,
+    #[msg("Config change timelock has not passed")]
+    ConfigChangeNotReady = 70,
+    #[msg("Invalid config change delay")]
+    InvalidConfigChangeDelay = 71,
+    #[msg("Config change changes nothing")]
+    EmptyConfigChange = 72,
+    #[msg("Invalid pause flags")]
+    InvalidPauseFlags = 73,
+    #[msg("Stake token account is frozen")]
+    TokenAccountFrozen = 74,
+    #[msg("Cumulative rewards claims exceed the cumulative rewards total")]
+    CumulativeRewardsExceeded = 75,
+    #[msg("No stake tokens are outstanding")]
+    NoStakeTokenSupply = 76,
 }
@@ -1,3 +1,4 @@
+use crate::state::ConfigChange;
 use anchor_lang::prelude::*;
 
 #[event]
@@ -14,6 +15,17 @@ pub struct UnbondEvent {
     pub amount: u64,
     pub mint: Pubkey,
     pub vault: Pubkey,
+    pub nonce: u64,
+    pub unlock_ts: i64,
+}
+
+#[event]
+pub struct UnbondCancelled {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+    pub nonce: u64,
 }
 
 #[event]
@@ -22,6 +34,37 @@ pub struct RedeemEvent {
     pub amount: u64,
     pub mint: Pubkey,
     pub vault: Pubkey,
+    pub nonce: u64,
+    pub remaining: u64, // stake tokens left on the ticket, zero once it is closed
+}
+
+#[event]
+pub struct WithdrawalEnqueued {
+    pub user: Pubkey,
+    pub nonce: u64,
+    pub position: u64,
+    pub amount: u64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct WithdrawalSettled {
+    pub user: Pubkey,
+    pub nonce: u64,
+    pub position: u64,
+    pub amount: u64,
+    pub remaining: u64, // still owed to this ticket, it stays at the head until zero
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct WithdrawalQueueFunded {
+    pub admin: Pubkey,
+    pub amount: u64,
+    pub queued_amount: u64,
+    pub vault: Pubkey,
 }
 
 #[event]
@@ -31,8 +74,11 @@ pub struct RewardsClaimed {
     pub amount: u64,
     pub mint: Pubkey,
     pub vault: Pubkey,
+    pub remaining: u64, // rewards in the epoch still unclaimed
 }
 
+/// The new period only applies to tickets opened after the update. Existing tickets keep the
+/// `unlock_ts` snapshotted when they were opened.
 #[event]
 pub struct UnbondingPeriodUpdated {
     pub admin: Pubkey,
@@ -41,3 +87,373 @@ pub struct UnbondingPeriodUpdated {
     pub mint: Pubkey,
     pub vault: Pubkey,
 }
+
+#[event]
+pub struct InstantUnbondEvent {
+    pub user: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+    pub treasury: Pubkey,
+    pub epoch: u64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct YieldReported {
+    pub admin: Pubkey,
+    pub amount: u64,
+    pub total_assets: u64,
+    pub supply: u64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct RewardsEpochFunded {
+    pub admin: Pubkey,
+    pub epoch: u64,
+    pub amount: u64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct RewardsEpochProposed {
+    pub admin: Pubkey,
+    pub epoch: u64,
+    pub merkle_root: [u8; 32],
+    pub total: u64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct RewardsEpochApproved {
+    pub admin: Pubkey,
+    pub epoch: u64,
+    pub approvals: u8,
+    pub threshold: u8,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct RewardsEpochPublished {
+    pub admin: Pubkey,
+    pub epoch: u64,
+    pub total: u64,
+    pub approvals: u8,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct RewardsVestingStarted {
+    pub user: Pubkey,
+    pub epoch: u64,
+    pub amount: u64,
+    pub start_ts: i64,
+    pub end_ts: i64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct VestedRewardsReleased {
+    pub user: Pubkey,
+    pub epoch: u64,
+    pub amount: u64,
+    pub released: u64,
+    pub total: u64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+/// Emitted when an expired rewards epoch is closed. `unclaimed` is what was never claimed before
+/// the window closed; for cumulative epochs it is relative to the lifetime `total`.
+#[event]
+pub struct RewardsEpochClosed {
+    pub admin: Pubkey,
+    pub epoch: u64,
+    pub total: u64,
+    pub claimed: u64,
+    pub unclaimed: u64,
+    pub cumulative: bool,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct AdminProposed {
+    pub admin: Pubkey,
+    pub pending_admin: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct AdminTransferred {
+    pub old_admin: Pubkey,
+    pub new_admin: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+/// `current` holds the values in effect when the change was proposed, for the fields it sets, so
+/// stakers can see what is about to change while the timelock runs.
+#[event]
+pub struct ConfigChangeProposed {
+    pub admin: Pubkey,
+    pub eta: i64,
+    pub change: ConfigChange,
+    pub current: ConfigChange,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct ConfigChangeExecuted {
+    pub admin: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct ConfigChangeCancelled {
+    pub admin: Pubkey,
+    pub eta: i64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+/// Emitted whenever the pause flags change. `paused` and `resumed` hold the PAUSE_* bits that
+/// were switched on and off; `authority` is the admin or pause guardian and `reason` its
+/// off-chain reason code (0 = unspecified).
+#[event]
+pub struct PauseFlagsUpdated {
+    pub authority: Pubkey,
+    pub old_flags: u8,
+    pub new_flags: u8,
+    pub paused: u8,
+    pub resumed: u8,
+    pub reason: u8,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct ProtocolInitialized {
+    pub admin: Pubkey,
+    pub unbonding_period: i64,
+    pub freeze_administrators: Vec<Pubkey>,
+    pub rewards_administrators: Vec<Pubkey>,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct FreezeAdministratorsUpdated {
+    pub admin: Pubkey,
+    pub old_administrators: Vec<Pubkey>,
+    pub new_administrators: Vec<Pubkey>,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct RewardsAdministratorsUpdated {
+    pub admin: Pubkey,
+    pub old_administrators: Vec<Pubkey>,
+    pub new_administrators: Vec<Pubkey>,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct PauseGuardiansUpdated {
+    pub admin: Pubkey,
+    pub old_guardians: Vec<Pubkey>,
+    pub new_guardians: Vec<Pubkey>,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct TokenAccountFrozen {
+    pub admin: Pubkey,
+    pub token_account: Pubkey,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct TokenAccountThawed {
+    pub admin: Pubkey,
+    pub token_account: Pubkey,
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct RewardsEpochCreated {
+    pub admin: Pubkey,
+    pub epoch: u64,
+    pub merkle_root: [u8; 32],
+    pub total: u64,
+    pub draft: bool,
+    pub cumulative: bool,
+    pub leaf_count: u64,
+    pub hash_version: u8,
+    pub claim_start_ts: i64,
+    pub claim_end_ts: i64,
+    pub vesting_duration: i64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct RewardsEpochRevoked {
+    pub admin: Pubkey,
+    pub epoch: u64,
+    pub merkle_root: [u8; 32],
+    pub total: u64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct InstantUnbondConfigUpdated {
+    pub admin: Pubkey,
+    pub old_fee_bps: u16,
+    pub new_fee_bps: u16,
+    pub old_epoch_cap: u64,
+    pub new_epoch_cap: u64,
+    pub old_treasury: Pubkey,
+    pub new_treasury: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct ExchangeRateModeUpdated {
+    pub admin: Pubkey,
+    pub enabled: bool,
+    pub total_assets: u64,
+    pub supply: u64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct EpochRewardsCapUpdated {
+    pub admin: Pubkey,
+    pub old_max_bps: u16,
+    pub new_max_bps: u16,
+    pub old_max_amount: u64,
+    pub new_max_amount: u64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct FundedRewardsUpdated {
+    pub admin: Pubkey,
+    pub old_value: bool,
+    pub new_value: bool,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct RewardsHashVersionUpdated {
+    pub admin: Pubkey,
+    pub old_value: u8,
+    pub new_value: u8,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct RewardsApprovalThresholdUpdated {
+    pub admin: Pubkey,
+    pub old_value: u8,
+    pub new_value: u8,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct NextEpochIndexUpdated {
+    pub admin: Pubkey,
+    pub old_value: u64,
+    pub new_value: u64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct RewardsVestingDurationUpdated {
+    pub admin: Pubkey,
+    pub old_value: i64,
+    pub new_value: i64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct ConfigChangeDelayUpdated {
+    pub admin: Pubkey,
+    pub old_value: i64,
+    pub new_value: i64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct StakeEscrowInitialized {
+    pub admin: Pubkey,
+    pub stake_escrow: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct WithdrawalQueueInitialized {
+    pub admin: Pubkey,
+    pub withdrawal_queue: Pubkey,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct ConfigMigrated {
+    pub admin: Pubkey,
+    pub old_len: u64,
+    pub new_len: u64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct RewardsEpochMigrated {
+    pub admin: Pubkey,
+    pub epoch: u64,
+    pub old_len: u64,
+    pub new_len: u64,
+    pub claimed: u64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
+
+#[event]
+pub struct TicketMigrated {
+    pub user: Pubkey,
+    pub legacy_ticket: Pubkey,
+    pub ticket: Pubkey,
+    pub nonce: u64,
+    pub escrowed_amount: u64,
+    pub unlock_ts: i64,
+    pub mint: Pubkey,
+    pub vault: Pubkey,
+}
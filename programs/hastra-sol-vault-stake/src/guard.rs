@@ -1,4 +1,5 @@
 use crate::error::CustomErrorCode;
+use crate::state::Config;
 use anchor_lang::prelude::*;
 
 #[allow(deprecated)]
@@ -38,3 +39,31 @@ pub fn validate_program_update_authority(
 
     Ok(())
 }
+
+// Admin-only instructions accept the protocol admin once one is set. Until then, the upgrade
+// authority bootstraps administration, e.g. by proposing the first admin.
+pub fn validate_admin(
+    config: &Config,
+    program_data_account: &UncheckedAccount,
+    authority: &Signer,
+) -> Result<()> {
+    if config.admin == Pubkey::default() {
+        return validate_program_update_authority(program_data_account, authority);
+    }
+    require!(authority.key() == config.admin, CustomErrorCode::InvalidAdmin);
+    Ok(())
+}
+
+// Pause guardians may pause further operations, but only the admin can resume any.
+pub fn validate_pause_authority(
+    config: &Config,
+    program_data_account: &UncheckedAccount,
+    authority: &Signer,
+    flags: u8,
+) -> Result<()> {
+    let only_pauses = flags & config.pause_flags == config.pause_flags;
+    if only_pauses && config.pause_guardians.contains(&authority.key()) {
+        return Ok(());
+    }
+    validate_admin(config, program_data_account, authority)
+}
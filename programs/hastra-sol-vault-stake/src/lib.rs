@@ -1,4 +1,5 @@
 pub mod account_structs;
+mod account_utils;
 /// # hastra sol vault stake - Token Staking System
 ///
 /// ## Business Process Flow
@@ -16,16 +17,18 @@ pub mod account_structs;
 ///
 /// 3. Withdrawal Flow:
 ///    a. Unbonding Initiation:
-///       - User initiates withdrawal by burning stake tokens (PRIME)
+///       - User initiates withdrawal by moving stake tokens (PRIME) into escrow
 ///       - System creates an unbonding ticket attached to the user
 ///       - Unbonding period timer starts
 ///    
 ///    b. Waiting Period:
 ///       - User holds an unbonding ticket during lock period
 ///       - Can query remaining time via status check
+///       - Can cancel the ticket to get the escrowed stake tokens (PRIME) back
 ///    
 ///    c. Redemption:
 ///       - After the unbonding period expires, the user can redeem
+///       - Escrowed stake tokens (PRIME) are burned
 ///       - Original vault tokens (wYLDS) returned to user
 ///      - Unbonding ticket is invalidated
 ///
@@ -40,122 +43,480 @@ pub mod account_structs;
 pub mod error;
 pub mod events;
 mod guard;
+mod merkle;
 pub mod processor;
 pub mod state;
 
 use account_structs::*;
 use anchor_lang::prelude::*;
-use state::ProofNode;
+use state::{ConfigChange, EpochClaim, ProofNode};
 
 declare_id!("dyXhxx6Y6LeMwZwb78oeTGWqwJkufPAMFEzH2QJ4mcp");
 
-#[program]
-pub mod hastra_sol_vault_stake {
+// #[program] generates the IDL instruction handlers next to the program module, and those still
+// call the deprecated AccountInfo::realloc, so the allowance wraps both
+#[allow(deprecated)]
+mod program_entry {
     use super::*;
 
-    /// Initializes the vault program with the required token configurations:
-    /// - vault_mint: The token that users deposit (e.g., wYLDS)
-    /// - stake_mint: The token users receive when staking (e.g., PRIME)
-    /// - unbonding_period: Time in seconds users must wait before redeeming
-    pub fn initialize(
-        ctx: Context<Initialize>,
-        vault_mint: Pubkey,
-        stake_mint: Pubkey,
-        unbonding_period: i64,
-        freeze_administrators: Vec<Pubkey>,
-        rewards_administrators: Vec<Pubkey>,
-    ) -> Result<()> {
-        processor::initialize(
-            ctx,
-            vault_mint,
-            stake_mint,
-            unbonding_period,
-            freeze_administrators,
-            rewards_administrators,
-        )
-    }
+    #[program]
+    pub mod hastra_sol_vault_stake {
+        use super::*;
 
-    /// Pauses or unpauses the protocol operations:
-    /// - pause: true to pause, false to unpause
-    pub fn pause(ctx: Context<Pause>, pause: bool) -> Result<()> {
-        processor::pause(ctx, pause)
-    }
+        /// Initializes the vault program with the required token configurations:
+        /// - vault_mint: The token that users deposit (e.g., wYLDS)
+        /// - stake_mint: The token users receive when staking (e.g., PRIME)
+        /// - unbonding_period: Time in seconds users must wait before redeeming
+        pub fn initialize(
+            ctx: Context<Initialize>,
+            vault_mint: Pubkey,
+            stake_mint: Pubkey,
+            unbonding_period: i64,
+            freeze_administrators: Vec<Pubkey>,
+            rewards_administrators: Vec<Pubkey>,
+        ) -> Result<()> {
+            processor::initialize(
+                ctx,
+                vault_mint,
+                stake_mint,
+                unbonding_period,
+                freeze_administrators,
+                rewards_administrators,
+            )
+        }
+
+        /// Pauses or unpauses the protocol operations:
+        /// - pause: true to pause every operation, false to lift that; operations paused with set_pause_flags stay paused
+        /// - reason: Short reason code recorded in the PauseFlagsUpdated event, 0 if unspecified
+        /// Pause guardians can pause; only the admin can unpause.
+        pub fn pause(ctx: Context<Pause>, pause: bool, reason: u8) -> Result<()> {
+            processor::pause(ctx, pause, reason)
+        }
+
+        /// Pauses individual operations, leaving the others running:
+        /// - flags: Bitmask of the operations to pause; 1 = everything, 2 = deposit, 4 = unbond (with cancel_unbond),
+        ///   8 = redeem (with settle_queue), 16 = rewards claims (with withdraw_vested), 32 = instant unbond
+        /// - Operations whose bits are clear are resumed
+        /// - reason: Short reason code recorded in the PauseFlagsUpdated event, 0 if unspecified
+        /// Pause guardians can only add bits; resuming an operation requires the admin.
+        pub fn set_pause_flags(ctx: Context<Pause>, flags: u8, reason: u8) -> Result<()> {
+            processor::set_pause_flags(ctx, flags, reason)
+        }
     
-    /// Updates the program configuration with new token addresses:
-    /// - new_unbonding_period: New unbonding period in seconds
-    pub fn update_config(ctx: Context<UpdateConfig>, new_unbonding_period: i64) -> Result<()> {
-        processor::update_config(ctx, new_unbonding_period)
-    }
+        /// Updates the program configuration with new token addresses:
+        /// - new_unbonding_period: New unbonding period in seconds, applied to tickets opened afterwards
+        pub fn update_config(ctx: Context<UpdateConfig>, new_unbonding_period: i64) -> Result<()> {
+            processor::update_config(ctx, new_unbonding_period)
+        }
 
-    /// Handles user deposits of vault tokens (e.g., wYLDS):
-    /// - Transfers vault tokens to program vault account
-    /// - Mints equivalent amount of stake tokens (e.g., PRIME) to user
-    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
-        processor::deposit(ctx, amount)
-    }
+        /// Timelocks config changes:
+        /// - delay: Seconds a proposed config change waits before it can be executed, at most 30 days
+        /// - While set, update_config, update_instant_unbond_config, the administrator and pause guardian list updates, propose_admin,
+        ///   update_epoch_rewards_cap and set_rewards_approval_threshold are disabled
+        /// - The delay can be raised at once; lowering it is itself a timelocked config change
+        pub fn set_config_change_delay(ctx: Context<SetConfigChangeDelay>, delay: i64) -> Result<()> {
+            processor::set_config_change_delay(ctx, delay)
+        }
 
-    /// Initiates the unbonding process:
-    /// - Burns user's stake tokens (e.g., PRIME)
-    /// - Starts unbonding period timer via user ticket
-    pub fn unbond(ctx: Context<Unbond>, amount: u64) -> Result<()> {
-        processor::unbond(ctx, amount)
-    }
+        /// Proposes a timelocked config change, executable once the config change delay has passed:
+        /// - change: New unbonding period, instant unbond parameters, administrator and pause guardian lists, delay, pending admin,
+        ///   approval threshold and/or epoch rewards cap; unset values are left as they are
+        /// - The treasury token account must be passed when the change sets instant unbond parameters
+        pub fn propose_config_change(ctx: Context<ProposeConfigChange>, change: ConfigChange) -> Result<()> {
+            processor::propose_config_change(ctx, change)
+        }
 
-    /// Completes the unbonding process after the period expires:
-    /// - Burns unbonding tokens (e.g., uwYLDS)
-    /// - Returns vault tokens (e.g., wYLDS) to user
-    pub fn redeem(ctx: Context<Redeem>) -> Result<()> {
-        processor::redeem(ctx)
-    }
+        /// Applies the pending config change after its eta.
+        pub fn execute_config_change(ctx: Context<ExecuteConfigChange>) -> Result<()> {
+            processor::execute_config_change(ctx)
+        }
 
-    pub fn update_freeze_administrators(
-        ctx: Context<UpdateFreezeAdministrators>,
-        new_administrators: Vec<Pubkey>,
-    ) -> Result<()> {
-        processor::update_freeze_administrators(ctx, new_administrators)
-    }
+        /// Drops the pending config change without applying it.
+        pub fn cancel_config_change(ctx: Context<CancelConfigChange>) -> Result<()> {
+            processor::cancel_config_change(ctx)
+        }
 
-    pub fn freeze_token_account(ctx: Context<FreezeTokenAccount>) -> Result<()> {
-        processor::freeze_token_account(ctx)
-    }
-    pub fn thaw_token_account(ctx: Context<ThawTokenAccount>) -> Result<()> {
-        processor::thaw_token_account(ctx)
-    }
+        /// Handles user deposits of vault tokens (e.g., wYLDS):
+        /// - Transfers vault tokens to program vault account
+        /// - Mints equivalent amount of stake tokens (e.g., PRIME) to user, at the exchange rate if enabled
+        pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+            processor::deposit(ctx, amount)
+        }
 
-    pub fn update_rewards_administrators(
-        ctx: Context<UpdateRewardsAdministrators>,
-        new_administrators: Vec<Pubkey>,
-    ) -> Result<()> {
-        processor::update_rewards_administrators(ctx, new_administrators)
-    }
+        /// Initiates the unbonding process:
+        /// - Moves user's stake tokens (e.g., PRIME) into the program-owned stake escrow
+        /// - Starts unbonding period timer via user ticket
+        pub fn unbond(ctx: Context<Unbond>, amount: u64) -> Result<()> {
+            processor::unbond(ctx, amount)
+        }
 
-    pub fn create_rewards_epoch(
-        ctx: Context<CreateRewardsEpoch>,
-        index: u64,
-        merkle_root: [u8; 32],
-        total: u64,
-    ) -> Result<()> {
-        processor::create_rewards_epoch(ctx, index, merkle_root, total)
-    }
+        /// Completes the unbonding process after the period expires:
+        /// - Burns the escrowed stake tokens (e.g., PRIME)
+        /// - Returns vault tokens (e.g., wYLDS) to user
+        /// - Joins the withdrawal queue instead if the vault is under-funded or others are queued
+        pub fn redeem(ctx: Context<Redeem>) -> Result<()> {
+            processor::redeem(ctx)
+        }
+
+        /// Redeems as much of a matured ticket as the vault can currently cover:
+        /// - Burns that part of the escrowed stake tokens and returns the vault tokens
+        /// - Keeps the ticket open with the remaining amount, closing it once fully redeemed
+        pub fn redeem_partial(ctx: Context<Redeem>) -> Result<()> {
+            processor::redeem_partial(ctx)
+        }
+
+        /// Creates the withdrawal queue that holds matured tickets the vault cannot pay out yet.
+        /// Must be called once after initialize, before any user can redeem.
+        pub fn initialize_withdrawal_queue(ctx: Context<InitializeWithdrawalQueue>) -> Result<()> {
+            processor::initialize_withdrawal_queue(ctx)
+        }
+
+        /// Tops up the vault token account so queued withdrawals can be settled:
+        /// - amount: Vault tokens (e.g., wYLDS) transferred from the admin into the vault
+        pub fn fund_queue(ctx: Context<FundQueue>, amount: u64) -> Result<()> {
+            processor::fund_queue(ctx, amount)
+        }
+
+        /// Settles the ticket at the head of the withdrawal queue:
+        /// - Pays the ticket owner as much as the vault holds, up to the remaining amount
+        /// - Advances the queue and closes the ticket once it is fully paid
+        pub fn settle_queue(ctx: Context<SettleQueue>) -> Result<()> {
+            processor::settle_queue(ctx)
+        }
+
+        /// Exits immediately instead of waiting out the unbonding period:
+        /// - Burns the user's stake tokens (e.g., PRIME)
+        /// - Pays the same amount of vault tokens (e.g., wYLDS) minus the exit fee, which goes to the treasury
+        /// - Limited by the per-epoch instant unbond cap and never pays out funds owed to the withdrawal queue
+        pub fn instant_unbond(ctx: Context<InstantUnbond>, amount: u64) -> Result<()> {
+            processor::instant_unbond(ctx, amount)
+        }
+
+        /// Configures instant unbonding:
+        /// - fee_bps: Exit fee in basis points, at most 5000
+        /// - epoch_cap: Max vault tokens paid out instantly per epoch, 0 disables instant unbonding
+        /// - The treasury token account passed in receives the fees
+        pub fn update_instant_unbond_config(
+            ctx: Context<UpdateInstantUnbondConfig>,
+            fee_bps: u16,
+            epoch_cap: u64,
+        ) -> Result<()> {
+            processor::update_instant_unbond_config(ctx, fee_bps, epoch_cap)
+        }
+
+        /// Turns exchange-rate mode on or off:
+        /// - Enabled: deposits mint `amount * supply / total_assets` and redemptions pay the inverse, both rounded down
+        /// - Enabling starts the rate at par; disabling requires the rate to still be at par
+        pub fn set_exchange_rate_mode(ctx: Context<SetExchangeRateMode>, enabled: bool) -> Result<()> {
+            processor::set_exchange_rate_mode(ctx, enabled)
+        }
+
+        /// Raises total assets by the yield earned, increasing the exchange rate for every holder:
+        /// - amount: Vault tokens (e.g., wYLDS) of yield to credit
+        pub fn report_yield(ctx: Context<ReportYield>, amount: u64) -> Result<()> {
+            processor::report_yield(ctx, amount)
+        }
+
+        /// Requires rewards epochs to be funded:
+        /// - enabled: true to make create_rewards_epoch transfer its total into the vault, false for legacy unfunded epochs
+        pub fn set_funded_rewards(ctx: Context<SetFundedRewards>, enabled: bool) -> Result<()> {
+            processor::set_funded_rewards(ctx, enabled)
+        }
+
+        /// Caps the new rewards a single epoch can issue, checked when the epoch is published:
+        /// - max_bps: Cap as basis points of the vault balance (0 to disable, max 10000)
+        /// - max_amount: Absolute cap (0 to disable)
+        pub fn update_epoch_rewards_cap(
+            ctx: Context<UpdateEpochRewardsCap>,
+            max_bps: u16,
+            max_amount: u64,
+        ) -> Result<()> {
+            processor::update_epoch_rewards_cap(ctx, max_bps, max_amount)
+        }
+
+        /// Makes classic rewards epochs created from now on vest their claims:
+        /// - duration: Seconds over which a claim unlocks linearly, 0 to mint claims at once
+        pub fn set_rewards_vesting_duration(
+            ctx: Context<SetRewardsVestingDuration>,
+            duration: i64,
+        ) -> Result<()> {
+            processor::set_rewards_vesting_duration(ctx, duration)
+        }
+
+        /// Requires rewards epochs to be created in order:
+        /// - next_epoch_index: Index the next epoch must use, then incremented by each new epoch. 0 allows any index
+        /// - For deployments with existing epochs, pass one past the highest existing index
+        /// - Can never be lowered, so once on, 0 is rejected too
+        pub fn set_next_epoch_index(ctx: Context<SetNextEpochIndex>, next_epoch_index: u64) -> Result<()> {
+            processor::set_next_epoch_index(ctx, next_epoch_index)
+        }
+
+        /// Sets how many rewards admins must approve a draft epoch before it is published:
+        /// - threshold: 0 or 1 lets any single rewards admin publish; above 1 epochs must be created as drafts
+        pub fn set_rewards_approval_threshold(
+            ctx: Context<SetRewardsApprovalThreshold>,
+            threshold: u8,
+        ) -> Result<()> {
+            processor::set_rewards_approval_threshold(ctx, threshold)
+        }
 
-    /// This is the classic “airdrop/claim per epoch” design
-    /// High-level idea:
-    /// 	1.	Off-chain (admin does this each epoch):
-    /// 	•	Calculate each user’s reward for this epoch.
-    /// 	•	Build a Merkle tree of (user, amount, epoch_index).
-    /// 	•	Publish the Merkle root on-chain with create_rewards_epoch function above.
-    ///
-    /// 	2.	On-chain:
-    /// 	•	Store each epoch’s Merkle root in a PDA.
-    /// 	•	When a user claims, they present (amount, proof) for their pubkey.
-    /// 	•	The program verifies the Merkle proof against the root.
-    /// 	•	If valid, transfer reward tokens (PRIME) from the rewards vault to the user's staking mint token account.
-    /// 	•	Mark the claim as redeemed so they can’t double-claim.
-    pub fn claim_rewards(
-        ctx: Context<ClaimRewards>,
-        amount: u64,
-        proof: Vec<ProofNode>,
-    ) -> Result<()> {
-        processor::claim_rewards(ctx, amount, proof)
+        /// Sets the merkle hashing scheme for rewards epochs created from now on:
+        /// - hash_version: 0 for legacy unprefixed hashing, 1 for 0x00/0x01 prefixed leaves and nodes with sorted pairs
+        pub fn set_rewards_hash_version(ctx: Context<SetRewardsHashVersion>, hash_version: u8) -> Result<()> {
+            processor::set_rewards_hash_version(ctx, hash_version)
+        }
+
+        /// Grows a rewards epoch created under an older layout so it can be claimed from again:
+        /// - claimed: Rewards already claimed from the epoch, tallied off-chain. Only used for epochs created before claims were counted
+        pub fn migrate_rewards_epoch(ctx: Context<MigrateRewardsEpoch>, claimed: u64) -> Result<()> {
+            processor::migrate_rewards_epoch(ctx, claimed)
+        }
+
+        /// Grows the config account to the current layout after new fields were added.
+        /// New fields start at zero, which keeps their legacy behaviour.
+        pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+            processor::migrate_config(ctx)
+        }
+
+        /// Abandons an open unbonding ticket:
+        /// - Returns the escrowed stake tokens (e.g., PRIME) to the user
+        /// - Closes the ticket and refunds its rent
+        pub fn cancel_unbond(ctx: Context<CancelUnbond>) -> Result<()> {
+            processor::cancel_unbond(ctx)
+        }
+
+        /// Completes several matured unbonding tickets in one transaction:
+        /// - Tickets are passed as writable remaining accounts
+        /// - Burns the combined escrowed stake tokens and returns the vault tokens in one transfer
+        pub fn redeem_many<'info>(ctx: Context<'_, '_, 'info, 'info, RedeemMany<'info>>) -> Result<()> {
+            processor::redeem_many(ctx)
+        }
+
+        /// Creates the stake escrow token account owned by the vault authority PDA.
+        /// Must be called once after initialize, before any user can unbond.
+        pub fn initialize_stake_escrow(ctx: Context<InitializeStakeEscrow>) -> Result<()> {
+            processor::initialize_stake_escrow(ctx)
+        }
+
+        /// Moves an unbonding ticket opened before tickets were keyed by nonce onto the user's next nonce:
+        /// - Escrows the ticket's requested amount (clamped to the user's balance) if it was never escrowed
+        /// - Keeps the original unbonding start time
+        pub fn migrate_ticket(ctx: Context<MigrateTicket>) -> Result<()> {
+            processor::migrate_ticket(ctx)
+        }
+
+        /// Starts handing protocol administration to a new admin key:
+        /// - new_admin: Key that must call accept_admin to take over. The default pubkey cancels a pending handover
+        /// Until an admin is set, the program upgrade authority administers the protocol.
+        pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+            processor::propose_admin(ctx, new_admin)
+        }
+
+        /// Completes the admin handover; must be signed by the pending admin.
+        pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+            processor::accept_admin(ctx)
+        }
+
+        /// Replaces the pause guardians, hot keys that can pause operations but not resume them:
+        /// - new_guardians: At most 5 guardian public keys
+        pub fn update_pause_guardians(
+            ctx: Context<UpdatePauseGuardians>,
+            new_guardians: Vec<Pubkey>,
+        ) -> Result<()> {
+            processor::update_pause_guardians(ctx, new_guardians)
+        }
+
+        pub fn update_freeze_administrators(
+            ctx: Context<UpdateFreezeAdministrators>,
+            new_administrators: Vec<Pubkey>,
+        ) -> Result<()> {
+            processor::update_freeze_administrators(ctx, new_administrators)
+        }
+
+        pub fn freeze_token_account(ctx: Context<FreezeTokenAccount>) -> Result<()> {
+            processor::freeze_token_account(ctx)
+        }
+        pub fn thaw_token_account(ctx: Context<ThawTokenAccount>) -> Result<()> {
+            processor::thaw_token_account(ctx)
+        }
+
+        pub fn update_rewards_administrators(
+            ctx: Context<UpdateRewardsAdministrators>,
+            new_administrators: Vec<Pubkey>,
+        ) -> Result<()> {
+            processor::update_rewards_administrators(ctx, new_administrators)
+        }
+
+        /// Publishes a rewards epoch:
+        /// - With funded rewards, the rewards admin transfers `total` vault tokens (e.g., wYLDS) into the vault
+        /// - claim_start_ts / claim_end_ts: Claim window, 0 to open immediately / never expire
+        /// - draft: Stage the epoch so it can be updated or revoked; it is funded and opened by publish_rewards_epoch
+        pub fn create_rewards_epoch(
+            ctx: Context<CreateRewardsEpoch>,
+            index: u64,
+            merkle_root: [u8; 32],
+            total: u64,
+            claim_start_ts: i64,
+            claim_end_ts: i64,
+            draft: bool,
+        ) -> Result<()> {
+            processor::create_rewards_epoch(
+                ctx,
+                index,
+                merkle_root,
+                total,
+                claim_start_ts,
+                claim_end_ts,
+                draft,
+            )
+        }
+
+        /// This is the classic “airdrop/claim per epoch” design
+        /// High-level idea:
+        /// 1. Off-chain (admin does this each epoch):
+        ///    - Calculate each user’s reward for this epoch.
+        ///    - Build a Merkle tree of (user, amount, epoch_index).
+        ///    - Publish the Merkle root on-chain with create_rewards_epoch function above.
+        ///
+        /// 2. On-chain:
+        ///    - Store each epoch’s Merkle root in a PDA.
+        ///    - When a user claims, they present (amount, proof) for their pubkey.
+        ///    - The program verifies the Merkle proof against the root.
+        ///    - If valid, transfer reward tokens (PRIME) from the rewards vault to the user's staking mint token account.
+        ///    - Mark the claim as redeemed so they can’t double-claim.
+        ///    - For vesting epochs, open a vesting account instead of minting; see withdraw_vested.
+        pub fn claim_rewards(
+            ctx: Context<ClaimRewards>,
+            amount: u64,
+            proof: Vec<ProofNode>,
+        ) -> Result<()> {
+            processor::claim_rewards(ctx, amount, proof)
+        }
+
+        /// Mints the part of a vesting claim unlocked since the last withdrawal:
+        /// - Closes the vesting account once the whole claim is released
+        pub fn withdraw_vested(ctx: Context<WithdrawVested>) -> Result<()> {
+            processor::withdraw_vested(ctx)
+        }
+
+        /// Publishes a cumulative rewards epoch whose leaves commit to each user's lifetime rewards:
+        /// - total: Lifetime total across all users, at least the previous cumulative epoch's total
+        /// - With funded rewards, only the increase over the previous cumulative total is transferred into the vault
+        pub fn create_cumulative_rewards_epoch(
+            ctx: Context<CreateRewardsEpoch>,
+            index: u64,
+            merkle_root: [u8; 32],
+            total: u64,
+            claim_start_ts: i64,
+            claim_end_ts: i64,
+            draft: bool,
+        ) -> Result<()> {
+            processor::create_cumulative_rewards_epoch(
+                ctx,
+                index,
+                merkle_root,
+                total,
+                claim_start_ts,
+                claim_end_ts,
+                draft,
+            )
+        }
+
+        /// Claims from a cumulative rewards epoch:
+        /// - cumulative_amount: The user's lifetime rewards committed to by the epoch root
+        /// - Mints `cumulative_amount - claimed` and raises the user's claimed watermark
+        pub fn claim_cumulative_rewards(
+            ctx: Context<ClaimCumulativeRewards>,
+            cumulative_amount: u64,
+            proof: Vec<ProofNode>,
+        ) -> Result<()> {
+            processor::claim_cumulative_rewards(ctx, cumulative_amount, proof)
+        }
+
+        /// Publishes a rewards epoch whose leaves include their index, tracked with a claim bitmap:
+        /// - leaf_count: Number of leaves in the tree, one claim bit each
+        /// - Leaves are sha256(user || amount || epoch_index || leaf_index)
+        #[allow(clippy::too_many_arguments)]
+        pub fn create_indexed_rewards_epoch(
+            ctx: Context<CreateIndexedRewardsEpoch>,
+            index: u64,
+            merkle_root: [u8; 32],
+            total: u64,
+            leaf_count: u64,
+            claim_start_ts: i64,
+            claim_end_ts: i64,
+            draft: bool,
+        ) -> Result<()> {
+            processor::create_indexed_rewards_epoch(
+                ctx,
+                index,
+                merkle_root,
+                total,
+                leaf_count,
+                claim_start_ts,
+                claim_end_ts,
+                draft,
+            )
+        }
+
+        /// Closes a rewards epoch after its claim window has ended:
+        /// - Returns the epoch's rent (and its claim bitmap's, for indexed epochs) to the rewards admin
+        /// - Emits RewardsEpochClosed with the unclaimed remainder
+        pub fn close_rewards_epoch(ctx: Context<CloseRewardsEpoch>) -> Result<()> {
+            processor::close_rewards_epoch(ctx)
+        }
+
+        /// Corrects a draft rewards epoch before it is published:
+        /// - merkle_root: Replacement root
+        /// - total: Replacement total
+        pub fn update_rewards_epoch(
+            ctx: Context<UpdateRewardsEpoch>,
+            merkle_root: [u8; 32],
+            total: u64,
+        ) -> Result<()> {
+            processor::update_rewards_epoch(ctx, merkle_root, total)
+        }
+
+        /// Revokes a draft rewards epoch, returning its rent and freeing its index
+        pub fn revoke_rewards_epoch(ctx: Context<CloseRewardsEpoch>) -> Result<()> {
+            processor::revoke_rewards_epoch(ctx)
+        }
+
+        /// Approves a draft rewards epoch's current root and total as a rewards admin:
+        /// - Approvals of an earlier root are discarded once the draft is updated
+        pub fn approve_rewards_epoch(ctx: Context<ApproveRewardsEpoch>) -> Result<()> {
+            processor::approve_rewards_epoch(ctx)
+        }
+
+        /// Publishes a draft rewards epoch so it can be claimed:
+        /// - Requires approvals from at least `rewards_approval_threshold` distinct rewards admins
+        /// - With funded rewards, the rewards admin transfers the epoch's total (or the cumulative increase) into the vault
+        pub fn publish_rewards_epoch(ctx: Context<PublishRewardsEpoch>) -> Result<()> {
+            processor::publish_rewards_epoch(ctx)
+        }
+
+        /// Claims a leaf of an indexed rewards epoch:
+        /// - Verifies the proof and flips the leaf's bit in the claim bitmap instead of creating a claim record
+        pub fn claim_indexed_rewards(
+            ctx: Context<ClaimIndexedRewards>,
+            leaf_index: u64,
+            amount: u64,
+            proof: Vec<ProofNode>,
+        ) -> Result<()> {
+            processor::claim_indexed_rewards(ctx, leaf_index, amount, proof)
+        }
+
+        /// Claims several rewards epochs in one transaction:
+        /// - claims: (amount, proof) per epoch, in the same order as the remaining accounts
+        /// - Remaining accounts: the writable epoch and claim record PDA for each claim, in pairs
+        /// - Verifies every proof, creates the claim records and mints the combined amount once
+        pub fn claim_rewards_batch<'info>(
+            ctx: Context<'_, '_, 'info, 'info, ClaimRewardsBatch<'info>>,
+            claims: Vec<EpochClaim>,
+        ) -> Result<()> {
+            processor::claim_rewards_batch(ctx, claims)
+        }
     }
 }
+
+pub use program_entry::*;
@@ -0,0 +1,91 @@
+use crate::error::CustomErrorCode;
+use crate::state::{ProofNode, HASH_VERSION_LEGACY, HASH_VERSION_PREFIXED_SORTED};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hashv;
+
+// Domain separation prefixes for HASH_VERSION_PREFIXED_SORTED, so a 64-byte interior node can
+// never be passed off as a leaf or the other way round
+const LEAF_PREFIX: &[u8] = &[0x00];
+const NODE_PREFIX: &[u8] = &[0x01];
+
+fn hash_leaf(hash_version: u8, data: &[u8]) -> Result<[u8; 32]> {
+    match hash_version {
+        HASH_VERSION_LEGACY => Ok(hashv(&[data]).to_bytes()),
+        HASH_VERSION_PREFIXED_SORTED => Ok(hashv(&[LEAF_PREFIX, data]).to_bytes()),
+        _ => err!(CustomErrorCode::InvalidHashVersion),
+    }
+}
+
+// legacy: sha256(user || amount_le || epoch_index_le)
+// prefixed: sha256(0x00 || user || amount_le || epoch_index_le)
+pub fn claim_leaf(hash_version: u8, user: &Pubkey, amount: u64, epoch_index: u64) -> Result<[u8; 32]> {
+    let mut data = Vec::with_capacity(32 + 8 + 8);
+    data.extend_from_slice(user.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&epoch_index.to_le_bytes());
+    hash_leaf(hash_version, &data)
+}
+
+// Same as claim_leaf with the leaf index appended, used by indexed epochs
+pub fn indexed_claim_leaf(
+    hash_version: u8,
+    user: &Pubkey,
+    amount: u64,
+    epoch_index: u64,
+    leaf_index: u64,
+) -> Result<[u8; 32]> {
+    let mut data = Vec::with_capacity(32 + 8 + 8 + 8);
+    data.extend_from_slice(user.as_ref());
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&epoch_index.to_le_bytes());
+    data.extend_from_slice(&leaf_index.to_le_bytes());
+    hash_leaf(hash_version, &data)
+}
+
+// Hash the leaf up through the proof and return the root it leads to
+pub fn compute_root(hash_version: u8, leaf: [u8; 32], proof: &[ProofNode]) -> Result<[u8; 32]> {
+    match hash_version {
+        HASH_VERSION_LEGACY => Ok(compute_root_legacy(leaf, proof)),
+        HASH_VERSION_PREFIXED_SORTED => Ok(compute_root_sorted(leaf, proof)),
+        _ => err!(CustomErrorCode::InvalidHashVersion),
+    }
+}
+
+// Unprefixed nodes ordered by the is_left flag; an all-zero sibling hashes the node on its own
+fn compute_root_legacy(leaf: [u8; 32], proof: &[ProofNode]) -> [u8; 32] {
+    let mut node = leaf;
+    for (i, step) in proof.iter().enumerate() {
+        let sib = &step.sibling;
+
+        if sib.iter().all(|&b| b == 0) {
+            msg!("[{}] right: sibling is zero - hashing just the node", i);
+            node = hashv(&[&node]).to_bytes();
+            continue;
+        }
+
+        if step.is_left {
+            // sibling is left, so hash(sib || node)
+            node = hashv(&[sib, &node]).to_bytes();
+            msg!("[{}] left: hash(sib,node) = {}", i, hex::encode(node));
+        } else {
+            // sibling is right, so hash(node || sib)
+            node = hashv(&[&node, sib]).to_bytes();
+            msg!("[{}] right: hash(node,sib) = {}", i, hex::encode(node));
+        }
+    }
+    node
+}
+
+// sha256(0x01 || min(a, b) || max(a, b)); is_left is ignored and zero siblings get no special case
+fn compute_root_sorted(leaf: [u8; 32], proof: &[ProofNode]) -> [u8; 32] {
+    let mut node = leaf;
+    for step in proof {
+        let sib = &step.sibling;
+        node = if node <= *sib {
+            hashv(&[NODE_PREFIX, &node, sib]).to_bytes()
+        } else {
+            hashv(&[NODE_PREFIX, sib, &node]).to_bytes()
+        };
+    }
+    node
+}
@@ -1,12 +1,17 @@
 use crate::account_structs::*;
+use crate::account_utils::{close_account, create_pda_account, load_zero_extended, resize_account};
 use crate::error::*;
 use crate::events::*;
-use crate::guard::validate_program_update_authority;
-use crate::state::{ProofNode, MAX_UNBONDING_PERIOD, MIN_UNBONDING_PERIOD, MAX_ADMINISTRATORS};
+use crate::guard::{validate_admin, validate_pause_authority, validate_program_update_authority};
+use crate::merkle::{claim_leaf, compute_root, indexed_claim_leaf};
+use crate::state::{
+    ClaimBitmap, ClaimRecord, Config, ConfigChange, VestingAccount, EpochClaim, ProofNode, RewardsEpoch, UnbondingTicket, BPS_DENOMINATOR, MAX_ADMINISTRATORS, MAX_UNBONDING_PERIOD,
+    HASH_VERSION_PREFIXED_SORTED, MAX_BITMAP_LEAVES, MAX_CONFIG_CHANGE_DELAY, MAX_INSTANT_UNBOND_FEE_BPS, MIN_UNBONDING_PERIOD, PAUSE_ALL, PAUSE_CLAIM,
+    PAUSE_DEPOSIT, PAUSE_FLAGS_MASK, PAUSE_INSTANT_UNBOND, PAUSE_REDEEM, PAUSE_UNBOND,
+};
 use anchor_lang::prelude::*;
-use anchor_lang::solana_program::hash::hashv;
 use anchor_spl::token::spl_token::instruction::AuthorityType;
-use anchor_spl::token::{self, Burn, MintTo, Transfer};
+use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount, Transfer};
 
 pub fn initialize(
     ctx: Context<Initialize>,
@@ -42,6 +47,18 @@ pub fn initialize(
     config.freeze_administrators = freeze_administrators;
     config.rewards_administrators = rewards_administrators;
     config.bump = ctx.bumps.config;
+    config.funded_rewards = true;
+    config.rewards_hash_version = HASH_VERSION_PREFIXED_SORTED;
+    config.admin = ctx.accounts.signer.key();
+
+    emit!(ProtocolInitialized {
+        admin: config.admin,
+        unbonding_period,
+        freeze_administrators: config.freeze_administrators.clone(),
+        rewards_administrators: config.rewards_administrators.clone(),
+        mint: stake_mint,
+        vault: vault_mint,
+    });
 
     // The vault token account must be owned by the program-derived address (PDA)
     // and is the token account that holds the deposited vault tokens (e.g., wYLDS).
@@ -49,7 +66,7 @@ pub fn initialize(
     // Only set vault token account to PDA authority if it's not already set to vault_authority
     if ctx.accounts.vault_token_account.owner == ctx.accounts.signer.key() {
         let seeds: &[&[u8]] = &[b"vault_authority", &[ctx.bumps.vault_authority]];
-        let signer = &[&seeds[..]];
+        let signer = &[seeds];
         token::set_authority(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
@@ -66,18 +83,46 @@ pub fn initialize(
     Ok(())
 }
 
-pub fn pause(ctx: Context<Pause>, pause: bool) -> Result<()> {
-    validate_program_update_authority(&ctx.accounts.program_data, &ctx.accounts.signer)?;
-    let config = &mut ctx.accounts.config;
-    config.paused = pause;
+// Sets or clears PAUSE_ALL. Operations paused through set_pause_flags stay paused either way.
+pub fn pause(ctx: Context<Pause>, pause: bool, reason: u8) -> Result<()> {
+    let current = ctx.accounts.config.pause_flags;
+    let flags = if pause { current | PAUSE_ALL } else { current & !PAUSE_ALL };
+    validate_pause_authority(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer, flags)?;
+    update_pause_flags(&mut ctx.accounts.config, ctx.accounts.signer.key(), flags, reason);
 
     msg!("Protocol paused: {}", pause);
 
     Ok(())
 }
 
+// Pauses exactly the operations whose PAUSE_* bits are set and resumes the rest
+pub fn set_pause_flags(ctx: Context<Pause>, flags: u8, reason: u8) -> Result<()> {
+    validate_pause_authority(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer, flags)?;
+    require!(flags & !PAUSE_FLAGS_MASK == 0, CustomErrorCode::InvalidPauseFlags);
+    update_pause_flags(&mut ctx.accounts.config, ctx.accounts.signer.key(), flags, reason);
+
+    Ok(())
+}
+
+fn update_pause_flags(config: &mut Config, authority: Pubkey, flags: u8, reason: u8) {
+    let old_flags = config.pause_flags;
+    config.pause_flags = flags;
+
+    emit!(PauseFlagsUpdated {
+        authority,
+        old_flags,
+        new_flags: flags,
+        paused: flags & !old_flags,
+        resumed: old_flags & !flags,
+        reason,
+        mint: config.mint,
+        vault: config.vault,
+    });
+}
+
 pub fn update_config(ctx: Context<UpdateConfig>, new_unbonding_period: i64) -> Result<()> {
-    validate_program_update_authority(&ctx.accounts.program_data, &ctx.accounts.signer)?;
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+    ctx.accounts.config.require_no_timelock()?;
     require!(new_unbonding_period >= MIN_UNBONDING_PERIOD, CustomErrorCode::InvalidBondingPeriod);
     require!(
         new_unbonding_period <= MAX_UNBONDING_PERIOD,
@@ -85,12 +130,279 @@ pub fn update_config(ctx: Context<UpdateConfig>, new_unbonding_period: i64) -> R
     );
 
     let config = &mut ctx.accounts.config;
+    let old_period = config.unbonding_period;
     config.unbonding_period = new_unbonding_period;
 
     emit!(UnbondingPeriodUpdated {
         admin: ctx.accounts.signer.key(),
-        old_period: ctx.accounts.config.unbonding_period,
+        old_period,
         new_period: new_unbonding_period,
+        mint: config.mint,
+        vault: config.vault,
+    });
+
+    Ok(())
+}
+
+// Raising the timelock applies at once; lowering it must itself wait out the current delay
+// through propose_config_change.
+pub fn set_config_change_delay(ctx: Context<SetConfigChangeDelay>, delay: i64) -> Result<()> {
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+    require!(
+        (0..=MAX_CONFIG_CHANGE_DELAY).contains(&delay),
+        CustomErrorCode::InvalidConfigChangeDelay
+    );
+    let config = &mut ctx.accounts.config;
+    require!(
+        delay >= config.config_change_delay,
+        CustomErrorCode::ConfigChangeTimelocked
+    );
+    let old_value = config.config_change_delay;
+    config.config_change_delay = delay;
+
+    emit!(ConfigChangeDelayUpdated {
+        admin: ctx.accounts.signer.key(),
+        old_value,
+        new_value: delay,
+        mint: config.mint,
+        vault: config.vault,
+    });
+
+    msg!("Config change delay: {}", delay);
+
+    Ok(())
+}
+
+pub fn propose_config_change(ctx: Context<ProposeConfigChange>, change: ConfigChange) -> Result<()> {
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+    require!(
+        change.unbonding_period.is_some()
+            || change.instant_unbond.is_some()
+            || change.freeze_administrators.is_some()
+            || change.rewards_administrators.is_some()
+            || change.config_change_delay.is_some()
+            || change.pause_guardians.is_some()
+            || change.pending_admin.is_some()
+            || change.rewards_approval_threshold.is_some()
+            || change.epoch_rewards_cap.is_some(),
+        CustomErrorCode::EmptyConfigChange
+    );
+    if let Some(period) = change.unbonding_period {
+        require!(
+            (MIN_UNBONDING_PERIOD..=MAX_UNBONDING_PERIOD).contains(&period),
+            CustomErrorCode::InvalidBondingPeriod
+        );
+    }
+    if let Some(params) = &change.instant_unbond {
+        require!(params.fee_bps <= MAX_INSTANT_UNBOND_FEE_BPS, CustomErrorCode::InvalidFee);
+        // the treasury is checked the same way update_instant_unbond_config checks it
+        let treasury = ctx
+            .accounts
+            .treasury_token_account
+            .as_ref()
+            .ok_or(CustomErrorCode::InvalidTreasury)?;
+        require!(
+            treasury.key() == params.treasury,
+            CustomErrorCode::InvalidTreasury
+        );
+    }
+    if let Some(administrators) = &change.freeze_administrators {
+        require!(
+            administrators.len() <= MAX_ADMINISTRATORS,
+            CustomErrorCode::TooManyAdministrators
+        );
+    }
+    if let Some(administrators) = &change.rewards_administrators {
+        require!(
+            administrators.len() <= MAX_ADMINISTRATORS,
+            CustomErrorCode::TooManyAdministrators
+        );
+    }
+    if let Some(delay) = change.config_change_delay {
+        require!(
+            (0..=MAX_CONFIG_CHANGE_DELAY).contains(&delay),
+            CustomErrorCode::InvalidConfigChangeDelay
+        );
+    }
+    if let Some(guardians) = &change.pause_guardians {
+        require!(
+            guardians.len() <= MAX_ADMINISTRATORS,
+            CustomErrorCode::TooManyAdministrators
+        );
+    }
+    if let Some(threshold) = change.rewards_approval_threshold {
+        let administrators = change
+            .rewards_administrators
+            .as_ref()
+            .unwrap_or(&ctx.accounts.config.rewards_administrators);
+        require!(
+            threshold as usize <= administrators.len(),
+            CustomErrorCode::InvalidApprovalThreshold
+        );
+    }
+    if let Some(cap) = &change.epoch_rewards_cap {
+        require!(
+            cap.max_bps as u64 <= BPS_DENOMINATOR,
+            CustomErrorCode::InvalidRewardsCap
+        );
+    }
+
+    let eta = Clock::get()?
+        .unix_timestamp
+        .checked_add(ctx.accounts.config.config_change_delay)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    let current = change.current_values(&ctx.accounts.config);
+    let pending = &mut ctx.accounts.pending_config_change;
+    pending.proposer = ctx.accounts.signer.key();
+    pending.eta = eta;
+    pending.change = change.clone();
+    pending.bump = ctx.bumps.pending_config_change;
+
+    emit!(ConfigChangeProposed {
+        admin: ctx.accounts.signer.key(),
+        eta,
+        change,
+        current,
+        mint: ctx.accounts.config.mint,
+        vault: ctx.accounts.config.vault,
+    });
+
+    Ok(())
+}
+
+// Applies a pending config change once its timelock has passed. The pending account is closed
+// to the signer.
+pub fn execute_config_change(ctx: Context<ExecuteConfigChange>) -> Result<()> {
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+    let pending = &ctx.accounts.pending_config_change;
+    require!(
+        Clock::get()?.unix_timestamp >= pending.eta,
+        CustomErrorCode::ConfigChangeNotReady
+    );
+
+    let change = pending.change.clone();
+    let config = &mut ctx.accounts.config;
+    if let Some(period) = change.unbonding_period {
+        let old_period = config.unbonding_period;
+        config.unbonding_period = period;
+        emit!(UnbondingPeriodUpdated {
+            admin: ctx.accounts.signer.key(),
+            old_period,
+            new_period: period,
+            mint: config.mint,
+            vault: config.vault,
+        });
+    }
+    if let Some(params) = change.instant_unbond {
+        emit!(InstantUnbondConfigUpdated {
+            admin: ctx.accounts.signer.key(),
+            old_fee_bps: config.instant_unbond_fee_bps,
+            new_fee_bps: params.fee_bps,
+            old_epoch_cap: config.instant_unbond_epoch_cap,
+            new_epoch_cap: params.epoch_cap,
+            old_treasury: config.treasury,
+            new_treasury: params.treasury,
+            mint: config.mint,
+            vault: config.vault,
+        });
+        config.instant_unbond_fee_bps = params.fee_bps;
+        config.instant_unbond_epoch_cap = params.epoch_cap;
+        config.treasury = params.treasury;
+    }
+    if let Some(administrators) = change.freeze_administrators {
+        let old_administrators = std::mem::replace(&mut config.freeze_administrators, administrators);
+        emit!(FreezeAdministratorsUpdated {
+            admin: ctx.accounts.signer.key(),
+            old_administrators,
+            new_administrators: config.freeze_administrators.clone(),
+            mint: config.mint,
+            vault: config.vault,
+        });
+    }
+    if let Some(administrators) = change.rewards_administrators {
+        let old_administrators = std::mem::replace(&mut config.rewards_administrators, administrators);
+        emit!(RewardsAdministratorsUpdated {
+            admin: ctx.accounts.signer.key(),
+            old_administrators,
+            new_administrators: config.rewards_administrators.clone(),
+            mint: config.mint,
+            vault: config.vault,
+        });
+    }
+    if let Some(delay) = change.config_change_delay {
+        emit!(ConfigChangeDelayUpdated {
+            admin: ctx.accounts.signer.key(),
+            old_value: config.config_change_delay,
+            new_value: delay,
+            mint: config.mint,
+            vault: config.vault,
+        });
+        config.config_change_delay = delay;
+    }
+    if let Some(guardians) = change.pause_guardians {
+        let old_guardians = std::mem::replace(&mut config.pause_guardians, guardians);
+        emit!(PauseGuardiansUpdated {
+            admin: ctx.accounts.signer.key(),
+            old_guardians,
+            new_guardians: config.pause_guardians.clone(),
+            mint: config.mint,
+            vault: config.vault,
+        });
+    }
+    if let Some(pending_admin) = change.pending_admin {
+        config.pending_admin = pending_admin;
+        emit!(AdminProposed {
+            admin: ctx.accounts.signer.key(),
+            pending_admin,
+            mint: config.mint,
+            vault: config.vault,
+        });
+    }
+    if let Some(threshold) = change.rewards_approval_threshold {
+        emit!(RewardsApprovalThresholdUpdated {
+            admin: ctx.accounts.signer.key(),
+            old_value: config.rewards_approval_threshold,
+            new_value: threshold,
+            mint: config.mint,
+            vault: config.vault,
+        });
+        config.rewards_approval_threshold = threshold;
+    }
+    if let Some(cap) = change.epoch_rewards_cap {
+        emit!(EpochRewardsCapUpdated {
+            admin: ctx.accounts.signer.key(),
+            old_max_bps: config.max_epoch_rewards_bps,
+            new_max_bps: cap.max_bps,
+            old_max_amount: config.max_epoch_rewards,
+            new_max_amount: cap.max_amount,
+            mint: config.mint,
+            vault: config.vault,
+        });
+        config.max_epoch_rewards_bps = cap.max_bps;
+        config.max_epoch_rewards = cap.max_amount;
+    }
+    // the threshold and the rewards admins can change together, and either may have changed
+    // since the proposal
+    require!(
+        config.rewards_approval_threshold as usize <= config.rewards_administrators.len(),
+        CustomErrorCode::InvalidApprovalThreshold
+    );
+
+    emit!(ConfigChangeExecuted {
+        admin: ctx.accounts.signer.key(),
+        mint: config.mint,
+        vault: config.vault,
+    });
+
+    Ok(())
+}
+
+pub fn cancel_config_change(ctx: Context<CancelConfigChange>) -> Result<()> {
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+
+    emit!(ConfigChangeCancelled {
+        admin: ctx.accounts.signer.key(),
+        eta: ctx.accounts.pending_config_change.eta,
         mint: ctx.accounts.config.mint,
         vault: ctx.accounts.config.vault,
     });
@@ -100,7 +412,12 @@ pub fn update_config(ctx: Context<UpdateConfig>, new_unbonding_period: i64) -> R
 
 pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
     require!(amount > 0, CustomErrorCode::InvalidAmount);
-    require!(!ctx.accounts.config.paused, CustomErrorCode::ProtocolPaused);
+    ctx.accounts.config.require_not_paused(PAUSE_DEPOSIT)?;
+
+    // 1:1 unless exchange-rate mode is on, in which case shares are rounded down
+    let supply = ctx.accounts.mint.supply;
+    let shares = ctx.accounts.config.to_shares(amount, supply)?;
+    require!(shares > 0, CustomErrorCode::InvalidAmount);
 
     let cpi_accounts = Transfer {
         from: ctx.accounts.user_vault_token_account.to_account_info(),
@@ -113,7 +430,7 @@ pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
     )?;
 
     let seeds: &[&[u8]] = &[b"mint_authority", &[ctx.bumps.mint_authority]];
-    let signer = &[&seeds[..]];
+    let signer = &[seeds];
     let cpi_accounts = MintTo {
         mint: ctx.accounts.mint.to_account_info(),
         to: ctx.accounts.user_mint_token_account.to_account_info(),
@@ -125,9 +442,11 @@ pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
             cpi_accounts,
             signer,
         ),
-        amount,
+        shares,
     )?;
 
+    ctx.accounts.config.record_deposit(amount, supply)?;
+
     emit!(DepositEvent {
         user: ctx.accounts.signer.key(),
         amount,
@@ -140,7 +459,7 @@ pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
 
 pub fn unbond(ctx: Context<Unbond>, amount: u64) -> Result<()> {
     require!(amount > 0, CustomErrorCode::InvalidAmount);
-    require!(!ctx.accounts.config.paused, CustomErrorCode::ProtocolPaused);
+    ctx.accounts.config.require_not_paused(PAUSE_UNBOND)?;
 
     let current_mint_amount = ctx.accounts.user_mint_token_account.amount;
     require!(
@@ -148,24 +467,68 @@ pub fn unbond(ctx: Context<Unbond>, amount: u64) -> Result<()> {
         CustomErrorCode::InsufficientUnbondingBalance
     );
 
+    // Move the stake tokens into escrow now so the ticket is fully backed for the whole
+    // unbonding period and the tokens cannot be transferred away in the meantime
+    let transfer_accounts = Transfer {
+        from: ctx.accounts.user_mint_token_account.to_account_info(),
+        to: ctx.accounts.stake_escrow_token_account.to_account_info(),
+        authority: ctx.accounts.signer.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer_accounts),
+        amount,
+    )?;
+
+    let user_state = &mut ctx.accounts.user_state;
+    user_state.owner = ctx.accounts.signer.key();
+    user_state.bump = ctx.bumps.user_state;
+    let nonce = user_state.next_ticket_nonce;
+    user_state.next_ticket_nonce = nonce
+        .checked_add(1)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    // Snapshot the unlock time so later unbonding period updates don't affect this ticket
+    let start_ts = Clock::get()?.unix_timestamp;
+    let unlock_ts = start_ts
+        .checked_add(ctx.accounts.config.unbonding_period)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
     let ticket = &mut ctx.accounts.ticket;
     ticket.owner = ctx.accounts.signer.key();
     ticket.requested_amount = amount;
     ticket.start_balance = current_mint_amount;
-    ticket.start_ts = Clock::get()?.unix_timestamp;
+    ticket.start_ts = start_ts;
+    ticket.remaining_amount = amount;
+    ticket.nonce = nonce;
+    ticket.unlock_ts = unlock_ts;
 
     emit!(UnbondEvent {
         user: ctx.accounts.signer.key(),
         amount,
         mint: ctx.accounts.mint.key(),
         vault: ctx.accounts.config.vault,
+        nonce,
+        unlock_ts,
     });
 
     Ok(())
 }
 
 pub fn redeem(ctx: Context<Redeem>) -> Result<()> {
-    require!(!ctx.accounts.config.paused, CustomErrorCode::ProtocolPaused);
+    redeem_ticket(ctx, false)
+}
+
+pub fn redeem_partial(ctx: Context<Redeem>) -> Result<()> {
+    redeem_ticket(ctx, true)
+}
+
+// Pay out a matured ticket. A full redeem requires the vault to cover the whole remaining amount.
+// A partial redeem pays whatever the vault holds and keeps the ticket open for the rest, so a
+// temporarily illiquid vault doesn't force the user to restart the unbonding clock. When the
+// vault can't pay anything, or earlier tickets are already waiting, the ticket joins the
+// withdrawal queue instead and is settled in order once the vault is funded.
+fn redeem_ticket(ctx: Context<Redeem>, allow_partial: bool) -> Result<()> {
+    ctx.accounts.config.require_not_paused(PAUSE_REDEEM)?;
     let now = Clock::get()?.unix_timestamp;
     let ticket = &ctx.accounts.ticket;
     require_keys_eq!(
@@ -174,37 +537,189 @@ pub fn redeem(ctx: Context<Redeem>) -> Result<()> {
         CustomErrorCode::InvalidTicketOwner
     );
 
+    require!(
+        now >= ticket.unlock_ts,
+        CustomErrorCode::UnbondingPeriodNotElapsed
+    );
+    require!(!ticket.queued, CustomErrorCode::TicketQueued);
+
+    let remaining = ticket.remaining_amount;
+    require!(remaining > 0, CustomErrorCode::TicketNotMigrated);
+
+    let vault_balance = ctx.accounts.vault_token_account.amount;
+    let supply = ctx.accounts.mint.supply;
     let config = &ctx.accounts.config;
+    let redeem = if !ctx.accounts.withdrawal_queue.is_empty() {
+        0
+    } else if allow_partial {
+        remaining.min(config.to_shares(vault_balance, supply)?)
+    } else if vault_balance >= config.to_assets(remaining, supply)? {
+        remaining
+    } else {
+        0
+    };
 
+    if redeem == 0 {
+        let queue = &mut ctx.accounts.withdrawal_queue;
+        let position = queue.tail;
+        queue.tail = position
+            .checked_add(1)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        queue.queued_amount = queue
+            .queued_amount
+            .checked_add(remaining)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        let ticket = &mut ctx.accounts.ticket;
+        ticket.queued = true;
+        ticket.queue_position = position;
+
+        emit!(WithdrawalEnqueued {
+            user: ctx.accounts.signer.key(),
+            nonce: ticket.nonce,
+            position,
+            amount: remaining,
+            mint: ctx.accounts.mint.key(),
+            vault: ctx.accounts.vault_token_account.key(),
+        });
+
+        return Ok(());
+    }
+
+    let payout = ctx.accounts.config.to_assets(redeem, supply)?;
+    release_escrow(
+        &ctx.accounts.token_program,
+        &ctx.accounts.mint,
+        &ctx.accounts.stake_escrow_token_account,
+        &ctx.accounts.vault_token_account,
+        &ctx.accounts.user_vault_token_account,
+        &ctx.accounts.vault_authority,
+        ctx.bumps.vault_authority,
+        redeem,
+        payout,
+    )?;
+    ctx.accounts.config.record_withdrawal(payout);
+
+    let ticket = &mut ctx.accounts.ticket;
+    ticket.remaining_amount = remaining - redeem;
+
+    emit!(RedeemEvent {
+        user: ctx.accounts.signer.key(),
+        amount: redeem,
+        mint: ctx.accounts.mint.key(),
+        vault: ctx.accounts.vault_token_account.key(),
+        nonce: ticket.nonce,
+        remaining: ticket.remaining_amount,
+    });
+
+    if ticket.remaining_amount == 0 {
+        // return rent to user when done
+        ticket.close(ctx.accounts.signer.to_account_info())?;
+    }
+
+    Ok(())
+}
+
+// Redeem every matured ticket passed in the remaining accounts with a single burn and transfer.
+// All tickets must belong to the signer and have completed their unbonding period.
+pub fn redeem_many<'info>(ctx: Context<'_, '_, 'info, 'info, RedeemMany<'info>>) -> Result<()> {
+    ctx.accounts.config.require_not_paused(PAUSE_REDEEM)?;
     require!(
-        now - ticket.start_ts >= config.unbonding_period,
-        CustomErrorCode::UnbondingPeriodNotElapsed
+        !ctx.remaining_accounts.is_empty(),
+        CustomErrorCode::NoTicketsProvided
     );
+    require!(
+        ctx.accounts.withdrawal_queue.is_empty(),
+        CustomErrorCode::WithdrawalQueueNotEmpty
+    );
+    let now = Clock::get()?.unix_timestamp;
+    let signer = ctx.accounts.signer.key();
 
-    let current_mint_amount = ctx.accounts.user_mint_token_account.amount;
-    let redeem = ticket.requested_amount.min(current_mint_amount);
-    require!(redeem > 0, CustomErrorCode::InsufficientUnbondingBalance);
+    let mut tickets: Vec<Account<'info, UnbondingTicket>> =
+        Vec::with_capacity(ctx.remaining_accounts.len());
+    let mut total: u64 = 0;
+    for (i, ticket_info) in ctx.remaining_accounts.iter().enumerate() {
+        require!(
+            !ctx.remaining_accounts[..i]
+                .iter()
+                .any(|other| other.key == ticket_info.key),
+            CustomErrorCode::DuplicateTicket
+        );
+
+        let ticket = Account::<UnbondingTicket>::try_from(ticket_info)?;
+        require_keys_eq!(ticket.owner, signer, CustomErrorCode::InvalidTicketOwner);
+        require!(
+            now >= ticket.unlock_ts,
+            CustomErrorCode::UnbondingPeriodNotElapsed
+        );
+        require!(!ticket.queued, CustomErrorCode::TicketQueued);
+        require!(ticket.remaining_amount > 0, CustomErrorCode::TicketNotMigrated);
+
+        total = total
+            .checked_add(ticket.remaining_amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        tickets.push(ticket);
+    }
 
+    let payout = ctx
+        .accounts
+        .config
+        .to_assets(total, ctx.accounts.mint.supply)?;
     require!(
-        ctx.accounts.vault_token_account.amount >= redeem,
+        ctx.accounts.vault_token_account.amount >= payout,
         CustomErrorCode::InsufficientVaultBalance
     );
 
-    let burn_accounts = Burn {
-        mint: ctx.accounts.mint.to_account_info(),
-        from: ctx.accounts.user_mint_token_account.to_account_info(),
-        authority: ctx.accounts.signer.to_account_info(),
-    };
-    token::burn(
-        CpiContext::new(ctx.accounts.token_program.to_account_info(), burn_accounts),
-        redeem,
+    release_escrow(
+        &ctx.accounts.token_program,
+        &ctx.accounts.mint,
+        &ctx.accounts.stake_escrow_token_account,
+        &ctx.accounts.vault_token_account,
+        &ctx.accounts.user_vault_token_account,
+        &ctx.accounts.vault_authority,
+        ctx.bumps.vault_authority,
+        total,
+        payout,
     )?;
+    ctx.accounts.config.record_withdrawal(payout);
+
+    for ticket in tickets {
+        emit!(RedeemEvent {
+            user: signer,
+            amount: ticket.remaining_amount,
+            mint: ctx.accounts.mint.key(),
+            vault: ctx.accounts.vault_token_account.key(),
+            nonce: ticket.nonce,
+            remaining: 0,
+        });
+        // return rent to user when done
+        ticket.close(ctx.accounts.signer.to_account_info())?;
+    }
+
+    Ok(())
+}
+
+// Close an open ticket and hand the escrowed stake tokens back to the owner, who stays staked
+pub fn cancel_unbond(ctx: Context<CancelUnbond>) -> Result<()> {
+    ctx.accounts.config.require_not_paused(PAUSE_UNBOND)?;
+    let ticket = &ctx.accounts.ticket;
+    require_keys_eq!(
+        ticket.owner,
+        ctx.accounts.signer.key(),
+        CustomErrorCode::InvalidTicketOwner
+    );
+
+    // Queued tickets hold a place the queue head has to reach, so they can't be withdrawn
+    require!(!ticket.queued, CustomErrorCode::TicketQueued);
+
+    let amount = ticket.remaining_amount;
+    require!(amount > 0, CustomErrorCode::TicketNotMigrated);
 
     let seeds: &[&[u8]] = &[b"vault_authority", &[ctx.bumps.vault_authority]];
-    let signer = &[&seeds[..]];
+    let signer = &[seeds];
     let transfer_accounts = Transfer {
-        from: ctx.accounts.vault_token_account.to_account_info(),
-        to: ctx.accounts.user_vault_token_account.to_account_info(),
+        from: ctx.accounts.stake_escrow_token_account.to_account_info(),
+        to: ctx.accounts.user_mint_token_account.to_account_info(),
         authority: ctx.accounts.vault_authority.to_account_info(),
     };
     token::transfer(
@@ -213,117 +728,886 @@ pub fn redeem(ctx: Context<Redeem>) -> Result<()> {
             transfer_accounts,
             signer,
         ),
-        redeem,
+        amount,
     )?;
 
-    emit!(RedeemEvent {
+    emit!(UnbondCancelled {
         user: ctx.accounts.signer.key(),
-        amount: redeem,
+        amount,
         mint: ctx.accounts.mint.key(),
-        vault: ctx.accounts.vault_token_account.key(),
+        vault: ctx.accounts.config.vault,
+        nonce: ticket.nonce,
     });
 
     Ok(())
 }
 
-// Set the mint token's freeze authority to the program PDA
-// Update the list of freeze administrators (only program update authority can do this)
-pub fn update_freeze_administrators(
-    ctx: Context<UpdateFreezeAdministrators>,
-    new_administrators: Vec<Pubkey>,
-) -> Result<()> {
-    // Validate that the signer is the program's update authority
-    validate_program_update_authority(&ctx.accounts.program_data, &ctx.accounts.signer)?;
-
-    let config = &mut ctx.accounts.config;
+pub fn initialize_withdrawal_queue(ctx: Context<InitializeWithdrawalQueue>) -> Result<()> {
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
 
-    require!(
-        new_administrators.len() <= MAX_ADMINISTRATORS,
-        CustomErrorCode::TooManyAdministrators
-    );
+    let queue = &mut ctx.accounts.withdrawal_queue;
+    queue.head = 0;
+    queue.tail = 0;
+    queue.queued_amount = 0;
+    queue.bump = ctx.bumps.withdrawal_queue;
 
-    config.freeze_administrators = new_administrators;
+    emit!(WithdrawalQueueInitialized {
+        admin: ctx.accounts.signer.key(),
+        withdrawal_queue: queue.key(),
+        mint: ctx.accounts.config.mint,
+        vault: ctx.accounts.config.vault,
+    });
 
-    msg!(
-        "Freeze administrators updated. New count: {}",
-        config.freeze_administrators.len()
-    );
+    msg!("Withdrawal queue {} initialized", queue.key());
     Ok(())
 }
 
-// Set the mint token's rewards authority to the program PDA
-// Update the list of rewards administrators (only program update authority can do this)
-pub fn update_rewards_administrators(
-    ctx: Context<UpdateRewardsAdministrators>,
-    new_administrators: Vec<Pubkey>,
-) -> Result<()> {
-    // Validate that the signer is the program's update authority
-    validate_program_update_authority(&ctx.accounts.program_data, &ctx.accounts.signer)?;
-
-    let config = &mut ctx.accounts.config;
+// Top up the vault with vault tokens (e.g. wYLDS) returned from external strategies
+pub fn fund_queue(ctx: Context<FundQueue>, amount: u64) -> Result<()> {
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+    require!(amount > 0, CustomErrorCode::InvalidAmount);
 
-    require!(
-        new_administrators.len() <= MAX_ADMINISTRATORS,
-        CustomErrorCode::TooManyAdministrators
-    );
+    let transfer_accounts = Transfer {
+        from: ctx.accounts.funder_vault_token_account.to_account_info(),
+        to: ctx.accounts.vault_token_account.to_account_info(),
+        authority: ctx.accounts.signer.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer_accounts),
+        amount,
+    )?;
 
-    config.rewards_administrators = new_administrators;
+    emit!(WithdrawalQueueFunded {
+        admin: ctx.accounts.signer.key(),
+        amount,
+        queued_amount: ctx.accounts.withdrawal_queue.queued_amount,
+        vault: ctx.accounts.vault_token_account.key(),
+    });
 
-    msg!(
-        "Rewards administrators updated. New count: {}",
-        config.freeze_administrators.len()
-    );
     Ok(())
 }
 
-// Freeze a specific token account (only freeze administrators can do this)
-pub fn freeze_token_account(ctx: Context<FreezeTokenAccount>) -> Result<()> {
-    let config = &ctx.accounts.config;
-    let signer = ctx.accounts.signer.key();
-
-    // Verify signer is a freeze administrator
+// Pay the ticket at the head of the withdrawal queue as far as the vault allows. The head only
+// moves on once its ticket is fully paid, which keeps settlement strictly first in, first out.
+pub fn settle_queue(ctx: Context<SettleQueue>) -> Result<()> {
+    ctx.accounts.config.require_not_paused(PAUSE_REDEEM)?;
+    let queue = &ctx.accounts.withdrawal_queue;
+    let ticket = &ctx.accounts.ticket;
+    require!(ticket.queued, CustomErrorCode::NotQueueHead);
     require!(
-        config.freeze_administrators.contains(&signer),
-        CustomErrorCode::UnauthorizedFreezeAdministrator
+        ticket.queue_position == queue.head,
+        CustomErrorCode::NotQueueHead
     );
 
-    let freeze_authority_seeds: &[&[&[u8]]] =
-        &[&[b"freeze_authority", &[ctx.bumps.freeze_authority_pda]]];
+    let remaining = ticket.remaining_amount;
+    let supply = ctx.accounts.mint.supply;
+    let settle = remaining.min(
+        ctx.accounts
+            .config
+            .to_shares(ctx.accounts.vault_token_account.amount, supply)?,
+    );
+    require!(settle > 0, CustomErrorCode::InsufficientVaultBalance);
+    let payout = ctx.accounts.config.to_assets(settle, supply)?;
+
+    release_escrow(
+        &ctx.accounts.token_program,
+        &ctx.accounts.mint,
+        &ctx.accounts.stake_escrow_token_account,
+        &ctx.accounts.vault_token_account,
+        &ctx.accounts.owner_vault_token_account,
+        &ctx.accounts.vault_authority,
+        ctx.bumps.vault_authority,
+        settle,
+        payout,
+    )?;
+    ctx.accounts.config.record_withdrawal(payout);
 
-    let cpi_accounts = token::FreezeAccount {
-        account: ctx.accounts.token_account.to_account_info(),
-        mint: ctx.accounts.mint.to_account_info(),
-        authority: ctx.accounts.freeze_authority_pda.to_account_info(),
-    };
+    let queue = &mut ctx.accounts.withdrawal_queue;
+    queue.queued_amount = queue.queued_amount.saturating_sub(settle);
 
-    let cpi_ctx = CpiContext::new_with_signer(
-        ctx.accounts.token_program.to_account_info(),
-        cpi_accounts,
-        freeze_authority_seeds,
-    );
+    let ticket = &mut ctx.accounts.ticket;
+    ticket.remaining_amount = remaining - settle;
+
+    emit!(WithdrawalSettled {
+        user: ticket.owner,
+        nonce: ticket.nonce,
+        position: ticket.queue_position,
+        amount: settle,
+        remaining: ticket.remaining_amount,
+        mint: ctx.accounts.mint.key(),
+        vault: ctx.accounts.vault_token_account.key(),
+    });
 
-    token::freeze_account(cpi_ctx)?;
+    if ticket.remaining_amount == 0 {
+        queue.head = queue
+            .head
+            .checked_add(1)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        // return rent to the ticket owner when done
+        ticket.close(ctx.accounts.owner.to_account_info())?;
+    }
 
-    msg!(
-        "Token account {} frozen by administrator {}",
-        ctx.accounts.token_account.key(),
-        signer
-    );
     Ok(())
 }
 
-// Thaw a specific token account (only freeze administrators can do this)
-pub fn thaw_token_account(ctx: Context<ThawTokenAccount>) -> Result<()> {
-    let config = &ctx.accounts.config;
-    let signer = ctx.accounts.signer.key();
+// Burn escrowed stake tokens and pay out their value in vault tokens from the vault
+#[allow(clippy::too_many_arguments)]
+fn release_escrow<'info>(
+    token_program: &Program<'info, Token>,
+    mint: &Account<'info, Mint>,
+    stake_escrow_token_account: &Account<'info, TokenAccount>,
+    vault_token_account: &Account<'info, TokenAccount>,
+    user_vault_token_account: &Account<'info, TokenAccount>,
+    vault_authority: &UncheckedAccount<'info>,
+    vault_authority_bump: u8,
+    shares: u64,
+    assets: u64,
+) -> Result<()> {
+    let seeds: &[&[u8]] = &[b"vault_authority", &[vault_authority_bump]];
+    let signer = &[seeds];
+    let burn_accounts = Burn {
+        mint: mint.to_account_info(),
+        from: stake_escrow_token_account.to_account_info(),
+        authority: vault_authority.to_account_info(),
+    };
+    token::burn(
+        CpiContext::new_with_signer(token_program.to_account_info(), burn_accounts, signer),
+        shares,
+    )?;
 
-    // Verify signer is a freeze administrator
-    require!(
-        config.freeze_administrators.contains(&signer),
-        CustomErrorCode::UnauthorizedFreezeAdministrator
-    );
+    let transfer_accounts = Transfer {
+        from: vault_token_account.to_account_info(),
+        to: user_vault_token_account.to_account_info(),
+        authority: vault_authority.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new_with_signer(token_program.to_account_info(), transfer_accounts, signer),
+        assets,
+    )?;
 
-    let freeze_authority_seeds: &[&[&[u8]]] =
+    Ok(())
+}
+
+// Exit without waiting out the unbonding period. The user burns stake tokens and is paid their
+// value in vault tokens minus the exit fee, which goes to the treasury. Payouts are limited per
+// epoch and can never dip into vault tokens already owed to the withdrawal queue.
+pub fn instant_unbond(ctx: Context<InstantUnbond>, amount: u64) -> Result<()> {
+    require!(amount > 0, CustomErrorCode::InvalidAmount);
+    ctx.accounts.config.require_not_paused(PAUSE_INSTANT_UNBOND)?;
+    require!(
+        ctx.accounts.config.instant_unbond_epoch_cap > 0,
+        CustomErrorCode::InstantUnbondDisabled
+    );
+
+    let supply = ctx.accounts.mint.supply;
+    let config = &ctx.accounts.config;
+    let assets = config.to_assets(amount, supply)?;
+    require!(assets > 0, CustomErrorCode::InvalidAmount);
+    let owed = config.to_assets(ctx.accounts.withdrawal_queue.queued_amount, supply)?;
+    let available = ctx.accounts.vault_token_account.amount.saturating_sub(owed);
+    require!(assets <= available, CustomErrorCode::InsufficientVaultBalance);
+
+    let epoch = Clock::get()?.epoch;
+    let config = &mut ctx.accounts.config;
+    if config.instant_unbond_epoch != epoch {
+        config.instant_unbond_epoch = epoch;
+        config.instant_unbond_epoch_used = 0;
+    }
+    let used = config
+        .instant_unbond_epoch_used
+        .checked_add(assets)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    require!(
+        used <= config.instant_unbond_epoch_cap,
+        CustomErrorCode::InstantUnbondCapExceeded
+    );
+    config.instant_unbond_epoch_used = used;
+    config.record_withdrawal(assets);
+
+    // round the fee up so small exits can't avoid it
+    let fee = (assets as u128 * config.instant_unbond_fee_bps as u128)
+        .div_ceil(BPS_DENOMINATOR as u128) as u64;
+    let payout = assets - fee;
+    // exits too small to pay anything after the fee would burn stake tokens for nothing
+    require!(payout > 0, CustomErrorCode::InvalidAmount);
+
+    let burn_accounts = Burn {
+        mint: ctx.accounts.mint.to_account_info(),
+        from: ctx.accounts.user_mint_token_account.to_account_info(),
+        authority: ctx.accounts.signer.to_account_info(),
+    };
+    token::burn(
+        CpiContext::new(ctx.accounts.token_program.to_account_info(), burn_accounts),
+        amount,
+    )?;
+
+    let seeds: &[&[u8]] = &[b"vault_authority", &[ctx.bumps.vault_authority]];
+    let signer = &[seeds];
+    let transfer_accounts = Transfer {
+        from: ctx.accounts.vault_token_account.to_account_info(),
+        to: ctx.accounts.user_vault_token_account.to_account_info(),
+        authority: ctx.accounts.vault_authority.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            transfer_accounts,
+            signer,
+        ),
+        payout,
+    )?;
+    if fee > 0 {
+        let transfer_accounts = Transfer {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            to: ctx.accounts.treasury_token_account.to_account_info(),
+            authority: ctx.accounts.vault_authority.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                transfer_accounts,
+                signer,
+            ),
+            fee,
+        )?;
+    }
+
+    emit!(InstantUnbondEvent {
+        user: ctx.accounts.signer.key(),
+        amount,
+        fee,
+        treasury: ctx.accounts.treasury_token_account.key(),
+        epoch,
+        mint: ctx.accounts.mint.key(),
+        vault: ctx.accounts.vault_token_account.key(),
+    });
+
+    Ok(())
+}
+
+pub fn update_instant_unbond_config(
+    ctx: Context<UpdateInstantUnbondConfig>,
+    fee_bps: u16,
+    epoch_cap: u64,
+) -> Result<()> {
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+    ctx.accounts.config.require_no_timelock()?;
+    require!(fee_bps <= MAX_INSTANT_UNBOND_FEE_BPS, CustomErrorCode::InvalidFee);
+
+    let config = &mut ctx.accounts.config;
+    let old_fee_bps = config.instant_unbond_fee_bps;
+    let old_epoch_cap = config.instant_unbond_epoch_cap;
+    let old_treasury = config.treasury;
+    config.instant_unbond_fee_bps = fee_bps;
+    config.treasury = ctx.accounts.treasury_token_account.key();
+    config.instant_unbond_epoch_cap = epoch_cap;
+
+    emit!(InstantUnbondConfigUpdated {
+        admin: ctx.accounts.signer.key(),
+        old_fee_bps,
+        new_fee_bps: fee_bps,
+        old_epoch_cap,
+        new_epoch_cap: epoch_cap,
+        old_treasury,
+        new_treasury: config.treasury,
+        mint: config.mint,
+        vault: config.vault,
+    });
+
+    msg!(
+        "Instant unbond fee: {} bps, epoch cap: {}, treasury: {}",
+        fee_bps,
+        epoch_cap,
+        config.treasury
+    );
+
+    Ok(())
+}
+
+// Switch between 1:1 pricing and exchange-rate mode. Enabling starts the rate at par by setting
+// total assets to the current stake token supply. Disabling is only allowed while the rate is
+// still at par, otherwise holders would be repriced.
+pub fn set_exchange_rate_mode(ctx: Context<SetExchangeRateMode>, enabled: bool) -> Result<()> {
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+
+    let supply = ctx.accounts.mint.supply;
+    let config = &mut ctx.accounts.config;
+    if enabled && !config.exchange_rate_mode {
+        config.total_assets = supply;
+    } else if !enabled && config.exchange_rate_mode {
+        require!(
+            config.total_assets == supply,
+            CustomErrorCode::InvalidExchangeRate
+        );
+        config.total_assets = 0;
+    }
+    config.exchange_rate_mode = enabled;
+
+    emit!(ExchangeRateModeUpdated {
+        admin: ctx.accounts.signer.key(),
+        enabled,
+        total_assets: config.total_assets,
+        supply,
+        mint: config.mint,
+        vault: config.vault,
+    });
+
+    msg!(
+        "Exchange-rate mode: {}, total assets: {}, supply: {}",
+        enabled,
+        config.total_assets,
+        supply
+    );
+
+    Ok(())
+}
+
+// Credit yield earned by the vault's strategies to every stake token holder by raising total assets
+pub fn report_yield(ctx: Context<ReportYield>, amount: u64) -> Result<()> {
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+    require!(amount > 0, CustomErrorCode::InvalidAmount);
+    require!(
+        ctx.accounts.config.exchange_rate_mode,
+        CustomErrorCode::ExchangeRateModeDisabled
+    );
+    // with no stake tokens out, the yield would go to whoever deposits next
+    require!(ctx.accounts.mint.supply > 0, CustomErrorCode::NoStakeTokenSupply);
+
+    let config = &mut ctx.accounts.config;
+    config.total_assets = config
+        .total_assets
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    emit!(YieldReported {
+        admin: ctx.accounts.signer.key(),
+        amount,
+        total_assets: config.total_assets,
+        supply: ctx.accounts.mint.supply,
+        mint: config.mint,
+        vault: config.vault,
+    });
+
+    Ok(())
+}
+
+// Deployments created before funded epochs keep minting unbacked rewards until this is switched on
+pub fn set_funded_rewards(ctx: Context<SetFundedRewards>, enabled: bool) -> Result<()> {
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+    let config = &mut ctx.accounts.config;
+    let old_value = config.funded_rewards;
+    config.funded_rewards = enabled;
+
+    emit!(FundedRewardsUpdated {
+        admin: ctx.accounts.signer.key(),
+        old_value,
+        new_value: enabled,
+        mint: config.mint,
+        vault: config.vault,
+    });
+
+    msg!("Funded rewards epochs: {}", enabled);
+
+    Ok(())
+}
+
+// Epochs created before claims were counted can't be claimed from until they are migrated. The
+// admin supplies the amount already claimed, tallied off-chain from RewardsClaimed events.
+pub fn migrate_rewards_epoch(ctx: Context<MigrateRewardsEpoch>, claimed: u64) -> Result<()> {
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+
+    let info = ctx.accounts.epoch.to_account_info();
+    let old_len = info.data_len();
+    require!(
+        old_len < RewardsEpoch::LEN,
+        CustomErrorCode::AccountAlreadyMigrated
+    );
+    let mut epoch = load_zero_extended::<RewardsEpoch>(&info, RewardsEpoch::LEN)?;
+    require!(
+        claimed <= epoch.total,
+        CustomErrorCode::RewardsEpochTotalExceeded
+    );
+    epoch.claimed = claimed;
+
+    resize_account(&info, &ctx.accounts.signer, &ctx.accounts.system_program, RewardsEpoch::LEN)?;
+    epoch.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+    emit!(RewardsEpochMigrated {
+        admin: ctx.accounts.signer.key(),
+        epoch: epoch.index,
+        old_len: old_len as u64,
+        new_len: RewardsEpoch::LEN as u64,
+        claimed: epoch.claimed,
+        mint: ctx.accounts.config.mint,
+        vault: ctx.accounts.config.vault,
+    });
+
+    msg!("Rewards epoch {} migrated, claimed: {}", epoch.index, epoch.claimed);
+    Ok(())
+}
+
+// Choose the merkle hashing scheme for rewards epochs created from now on. Existing epochs keep
+// the scheme they were created with.
+pub fn set_rewards_hash_version(ctx: Context<SetRewardsHashVersion>, hash_version: u8) -> Result<()> {
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+    require!(
+        hash_version <= HASH_VERSION_PREFIXED_SORTED,
+        CustomErrorCode::InvalidHashVersion
+    );
+    let config = &mut ctx.accounts.config;
+    let old_value = config.rewards_hash_version;
+    config.rewards_hash_version = hash_version;
+
+    emit!(RewardsHashVersionUpdated {
+        admin: ctx.accounts.signer.key(),
+        old_value,
+        new_value: hash_version,
+        mint: config.mint,
+        vault: config.vault,
+    });
+
+    msg!("Rewards hash version: {}", hash_version);
+
+    Ok(())
+}
+
+// Cap the new rewards a single epoch can issue, as bps of the vault balance and as an absolute
+// amount. Zero turns either cap off.
+pub fn update_epoch_rewards_cap(
+    ctx: Context<UpdateEpochRewardsCap>,
+    max_bps: u16,
+    max_amount: u64,
+) -> Result<()> {
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+    ctx.accounts.config.require_no_timelock()?;
+    require!(
+        max_bps as u64 <= BPS_DENOMINATOR,
+        CustomErrorCode::InvalidRewardsCap
+    );
+    let config = &mut ctx.accounts.config;
+    let old_max_bps = config.max_epoch_rewards_bps;
+    let old_max_amount = config.max_epoch_rewards;
+    config.max_epoch_rewards_bps = max_bps;
+    config.max_epoch_rewards = max_amount;
+
+    emit!(EpochRewardsCapUpdated {
+        admin: ctx.accounts.signer.key(),
+        old_max_bps,
+        new_max_bps: max_bps,
+        old_max_amount,
+        new_max_amount: max_amount,
+        mint: config.mint,
+        vault: config.vault,
+    });
+
+    msg!(
+        "Epoch rewards cap: {} bps of the vault, {} absolute",
+        max_bps,
+        max_amount
+    );
+
+    Ok(())
+}
+
+// Make classic rewards epochs created from now on vest their claims over `duration` seconds, or
+// mint them at once with 0. Existing epochs keep the duration they were created with.
+pub fn set_rewards_vesting_duration(
+    ctx: Context<SetRewardsVestingDuration>,
+    duration: i64,
+) -> Result<()> {
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+    require!(duration >= 0, CustomErrorCode::InvalidVestingDuration);
+    let config = &mut ctx.accounts.config;
+    let old_value = config.rewards_vesting_duration;
+    config.rewards_vesting_duration = duration;
+
+    emit!(RewardsVestingDurationUpdated {
+        admin: ctx.accounts.signer.key(),
+        old_value,
+        new_value: duration,
+        mint: config.mint,
+        vault: config.vault,
+    });
+
+    msg!("Rewards vesting duration: {}", duration);
+
+    Ok(())
+}
+
+// Require rewards epochs to be created in order from `next_epoch_index`. Deployments with existing
+// out-of-order epochs start past the highest existing index. The index only moves forward, so
+// indexers never see an index reused and sequential epochs cannot be switched off again.
+pub fn set_next_epoch_index(ctx: Context<SetNextEpochIndex>, next_epoch_index: u64) -> Result<()> {
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+    let config = &mut ctx.accounts.config;
+    let old_value = config.next_epoch_index;
+    require!(
+        next_epoch_index >= old_value,
+        CustomErrorCode::InvalidEpochIndex
+    );
+    config.next_epoch_index = next_epoch_index;
+
+    emit!(NextEpochIndexUpdated {
+        admin: ctx.accounts.signer.key(),
+        old_value,
+        new_value: next_epoch_index,
+        mint: config.mint,
+        vault: config.vault,
+    });
+
+    msg!("Next epoch index: {}", next_epoch_index);
+
+    Ok(())
+}
+
+// Set how many rewards admins must approve a draft epoch before it can be published. Above one,
+// epochs can no longer be created already published.
+pub fn set_rewards_approval_threshold(
+    ctx: Context<SetRewardsApprovalThreshold>,
+    threshold: u8,
+) -> Result<()> {
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+    ctx.accounts.config.require_no_timelock()?;
+    let config = &mut ctx.accounts.config;
+    require!(
+        threshold as usize <= config.rewards_administrators.len(),
+        CustomErrorCode::InvalidApprovalThreshold
+    );
+    let old_value = config.rewards_approval_threshold;
+    config.rewards_approval_threshold = threshold;
+
+    emit!(RewardsApprovalThresholdUpdated {
+        admin: ctx.accounts.signer.key(),
+        old_value,
+        new_value: threshold,
+        mint: config.mint,
+        vault: config.vault,
+    });
+
+    msg!("Rewards approval threshold: {}", threshold);
+
+    Ok(())
+}
+
+// Grow the config account to the current layout. Fields appended since the account was created
+// read back as zero, which keeps their legacy behaviour until an admin sets them.
+pub fn migrate_config(ctx: Context<MigrateConfig>) -> Result<()> {
+    validate_program_update_authority(&ctx.accounts.program_data, &ctx.accounts.signer)?;
+
+    let info = ctx.accounts.config.to_account_info();
+    let old_len = info.data_len();
+    // make sure the account still parses before touching it
+    let config = load_zero_extended::<Config>(&info, Config::LEN)?;
+    resize_account(&info, &ctx.accounts.signer, &ctx.accounts.system_program, Config::LEN)?;
+
+    emit!(ConfigMigrated {
+        admin: ctx.accounts.signer.key(),
+        old_len: old_len as u64,
+        new_len: info.data_len() as u64,
+        mint: config.mint,
+        vault: config.vault,
+    });
+
+    msg!("Config migrated from {} to {} bytes", old_len, info.data_len());
+    Ok(())
+}
+
+// Create the program-owned token account that holds stake tokens for open unbonding tickets
+pub fn initialize_stake_escrow(ctx: Context<InitializeStakeEscrow>) -> Result<()> {
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+
+    emit!(StakeEscrowInitialized {
+        admin: ctx.accounts.signer.key(),
+        stake_escrow: ctx.accounts.stake_escrow_token_account.key(),
+        mint: ctx.accounts.mint.key(),
+        vault: ctx.accounts.config.vault,
+    });
+
+    msg!(
+        "Stake escrow {} initialized for mint {}",
+        ctx.accounts.stake_escrow_token_account.key(),
+        ctx.accounts.mint.key()
+    );
+    Ok(())
+}
+
+// Tickets opened before tickets were keyed by nonce live at [b"ticket", owner]. Migrating moves
+// one onto the owner's next nonce, keeping its original start time. Those tickets left the stake
+// tokens in the user's wallet, so they are escrowed now (up to the requested amount), and the
+// unlock time comes from the unbonding period in effect at migration.
+pub fn migrate_ticket(ctx: Context<MigrateTicket>) -> Result<()> {
+    ctx.accounts.config.require_not_paused(PAUSE_UNBOND)?;
+
+    let legacy_info = ctx.accounts.legacy_ticket.to_account_info();
+    let legacy: UnbondingTicket = load_zero_extended(&legacy_info, UnbondingTicket::LEN)?;
+    require_keys_eq!(
+        legacy.owner,
+        ctx.accounts.signer.key(),
+        CustomErrorCode::InvalidTicketOwner
+    );
+
+    let escrowed_amount = legacy
+        .requested_amount
+        .min(ctx.accounts.user_mint_token_account.amount);
+    if escrowed_amount > 0 {
+        let transfer_accounts = Transfer {
+            from: ctx.accounts.user_mint_token_account.to_account_info(),
+            to: ctx.accounts.stake_escrow_token_account.to_account_info(),
+            authority: ctx.accounts.signer.to_account_info(),
+        };
+        token::transfer(
+            CpiContext::new(ctx.accounts.token_program.to_account_info(), transfer_accounts),
+            escrowed_amount,
+        )?;
+    }
+
+    close_account(&legacy_info, &ctx.accounts.signer.to_account_info())?;
+
+    if escrowed_amount == 0 {
+        // Nothing left to back the ticket with, so it could never be redeemed
+        msg!("Ticket {} has no stake tokens to escrow, closing", legacy_info.key());
+        return ctx
+            .accounts
+            .ticket
+            .close(ctx.accounts.signer.to_account_info());
+    }
+
+    let user_state = &mut ctx.accounts.user_state;
+    user_state.owner = ctx.accounts.signer.key();
+    user_state.bump = ctx.bumps.user_state;
+    let nonce = user_state.next_ticket_nonce;
+    user_state.next_ticket_nonce = nonce
+        .checked_add(1)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    let ticket = &mut ctx.accounts.ticket;
+    ticket.owner = legacy.owner;
+    ticket.requested_amount = legacy.requested_amount;
+    ticket.start_balance = legacy.start_balance;
+    ticket.start_ts = legacy.start_ts;
+    ticket.remaining_amount = escrowed_amount;
+    ticket.nonce = nonce;
+    ticket.unlock_ts = legacy
+        .start_ts
+        .checked_add(ctx.accounts.config.unbonding_period)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    emit!(TicketMigrated {
+        user: ticket.owner,
+        legacy_ticket: legacy_info.key(),
+        ticket: ticket.key(),
+        nonce,
+        escrowed_amount,
+        unlock_ts: ticket.unlock_ts,
+        mint: ctx.accounts.config.mint,
+        vault: ctx.accounts.config.vault,
+    });
+
+    msg!(
+        "Ticket {} migrated to {} with {} stake tokens escrowed",
+        legacy_info.key(),
+        ticket.key(),
+        escrowed_amount
+    );
+    Ok(())
+}
+
+// Starts the admin handover; the new admin must accept it. Proposing the default pubkey
+// cancels a pending handover. While a timelock is set, handovers go through propose_config_change.
+pub fn propose_admin(ctx: Context<ProposeAdmin>, new_admin: Pubkey) -> Result<()> {
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+    ctx.accounts.config.require_no_timelock()?;
+    let config = &mut ctx.accounts.config;
+    config.pending_admin = new_admin;
+
+    emit!(AdminProposed {
+        admin: ctx.accounts.signer.key(),
+        pending_admin: new_admin,
+        mint: config.mint,
+        vault: config.vault,
+    });
+
+    Ok(())
+}
+
+pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+    let config = &mut ctx.accounts.config;
+    require!(
+        config.pending_admin != Pubkey::default() && ctx.accounts.signer.key() == config.pending_admin,
+        CustomErrorCode::InvalidPendingAdmin
+    );
+    let old_admin = config.admin;
+    config.admin = config.pending_admin;
+    config.pending_admin = Pubkey::default();
+
+    emit!(AdminTransferred {
+        old_admin,
+        new_admin: config.admin,
+        mint: config.mint,
+        vault: config.vault,
+    });
+
+    Ok(())
+}
+
+// Set the mint token's freeze authority to the program PDA
+// Update the list of freeze administrators (only the protocol admin can do this)
+pub fn update_freeze_administrators(
+    ctx: Context<UpdateFreezeAdministrators>,
+    new_administrators: Vec<Pubkey>,
+) -> Result<()> {
+    // Validate that the signer is the protocol admin, or the update authority while none is set
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+    ctx.accounts.config.require_no_timelock()?;
+
+    let config = &mut ctx.accounts.config;
+
+    require!(
+        new_administrators.len() <= MAX_ADMINISTRATORS,
+        CustomErrorCode::TooManyAdministrators
+    );
+
+    let old_administrators = std::mem::replace(&mut config.freeze_administrators, new_administrators);
+
+    emit!(FreezeAdministratorsUpdated {
+        admin: ctx.accounts.signer.key(),
+        old_administrators,
+        new_administrators: config.freeze_administrators.clone(),
+        mint: config.mint,
+        vault: config.vault,
+    });
+
+    msg!(
+        "Freeze administrators updated. New count: {}",
+        config.freeze_administrators.len()
+    );
+    Ok(())
+}
+
+// Update the list of pause guardians, who can pause operations but not resume them
+pub fn update_pause_guardians(
+    ctx: Context<UpdatePauseGuardians>,
+    new_guardians: Vec<Pubkey>,
+) -> Result<()> {
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+    ctx.accounts.config.require_no_timelock()?;
+    require!(
+        new_guardians.len() <= MAX_ADMINISTRATORS,
+        CustomErrorCode::TooManyAdministrators
+    );
+
+    let config = &mut ctx.accounts.config;
+    let old_guardians = std::mem::replace(&mut config.pause_guardians, new_guardians);
+
+    emit!(PauseGuardiansUpdated {
+        admin: ctx.accounts.signer.key(),
+        old_guardians,
+        new_guardians: config.pause_guardians.clone(),
+        mint: config.mint,
+        vault: config.vault,
+    });
+
+    msg!(
+        "Pause guardians updated. New count: {}",
+        config.pause_guardians.len()
+    );
+    Ok(())
+}
+
+// Set the mint token's rewards authority to the program PDA
+// Update the list of rewards administrators (only the protocol admin can do this)
+pub fn update_rewards_administrators(
+    ctx: Context<UpdateRewardsAdministrators>,
+    new_administrators: Vec<Pubkey>,
+) -> Result<()> {
+    // Validate that the signer is the protocol admin, or the update authority while none is set
+    validate_admin(&ctx.accounts.config, &ctx.accounts.program_data, &ctx.accounts.signer)?;
+    ctx.accounts.config.require_no_timelock()?;
+
+    let config = &mut ctx.accounts.config;
+
+    require!(
+        new_administrators.len() <= MAX_ADMINISTRATORS,
+        CustomErrorCode::TooManyAdministrators
+    );
+
+    require!(
+        new_administrators.len() >= config.rewards_approval_threshold as usize,
+        CustomErrorCode::InvalidApprovalThreshold
+    );
+
+    let old_administrators = std::mem::replace(&mut config.rewards_administrators, new_administrators);
+
+    emit!(RewardsAdministratorsUpdated {
+        admin: ctx.accounts.signer.key(),
+        old_administrators,
+        new_administrators: config.rewards_administrators.clone(),
+        mint: config.mint,
+        vault: config.vault,
+    });
+
+    msg!(
+        "Rewards administrators updated. New count: {}",
+        config.rewards_administrators.len()
+    );
+    Ok(())
+}
+
+// Freeze a specific token account (only freeze administrators can do this)
+pub fn freeze_token_account(ctx: Context<FreezeTokenAccount>) -> Result<()> {
+    let config = &ctx.accounts.config;
+    let signer = ctx.accounts.signer.key();
+
+    // Verify signer is a freeze administrator
+    require!(
+        config.freeze_administrators.contains(&signer),
+        CustomErrorCode::UnauthorizedFreezeAdministrator
+    );
+
+    let freeze_authority_seeds: &[&[&[u8]]] =
+        &[&[b"freeze_authority", &[ctx.bumps.freeze_authority_pda]]];
+
+    let cpi_accounts = token::FreezeAccount {
+        account: ctx.accounts.token_account.to_account_info(),
+        mint: ctx.accounts.mint.to_account_info(),
+        authority: ctx.accounts.freeze_authority_pda.to_account_info(),
+    };
+
+    let cpi_ctx = CpiContext::new_with_signer(
+        ctx.accounts.token_program.to_account_info(),
+        cpi_accounts,
+        freeze_authority_seeds,
+    );
+
+    token::freeze_account(cpi_ctx)?;
+
+    emit!(TokenAccountFrozen {
+        admin: signer,
+        token_account: ctx.accounts.token_account.key(),
+        owner: ctx.accounts.token_account.owner,
+        mint: ctx.accounts.mint.key(),
+    });
+
+    msg!(
+        "Token account {} frozen by administrator {}",
+        ctx.accounts.token_account.key(),
+        signer
+    );
+    Ok(())
+}
+
+// Thaw a specific token account (only freeze administrators can do this)
+pub fn thaw_token_account(ctx: Context<ThawTokenAccount>) -> Result<()> {
+    let config = &ctx.accounts.config;
+    let signer = ctx.accounts.signer.key();
+
+    // Verify signer is a freeze administrator
+    require!(
+        config.freeze_administrators.contains(&signer),
+        CustomErrorCode::UnauthorizedFreezeAdministrator
+    );
+
+    let freeze_authority_seeds: &[&[&[u8]]] =
         &[&[b"freeze_authority", &[ctx.bumps.freeze_authority_pda]]];
 
     let cpi_accounts = token::ThawAccount {
@@ -340,17 +1624,297 @@ pub fn thaw_token_account(ctx: Context<ThawTokenAccount>) -> Result<()> {
 
     token::thaw_account(cpi_ctx)?;
 
+    emit!(TokenAccountThawed {
+        admin: signer,
+        token_account: ctx.accounts.token_account.key(),
+        owner: ctx.accounts.token_account.owner,
+        mint: ctx.accounts.mint.key(),
+    });
+
+    msg!(
+        "Token account {} thawed by administrator {}",
+        ctx.accounts.token_account.key(),
+        signer
+    );
+    Ok(())
+}
+
+pub fn create_rewards_epoch(
+    ctx: Context<CreateRewardsEpoch>,
+    index: u64,
+    merkle_root: [u8; 32],
+    total: u64,
+    claim_start_ts: i64,
+    claim_end_ts: i64,
+    draft: bool,
+) -> Result<()> {
+    require!(
+        ctx.accounts
+            .config
+            .rewards_administrators
+            .contains(&ctx.accounts.admin.key()),
+        CustomErrorCode::InvalidRewardsAdministrator
+    );
+    let now = Clock::get()?.unix_timestamp;
+    validate_claim_window(claim_start_ts, claim_end_ts, now)?;
+    require!(
+        draft || ctx.accounts.config.rewards_approval_threshold <= 1,
+        CustomErrorCode::RewardsApprovalRequired
+    );
+    ctx.accounts.config.take_epoch_index(index)?;
+
+    // drafts are funded when they are published
+    if !draft {
+        fund_rewards_epoch(
+            &ctx.accounts.config,
+            &ctx.accounts.admin,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.admin_vault_token_account,
+            &ctx.accounts.token_program,
+            index,
+            total,
+        )?;
+    }
+
+    let e = &mut ctx.accounts.epoch;
+    e.index = index;
+    e.merkle_root = merkle_root;
+    e.total = total;
+    e.created_ts = now;
+    e.hash_version = ctx.accounts.config.rewards_hash_version;
+    e.claim_start_ts = claim_start_ts;
+    e.claim_end_ts = claim_end_ts;
+    e.draft = draft;
+    e.vesting_duration = ctx.accounts.config.rewards_vesting_duration;
+
+    emit_rewards_epoch_created(&ctx.accounts.config, ctx.accounts.admin.key(), e);
+    if draft {
+        emit!(RewardsEpochProposed {
+            admin: ctx.accounts.admin.key(),
+            epoch: index,
+            merkle_root,
+            total,
+            mint: ctx.accounts.config.mint,
+            vault: ctx.accounts.config.vault,
+        });
+    }
+    Ok(())
+}
+
+// Leaves of an indexed epoch include their position in the tree, so claims are tracked with one
+// bit per leaf in a ClaimBitmap paid for once by the admin instead of a ClaimRecord per user.
+#[allow(clippy::too_many_arguments)]
+pub fn create_indexed_rewards_epoch(
+    ctx: Context<CreateIndexedRewardsEpoch>,
+    index: u64,
+    merkle_root: [u8; 32],
+    total: u64,
+    leaf_count: u64,
+    claim_start_ts: i64,
+    claim_end_ts: i64,
+    draft: bool,
+) -> Result<()> {
+    require!(
+        ctx.accounts
+            .config
+            .rewards_administrators
+            .contains(&ctx.accounts.admin.key()),
+        CustomErrorCode::InvalidRewardsAdministrator
+    );
+    let now = Clock::get()?.unix_timestamp;
+    validate_claim_window(claim_start_ts, claim_end_ts, now)?;
+    require!(
+        draft || ctx.accounts.config.rewards_approval_threshold <= 1,
+        CustomErrorCode::RewardsApprovalRequired
+    );
+    ctx.accounts.config.take_epoch_index(index)?;
+    require!(
+        leaf_count > 0 && leaf_count <= MAX_BITMAP_LEAVES,
+        CustomErrorCode::InvalidLeafCount
+    );
+
+    // drafts are funded when they are published
+    if !draft {
+        fund_rewards_epoch(
+            &ctx.accounts.config,
+            &ctx.accounts.admin,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.admin_vault_token_account,
+            &ctx.accounts.token_program,
+            index,
+            total,
+        )?;
+    }
+
+    let bitmap = &mut ctx.accounts.claim_bitmap;
+    bitmap.epoch = ctx.accounts.epoch.key();
+    bitmap.bits = vec![0; leaf_count.div_ceil(8) as usize];
+
+    let e = &mut ctx.accounts.epoch;
+    e.index = index;
+    e.merkle_root = merkle_root;
+    e.total = total;
+    e.created_ts = now;
+    e.hash_version = ctx.accounts.config.rewards_hash_version;
+    e.claim_start_ts = claim_start_ts;
+    e.claim_end_ts = claim_end_ts;
+    e.draft = draft;
+    e.leaf_count = leaf_count;
+
+    emit_rewards_epoch_created(&ctx.accounts.config, ctx.accounts.admin.key(), e);
+    if draft {
+        emit!(RewardsEpochProposed {
+            admin: ctx.accounts.admin.key(),
+            epoch: index,
+            merkle_root,
+            total,
+            mint: ctx.accounts.config.mint,
+            vault: ctx.accounts.config.vault,
+        });
+    }
+    Ok(())
+}
+
+// A cumulative epoch's root commits to every user's lifetime rewards, so `total` is the lifetime
+// total across all users and only the increase over the previous cumulative epoch is new.
+pub fn create_cumulative_rewards_epoch(
+    ctx: Context<CreateRewardsEpoch>,
+    index: u64,
+    merkle_root: [u8; 32],
+    total: u64,
+    claim_start_ts: i64,
+    claim_end_ts: i64,
+    draft: bool,
+) -> Result<()> {
+    require!(
+        ctx.accounts
+            .config
+            .rewards_administrators
+            .contains(&ctx.accounts.admin.key()),
+        CustomErrorCode::InvalidRewardsAdministrator
+    );
+    let now = Clock::get()?.unix_timestamp;
+    validate_claim_window(claim_start_ts, claim_end_ts, now)?;
+    require!(
+        draft || ctx.accounts.config.rewards_approval_threshold <= 1,
+        CustomErrorCode::RewardsApprovalRequired
+    );
+    ctx.accounts.config.take_epoch_index(index)?;
+    let previous = ctx.accounts.config.cumulative_rewards_total;
+    require!(total >= previous, CustomErrorCode::InvalidAmount);
+
+    // drafts are funded, and raise the cumulative total, when they are published
+    if !draft {
+        fund_rewards_epoch(
+            &ctx.accounts.config,
+            &ctx.accounts.admin,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.admin_vault_token_account,
+            &ctx.accounts.token_program,
+            index,
+            total - previous,
+        )?;
+        ctx.accounts.config.cumulative_rewards_total = total;
+    }
+
+    let e = &mut ctx.accounts.epoch;
+    e.index = index;
+    e.merkle_root = merkle_root;
+    e.total = total;
+    e.created_ts = now;
+    e.hash_version = ctx.accounts.config.rewards_hash_version;
+    e.claim_start_ts = claim_start_ts;
+    e.claim_end_ts = claim_end_ts;
+    e.draft = draft;
+    e.cumulative = true;
+
+    emit_rewards_epoch_created(&ctx.accounts.config, ctx.accounts.admin.key(), e);
+    if draft {
+        emit!(RewardsEpochProposed {
+            admin: ctx.accounts.admin.key(),
+            epoch: index,
+            merkle_root,
+            total,
+            mint: ctx.accounts.config.mint,
+            vault: ctx.accounts.config.vault,
+        });
+    }
+    Ok(())
+}
+
+// A zero start opens claims right away and a zero end never expires the epoch
+fn validate_claim_window(claim_start_ts: i64, claim_end_ts: i64, now: i64) -> Result<()> {
+    require!(claim_start_ts >= 0 && claim_end_ts >= 0, CustomErrorCode::InvalidClaimWindow);
+    require!(
+        claim_end_ts == 0 || (claim_end_ts > claim_start_ts && claim_end_ts > now),
+        CustomErrorCode::InvalidClaimWindow
+    );
+    Ok(())
+}
+
+// Every way of creating an epoch reports the same event
+fn emit_rewards_epoch_created(config: &Config, admin: Pubkey, epoch: &RewardsEpoch) {
+    emit!(RewardsEpochCreated {
+        admin,
+        epoch: epoch.index,
+        merkle_root: epoch.merkle_root,
+        total: epoch.total,
+        draft: epoch.draft,
+        cumulative: epoch.cumulative,
+        leaf_count: epoch.leaf_count,
+        hash_version: epoch.hash_version,
+        claim_start_ts: epoch.claim_start_ts,
+        claim_end_ts: epoch.claim_end_ts,
+        vesting_duration: epoch.vesting_duration,
+        mint: config.mint,
+        vault: config.vault,
+    });
+}
+
+// Close an epoch whose claim window has ended and return its rent, plus that of its claim bitmap
+// and approvals. Whatever was never claimed is reported in the event for accounting.
+pub fn close_rewards_epoch(ctx: Context<CloseRewardsEpoch>) -> Result<()> {
+    require!(
+        ctx.accounts
+            .config
+            .rewards_administrators
+            .contains(&ctx.accounts.admin.key()),
+        CustomErrorCode::InvalidRewardsAdministrator
+    );
+    let epoch = &ctx.accounts.epoch;
+    require!(!epoch.draft, CustomErrorCode::RewardsEpochNotPublished);
+    require!(
+        epoch.is_expired(Clock::get()?.unix_timestamp),
+        CustomErrorCode::RewardsEpochNotExpired
+    );
+    close_claim_bitmap(epoch, &ctx.accounts.claim_bitmap, &ctx.accounts.admin)?;
+    if let Some(approvals) = &ctx.accounts.approvals {
+        close_account(&approvals.to_account_info(), &ctx.accounts.admin.to_account_info())?;
+    }
+
+    emit!(RewardsEpochClosed {
+        admin: ctx.accounts.admin.key(),
+        epoch: epoch.index,
+        total: epoch.total,
+        claimed: epoch.claimed,
+        unclaimed: epoch.total.saturating_sub(epoch.claimed),
+        cumulative: epoch.cumulative,
+        mint: ctx.accounts.config.mint,
+        vault: ctx.accounts.config.vault,
+    });
+
     msg!(
-        "Token account {} thawed by administrator {}",
-        ctx.accounts.token_account.key(),
-        signer
+        "Rewards epoch {} closed, {} unclaimed",
+        epoch.index,
+        epoch.total.saturating_sub(epoch.claimed)
     );
+
     Ok(())
 }
 
-pub fn create_rewards_epoch(
-    ctx: Context<CreateRewardsEpoch>,
-    index: u64,
+// Replace the root and total of a draft epoch, e.g. to fix an off-chain calculation error
+pub fn update_rewards_epoch(
+    ctx: Context<UpdateRewardsEpoch>,
     merkle_root: [u8; 32],
     total: u64,
 ) -> Result<()> {
@@ -361,46 +1925,244 @@ pub fn create_rewards_epoch(
             .contains(&ctx.accounts.admin.key()),
         CustomErrorCode::InvalidRewardsAdministrator
     );
-    let e = &mut ctx.accounts.epoch;
-    e.index = index;
-    e.merkle_root = merkle_root;
-    e.total = total;
-    e.created_ts = Clock::get()?.unix_timestamp;
+    let epoch = &mut ctx.accounts.epoch;
+    require!(epoch.draft, CustomErrorCode::RewardsEpochAlreadyPublished);
+
+    epoch.merkle_root = merkle_root;
+    epoch.total = total;
+
+    // a corrected draft is a new proposal; approvals of the old root no longer count
+    emit!(RewardsEpochProposed {
+        admin: ctx.accounts.admin.key(),
+        epoch: epoch.index,
+        merkle_root,
+        total,
+        mint: ctx.accounts.config.mint,
+        vault: ctx.accounts.config.vault,
+    });
+
+    msg!("Draft rewards epoch {} updated, total {}", epoch.index, total);
+
+    Ok(())
+}
+
+// Discard a draft epoch, returning its rent and freeing its index for a corrected epoch
+pub fn revoke_rewards_epoch(ctx: Context<CloseRewardsEpoch>) -> Result<()> {
+    require!(
+        ctx.accounts
+            .config
+            .rewards_administrators
+            .contains(&ctx.accounts.admin.key()),
+        CustomErrorCode::InvalidRewardsAdministrator
+    );
+    let epoch = &ctx.accounts.epoch;
+    require!(epoch.draft, CustomErrorCode::RewardsEpochAlreadyPublished);
+    close_claim_bitmap(epoch, &ctx.accounts.claim_bitmap, &ctx.accounts.admin)?;
+    // revoking the latest sequential epoch hands its index to the corrected one
+    let config = &mut ctx.accounts.config;
+    if config.latest_epoch_index() == Some(epoch.index) {
+        config.next_epoch_index = epoch.index;
+    }
+    if let Some(approvals) = &ctx.accounts.approvals {
+        close_account(&approvals.to_account_info(), &ctx.accounts.admin.to_account_info())?;
+    }
+
+    emit!(RewardsEpochRevoked {
+        admin: ctx.accounts.admin.key(),
+        epoch: epoch.index,
+        merkle_root: epoch.merkle_root,
+        total: epoch.total,
+        mint: config.mint,
+        vault: config.vault,
+    });
+
+    msg!("Draft rewards epoch {} revoked", epoch.index);
+
+    Ok(())
+}
+
+// Open a draft epoch for claims, funding it the same way it would have been at creation
+pub fn publish_rewards_epoch(ctx: Context<PublishRewardsEpoch>) -> Result<()> {
+    require!(
+        ctx.accounts
+            .config
+            .rewards_administrators
+            .contains(&ctx.accounts.admin.key()),
+        CustomErrorCode::InvalidRewardsAdministrator
+    );
+    let epoch = &ctx.accounts.epoch;
+    require!(epoch.draft, CustomErrorCode::RewardsEpochAlreadyPublished);
+    validate_claim_window(
+        epoch.claim_start_ts,
+        epoch.claim_end_ts,
+        Clock::get()?.unix_timestamp,
+    )?;
+
+    let config = &ctx.accounts.config;
+    let approvals = match &ctx.accounts.approvals {
+        Some(approvals) => approvals.count(epoch, &config.rewards_administrators),
+        None => 0,
+    };
+    // a threshold of 0 or 1 lets the publishing rewards admin act alone
+    let threshold = config.rewards_approval_threshold as usize;
+    require!(
+        threshold <= 1 || approvals >= threshold,
+        CustomErrorCode::InsufficientApprovals
+    );
+
+    let amount = if epoch.cumulative {
+        let previous = ctx.accounts.config.cumulative_rewards_total;
+        require!(epoch.total >= previous, CustomErrorCode::InvalidAmount);
+        epoch.total - previous
+    } else {
+        epoch.total
+    };
+    fund_rewards_epoch(
+        &ctx.accounts.config,
+        &ctx.accounts.admin,
+        &ctx.accounts.vault_token_account,
+        &ctx.accounts.admin_vault_token_account,
+        &ctx.accounts.token_program,
+        epoch.index,
+        amount,
+    )?;
+    if epoch.cumulative {
+        ctx.accounts.config.cumulative_rewards_total = epoch.total;
+    }
+
+    let epoch = &mut ctx.accounts.epoch;
+    epoch.draft = false;
+
+    emit!(RewardsEpochPublished {
+        admin: ctx.accounts.admin.key(),
+        epoch: epoch.index,
+        total: epoch.total,
+        approvals: approvals as u8,
+        mint: ctx.accounts.config.mint,
+        vault: ctx.accounts.config.vault,
+    });
+
+    msg!("Rewards epoch {} published", epoch.index);
+
+    Ok(())
+}
+
+// Record a rewards admin's approval of a draft's current root and total. Approvals of an earlier
+// root are discarded, since the draft was updated after they were given.
+pub fn approve_rewards_epoch(ctx: Context<ApproveRewardsEpoch>) -> Result<()> {
+    let admin = ctx.accounts.admin.key();
+    require!(
+        ctx.accounts.config.rewards_administrators.contains(&admin),
+        CustomErrorCode::InvalidRewardsAdministrator
+    );
+    let epoch = &ctx.accounts.epoch;
+    require!(epoch.draft, CustomErrorCode::RewardsEpochAlreadyPublished);
+
+    let approvals = &mut ctx.accounts.approvals;
+    if approvals.merkle_root != epoch.merkle_root || approvals.total != epoch.total {
+        approvals.merkle_root = epoch.merkle_root;
+        approvals.total = epoch.total;
+        approvals.approvers.clear();
+    }
+    approvals.epoch = epoch.key();
+    approvals.bump = ctx.bumps.approvals;
+    // drop admins that were removed since they approved, keeping room for current ones
+    let rewards_administrators = &ctx.accounts.config.rewards_administrators;
+    approvals
+        .approvers
+        .retain(|a| rewards_administrators.contains(a));
+    require!(
+        !approvals.approvers.contains(&admin),
+        CustomErrorCode::AlreadyApproved
+    );
+    approvals.approvers.push(admin);
+
+    emit!(RewardsEpochApproved {
+        admin,
+        epoch: epoch.index,
+        approvals: approvals.approvers.len() as u8,
+        threshold: ctx.accounts.config.rewards_approval_threshold,
+        mint: ctx.accounts.config.mint,
+        vault: ctx.accounts.config.vault,
+    });
+
+    Ok(())
+}
+
+// Indexed epochs own a claim bitmap that is closed along with the epoch
+fn close_claim_bitmap<'info>(
+    epoch: &RewardsEpoch,
+    claim_bitmap: &Option<Account<'info, ClaimBitmap>>,
+    admin: &Signer<'info>,
+) -> Result<()> {
+    if epoch.leaf_count == 0 {
+        return Ok(());
+    }
+    let bitmap = claim_bitmap
+        .as_ref()
+        .ok_or(CustomErrorCode::InvalidClaimAccounts)?;
+    close_account(&bitmap.to_account_info(), &admin.to_account_info())
+}
+
+// Back every claimable stake token with vault tokens up front so later redeemers aren't short.
+// Every epoch's new rewards pass through here, so this is also where the rewards cap applies.
+fn fund_rewards_epoch<'info>(
+    config: &Account<'info, Config>,
+    admin: &Signer<'info>,
+    vault_token_account: &Option<Account<'info, TokenAccount>>,
+    admin_vault_token_account: &Option<Account<'info, TokenAccount>>,
+    token_program: &Option<Program<'info, Token>>,
+    index: u64,
+    amount: u64,
+) -> Result<()> {
+    // checked against the balance before this epoch's funding lands
+    config.check_epoch_rewards_cap(amount, vault_token_account.as_ref().map(|v| v.amount))?;
+
+    if !config.funded_rewards {
+        // in exchange-rate mode every claimed reward must be backed by vault tokens
+        require!(!config.exchange_rate_mode, CustomErrorCode::RewardsFundingRequired);
+        return Ok(());
+    }
+    let (Some(vault_token_account), Some(admin_vault_token_account), Some(token_program)) =
+        (vault_token_account, admin_vault_token_account, token_program)
+    else {
+        return err!(CustomErrorCode::RewardsFundingRequired);
+    };
+
+    let transfer_accounts = Transfer {
+        from: admin_vault_token_account.to_account_info(),
+        to: vault_token_account.to_account_info(),
+        authority: admin.to_account_info(),
+    };
+    token::transfer(
+        CpiContext::new(token_program.to_account_info(), transfer_accounts),
+        amount,
+    )?;
+
+    emit!(RewardsEpochFunded {
+        admin: admin.key(),
+        epoch: index,
+        amount,
+        mint: config.mint,
+        vault: vault_token_account.key(),
+    });
     Ok(())
 }
 
 pub fn claim_rewards(ctx: Context<ClaimRewards>, amount: u64, proof: Vec<ProofNode>) -> Result<()> {
-    require!(!ctx.accounts.config.paused, CustomErrorCode::ProtocolPaused);
+    ctx.accounts.config.require_not_paused(PAUSE_CLAIM)?;
     require!(amount > 0, CustomErrorCode::InvalidAmount);
-    // leaf = sha256(user || amount_le || epoch_index_le)
-    let mut data = Vec::with_capacity(32 + 8 + 8);
-    data.extend_from_slice(ctx.accounts.user.key.as_ref());
-    data.extend_from_slice(&amount.to_le_bytes());
-    data.extend_from_slice(&ctx.accounts.epoch.index.to_le_bytes());
-    let mut node = hashv(&[&data]).to_bytes();
-
-    msg!("User Leaf node: {}", hex::encode(node));
-
-    // iterate through proof
-    for (i, step) in proof.iter().enumerate() {
-        let sib = &step.sibling;
-
-        if sib.iter().all(|&b| b == 0) {
-            msg!("[{}] right: sibling is zero - hashing just the node", i);
-            node = hashv(&[&node]).to_bytes();
-            continue;
-        }
-
-        if step.is_left {
-            // sibling is left, so hash(sib || node)
-            node = hashv(&[sib, &node]).to_bytes();
-            msg!("[{}] left: hash(sib,node) = {}", i, hex::encode(node));
-        } else {
-            // sibling is right, so hash(node || sib)
-            node = hashv(&[&node, sib]).to_bytes();
-            msg!("[{}] right: hash(node,sib) = {}", i, hex::encode(node));
-        }
-    }
+    require!(
+        !ctx.accounts.epoch.cumulative && ctx.accounts.epoch.leaf_count == 0,
+        CustomErrorCode::WrongRewardsEpochKind
+    );
+    ctx.accounts.epoch.check_claimable(Clock::get()?.unix_timestamp)?;
+    let epoch = &ctx.accounts.epoch;
+    let leaf = claim_leaf(epoch.hash_version, ctx.accounts.user.key, amount, epoch.index)?;
+
+    msg!("User Leaf node: {}", hex::encode(leaf));
+
+    let node = compute_root(epoch.hash_version, leaf, &proof)?;
 
     msg!("Computed root: {}", hex::encode(node));
     msg!(
@@ -413,9 +2175,30 @@ pub fn claim_rewards(ctx: Context<ClaimRewards>, amount: u64, proof: Vec<ProofNo
         CustomErrorCode::InvalidMerkleProof
     );
 
-    // mint staking tokens (PRIME) to user
+    // the declared total is a hard cap, even if the merkle tree allocates more
+    let epoch = &mut ctx.accounts.epoch;
+    let claimed = epoch
+        .claimed
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    require!(
+        claimed <= epoch.total,
+        CustomErrorCode::RewardsEpochTotalExceeded
+    );
+    epoch.claimed = claimed;
+
+    // vesting epochs lock the claim in a vesting account instead, released by withdraw_vested
+    if epoch.vesting_duration > 0 {
+        return start_vesting(ctx, amount);
+    }
+
+    // mint staking tokens (PRIME) to user, at the exchange rate if enabled
+    let shares = ctx
+        .accounts
+        .config
+        .record_reward_claim(amount, ctx.accounts.mint.supply)?;
     let seeds: &[&[u8]] = &[b"mint_authority", &[ctx.bumps.mint_authority]];
-    let signer = &[&seeds[..]];
+    let signer = &[seeds];
     let cpi_accounts = MintTo {
         mint: ctx.accounts.mint.to_account_info(),
         to: ctx.accounts.user_stake_token_account.to_account_info(),
@@ -427,7 +2210,7 @@ pub fn claim_rewards(ctx: Context<ClaimRewards>, amount: u64, proof: Vec<ProofNo
             cpi_accounts,
             signer,
         ),
-        amount,
+        shares,
     )?;
 
     emit!(RewardsClaimed {
@@ -436,6 +2219,389 @@ pub fn claim_rewards(ctx: Context<ClaimRewards>, amount: u64, proof: Vec<ProofNo
         amount,
         mint: ctx.accounts.mint.key(),
         vault: ctx.accounts.config.vault,
+        remaining: ctx.accounts.epoch.total - ctx.accounts.epoch.claimed,
+    });
+
+    Ok(())
+}
+
+// Open the user's vesting account for a claim from a vesting epoch
+fn start_vesting(ctx: Context<ClaimRewards>, amount: u64) -> Result<()> {
+    let user = ctx.accounts.user.key();
+    let epoch = &ctx.accounts.epoch;
+    let epoch_key = epoch.key();
+    let vesting_info = ctx
+        .accounts
+        .vesting
+        .as_ref()
+        .ok_or(CustomErrorCode::InvalidClaimAccounts)?
+        .to_account_info();
+    let (vesting_key, bump) = Pubkey::find_program_address(
+        &[b"vesting", epoch_key.as_ref(), user.as_ref()],
+        &crate::ID,
+    );
+    require_keys_eq!(
+        vesting_info.key(),
+        vesting_key,
+        CustomErrorCode::InvalidClaimAccounts
+    );
+    create_pda_account(
+        &vesting_info,
+        &ctx.accounts.user,
+        &ctx.accounts.system_program,
+        VestingAccount::LEN,
+        &[b"vesting", epoch_key.as_ref(), user.as_ref(), &[bump]],
+    )?;
+
+    let start_ts = Clock::get()?.unix_timestamp;
+    let vesting = VestingAccount {
+        user,
+        epoch: epoch_key,
+        epoch_index: epoch.index,
+        total: amount,
+        released: 0,
+        start_ts,
+        duration: epoch.vesting_duration,
+        bump,
+    };
+    vesting.try_serialize(&mut &mut vesting_info.try_borrow_mut_data()?[..])?;
+
+    emit!(RewardsVestingStarted {
+        user,
+        epoch: epoch.index,
+        amount,
+        start_ts,
+        end_ts: start_ts.saturating_add(epoch.vesting_duration),
+        mint: ctx.accounts.mint.key(),
+        vault: ctx.accounts.config.vault,
+    });
+
+    Ok(())
+}
+
+// Mint whatever part of a vesting claim has unlocked since the last withdrawal. The vesting
+// account is closed back to the user once everything is released.
+pub fn withdraw_vested(ctx: Context<WithdrawVested>) -> Result<()> {
+    ctx.accounts.config.require_not_paused(PAUSE_CLAIM)?;
+    let vesting = &mut ctx.accounts.vesting;
+    let amount = vesting.vested(Clock::get()?.unix_timestamp) - vesting.released;
+    require!(amount > 0, CustomErrorCode::NothingToClaim);
+    vesting.released += amount;
+
+    // mint staking tokens (PRIME) to user, at the exchange rate if enabled
+    let shares = ctx
+        .accounts
+        .config
+        .record_reward_claim(amount, ctx.accounts.mint.supply)?;
+    let seeds: &[&[u8]] = &[b"mint_authority", &[ctx.bumps.mint_authority]];
+    let signer = &[seeds];
+    let cpi_accounts = MintTo {
+        mint: ctx.accounts.mint.to_account_info(),
+        to: ctx.accounts.user_stake_token_account.to_account_info(),
+        authority: ctx.accounts.mint_authority.to_account_info(),
+    };
+    token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        ),
+        shares,
+    )?;
+
+    let vesting = &ctx.accounts.vesting;
+    emit!(VestedRewardsReleased {
+        user: ctx.accounts.user.key(),
+        epoch: vesting.epoch_index,
+        amount,
+        released: vesting.released,
+        total: vesting.total,
+        mint: ctx.accounts.mint.key(),
+        vault: ctx.accounts.config.vault,
+    });
+
+    if vesting.released == vesting.total {
+        close_account(&vesting.to_account_info(), &ctx.accounts.user.to_account_info())?;
+    }
+
+    Ok(())
+}
+
+// Claim several epochs with one mint. Each claim is paired with its epoch and claim record in the
+// remaining accounts; any invalid proof or repeated claim fails the whole batch.
+pub fn claim_rewards_batch<'info>(
+    ctx: Context<'_, '_, 'info, 'info, ClaimRewardsBatch<'info>>,
+    claims: Vec<EpochClaim>,
+) -> Result<()> {
+    ctx.accounts.config.require_not_paused(PAUSE_CLAIM)?;
+    require!(
+        !claims.is_empty() && ctx.remaining_accounts.len() == claims.len() * 2,
+        CustomErrorCode::InvalidClaimAccounts
+    );
+    let user = ctx.accounts.user.key();
+    let now = Clock::get()?.unix_timestamp;
+
+    let mut epochs: Vec<(Account<'info, RewardsEpoch>, u64)> = Vec::with_capacity(claims.len());
+    let mut total: u64 = 0;
+    for (i, (claim, accounts)) in claims
+        .iter()
+        .zip(ctx.remaining_accounts.chunks(2))
+        .enumerate()
+    {
+        require!(claim.amount > 0, CustomErrorCode::InvalidAmount);
+        let (epoch_info, claim_record_info) = (&accounts[0], &accounts[1]);
+        require!(
+            !ctx.remaining_accounts[..i * 2]
+                .chunks(2)
+                .any(|other| other[0].key == epoch_info.key),
+            CustomErrorCode::DuplicateRewardsEpoch
+        );
+
+        let mut epoch = Account::<RewardsEpoch>::try_from(epoch_info)?;
+        require!(
+            !epoch.cumulative && epoch.leaf_count == 0 && epoch.vesting_duration == 0,
+            CustomErrorCode::WrongRewardsEpochKind
+        );
+        epoch.check_claimable(now)?;
+        let leaf = claim_leaf(epoch.hash_version, &user, claim.amount, epoch.index)?;
+        require!(
+            compute_root(epoch.hash_version, leaf, &claim.proof)? == epoch.merkle_root,
+            CustomErrorCode::InvalidMerkleProof
+        );
+
+        let claimed = epoch
+            .claimed
+            .checked_add(claim.amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        require!(
+            claimed <= epoch.total,
+            CustomErrorCode::RewardsEpochTotalExceeded
+        );
+        epoch.claimed = claimed;
+
+        // same claim record PDA as claim_rewards, so an epoch can only be claimed once either way
+        let (claim_record, bump) = Pubkey::find_program_address(
+            &[b"claim", epoch_info.key.as_ref(), user.as_ref()],
+            &crate::ID,
+        );
+        require_keys_eq!(
+            claim_record_info.key(),
+            claim_record,
+            CustomErrorCode::InvalidClaimAccounts
+        );
+        require!(
+            claim_record_info.owner != &crate::ID,
+            CustomErrorCode::RewardsAlreadyClaimed
+        );
+        create_pda_account(
+            claim_record_info,
+            &ctx.accounts.user,
+            &ctx.accounts.system_program,
+            ClaimRecord::LEN,
+            &[b"claim", epoch_info.key.as_ref(), user.as_ref(), &[bump]],
+        )?;
+        claim_record_info.try_borrow_mut_data()?[..8].copy_from_slice(ClaimRecord::DISCRIMINATOR);
+
+        total = total
+            .checked_add(claim.amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        epochs.push((epoch, claim.amount));
+    }
+
+    // mint staking tokens (PRIME) to user for all epochs at once, at the exchange rate if enabled
+    let shares = ctx
+        .accounts
+        .config
+        .record_reward_claim(total, ctx.accounts.mint.supply)?;
+    let seeds: &[&[u8]] = &[b"mint_authority", &[ctx.bumps.mint_authority]];
+    let signer = &[seeds];
+    let cpi_accounts = MintTo {
+        mint: ctx.accounts.mint.to_account_info(),
+        to: ctx.accounts.user_stake_token_account.to_account_info(),
+        authority: ctx.accounts.mint_authority.to_account_info(),
+    };
+    token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        ),
+        shares,
+    )?;
+
+    for (epoch, amount) in epochs {
+        emit!(RewardsClaimed {
+            user,
+            epoch: epoch.index,
+            amount,
+            mint: ctx.accounts.mint.key(),
+            vault: ctx.accounts.config.vault,
+            remaining: epoch.total - epoch.claimed,
+        });
+        epoch.exit(&crate::ID)?;
+    }
+
+    Ok(())
+}
+
+// Pay the difference between the user's lifetime total in a cumulative epoch and what they were
+// already paid. Only the latest proof is ever needed and no per-epoch claim records are created.
+pub fn claim_cumulative_rewards(
+    ctx: Context<ClaimCumulativeRewards>,
+    cumulative_amount: u64,
+    proof: Vec<ProofNode>,
+) -> Result<()> {
+    ctx.accounts.config.require_not_paused(PAUSE_CLAIM)?;
+    require!(
+        ctx.accounts.epoch.cumulative && ctx.accounts.epoch.leaf_count == 0,
+        CustomErrorCode::WrongRewardsEpochKind
+    );
+    ctx.accounts.epoch.check_claimable(Clock::get()?.unix_timestamp)?;
+    let user = ctx.accounts.user.key();
+
+    // leaf = sha256(user || cumulative_amount_le || epoch_index_le)
+    let epoch = &ctx.accounts.epoch;
+    let leaf = claim_leaf(epoch.hash_version, &user, cumulative_amount, epoch.index)?;
+    require!(
+        compute_root(epoch.hash_version, leaf, &proof)? == epoch.merkle_root,
+        CustomErrorCode::InvalidMerkleProof
+    );
+
+    let state = &mut ctx.accounts.claim_state;
+    state.user = user;
+    state.bump = ctx.bumps.claim_state;
+    require!(
+        cumulative_amount > state.claimed,
+        CustomErrorCode::NothingToClaim
+    );
+    let amount = cumulative_amount - state.claimed;
+    state.claimed = cumulative_amount;
+
+    let epoch = &mut ctx.accounts.epoch;
+    let claimed = epoch
+        .claimed
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    require!(
+        claimed <= epoch.total,
+        CustomErrorCode::RewardsEpochTotalExceeded
+    );
+    epoch.claimed = claimed;
+
+    // claim states are shared by every cumulative epoch and older epochs stay claimable, so the
+    // per-epoch bound alone doesn't stop payouts beyond what was funded
+    let config = &mut ctx.accounts.config;
+    let cumulative_claimed = config
+        .cumulative_rewards_claimed
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    require!(
+        cumulative_claimed <= config.cumulative_rewards_total,
+        CustomErrorCode::CumulativeRewardsExceeded
+    );
+    config.cumulative_rewards_claimed = cumulative_claimed;
+
+    // mint staking tokens (PRIME) to user, at the exchange rate if enabled
+    let shares = ctx
+        .accounts
+        .config
+        .record_reward_claim(amount, ctx.accounts.mint.supply)?;
+    let seeds: &[&[u8]] = &[b"mint_authority", &[ctx.bumps.mint_authority]];
+    let signer = &[seeds];
+    let cpi_accounts = MintTo {
+        mint: ctx.accounts.mint.to_account_info(),
+        to: ctx.accounts.user_stake_token_account.to_account_info(),
+        authority: ctx.accounts.mint_authority.to_account_info(),
+    };
+    token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        ),
+        shares,
+    )?;
+
+    emit!(RewardsClaimed {
+        user,
+        epoch: ctx.accounts.epoch.index,
+        amount,
+        mint: ctx.accounts.mint.key(),
+        vault: ctx.accounts.config.vault,
+        remaining: ctx.accounts.epoch.total - ctx.accounts.epoch.claimed,
+    });
+
+    Ok(())
+}
+
+// Claim one leaf of an indexed epoch. The leaf's bit in the epoch's claim bitmap marks it claimed,
+// so the user pays no rent for a claim record.
+pub fn claim_indexed_rewards(
+    ctx: Context<ClaimIndexedRewards>,
+    leaf_index: u64,
+    amount: u64,
+    proof: Vec<ProofNode>,
+) -> Result<()> {
+    ctx.accounts.config.require_not_paused(PAUSE_CLAIM)?;
+    require!(amount > 0, CustomErrorCode::InvalidAmount);
+    let epoch = &ctx.accounts.epoch;
+    require!(epoch.leaf_count > 0, CustomErrorCode::WrongRewardsEpochKind);
+    require!(leaf_index < epoch.leaf_count, CustomErrorCode::InvalidLeafIndex);
+    epoch.check_claimable(Clock::get()?.unix_timestamp)?;
+    let user = ctx.accounts.user.key();
+
+    let leaf = indexed_claim_leaf(epoch.hash_version, &user, amount, epoch.index, leaf_index)?;
+    require!(
+        compute_root(epoch.hash_version, leaf, &proof)? == epoch.merkle_root,
+        CustomErrorCode::InvalidMerkleProof
+    );
+
+    let bitmap = &mut ctx.accounts.claim_bitmap;
+    require!(
+        !bitmap.is_claimed(leaf_index),
+        CustomErrorCode::RewardsAlreadyClaimed
+    );
+    bitmap.set_claimed(leaf_index);
+
+    let epoch = &mut ctx.accounts.epoch;
+    let claimed = epoch
+        .claimed
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    require!(
+        claimed <= epoch.total,
+        CustomErrorCode::RewardsEpochTotalExceeded
+    );
+    epoch.claimed = claimed;
+
+    // mint staking tokens (PRIME) to user, at the exchange rate if enabled
+    let shares = ctx
+        .accounts
+        .config
+        .record_reward_claim(amount, ctx.accounts.mint.supply)?;
+    let seeds: &[&[u8]] = &[b"mint_authority", &[ctx.bumps.mint_authority]];
+    let signer = &[seeds];
+    let cpi_accounts = MintTo {
+        mint: ctx.accounts.mint.to_account_info(),
+        to: ctx.accounts.user_stake_token_account.to_account_info(),
+        authority: ctx.accounts.mint_authority.to_account_info(),
+    };
+    token::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            cpi_accounts,
+            signer,
+        ),
+        shares,
+    )?;
+
+    emit!(RewardsClaimed {
+        user,
+        epoch: ctx.accounts.epoch.index,
+        amount,
+        mint: ctx.accounts.mint.key(),
+        vault: ctx.accounts.config.vault,
+        remaining: ctx.accounts.epoch.total - ctx.accounts.epoch.claimed,
     });
 
     Ok(())
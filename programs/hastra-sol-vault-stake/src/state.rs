@@ -1,8 +1,27 @@
+use crate::error::CustomErrorCode;
 use anchor_lang::prelude::*;
 
 pub const MAX_UNBONDING_PERIOD: i64 = 31536000; // 365 days in seconds
 pub const MIN_UNBONDING_PERIOD: i64 = 1; // 1 second
 pub const MAX_ADMINISTRATORS: usize = 5; // max number of freeze/rewards administrators
+pub const BPS_DENOMINATOR: u64 = 10_000; // 100% in basis points
+pub const MAX_INSTANT_UNBOND_FEE_BPS: u16 = 5_000; // 50%, so an instant exit always pays out
+pub const MAX_CONFIG_CHANGE_DELAY: i64 = 2592000; // 30 days in seconds
+
+// Config.pause_flags bits. PAUSE_ALL sits where the legacy `paused` bool was stored.
+pub const PAUSE_ALL: u8 = 1 << 0; // every operation below
+pub const PAUSE_DEPOSIT: u8 = 1 << 1;
+pub const PAUSE_UNBOND: u8 = 1 << 2; // unbond, cancel_unbond and migrate_ticket
+pub const PAUSE_REDEEM: u8 = 1 << 3; // redeem, redeem_many and settle_queue
+pub const PAUSE_CLAIM: u8 = 1 << 4; // rewards claims and withdraw_vested
+pub const PAUSE_INSTANT_UNBOND: u8 = 1 << 5;
+pub const PAUSE_FLAGS_MASK: u8 =
+    PAUSE_ALL | PAUSE_DEPOSIT | PAUSE_UNBOND | PAUSE_REDEEM | PAUSE_CLAIM | PAUSE_INSTANT_UNBOND;
+// rewards epoch merkle hashing schemes, see merkle.rs
+pub const HASH_VERSION_LEGACY: u8 = 0; // unprefixed hashes, pair order from the proof's is_left flags
+pub const HASH_VERSION_PREFIXED_SORTED: u8 = 1; // 0x00 leaf / 0x01 node prefixes, sorted pairs
+// leaves a claim bitmap can track while still fitting the 10KiB an account can be created with
+pub const MAX_BITMAP_LEAVES: u64 = ((10_240 - ClaimBitmap::BASE_LEN) * 8) as u64;
 
 #[account]
 pub struct Config {
@@ -12,12 +31,138 @@ pub struct Config {
     pub freeze_administrators: Vec<Pubkey>,
     pub rewards_administrators: Vec<Pubkey>,
     pub bump: u8,
-    pub paused: bool,
+    pub pause_flags: u8, // PAUSE_* bits of the operations currently paused
+    // Fields below were appended after launch; a zero value keeps the legacy behaviour.
+    pub instant_unbond_fee_bps: u16,    // exit fee charged by instant_unbond, in basis points
+    pub treasury: Pubkey,               // vault token account that receives instant unbond fees
+    pub instant_unbond_epoch_cap: u64,  // max vault tokens paid out instantly per epoch, 0 disables
+    pub instant_unbond_epoch: u64,      // epoch that instant_unbond_epoch_used belongs to
+    pub instant_unbond_epoch_used: u64, // vault tokens paid out instantly during that epoch
+    pub exchange_rate_mode: bool,       // price stake tokens against total_assets instead of 1:1
+    pub total_assets: u64,              // vault tokens backing the stake token supply in exchange-rate mode
+    pub funded_rewards: bool,           // rewards epochs must transfer their total into the vault
+    pub cumulative_rewards_total: u64,  // lifetime total committed by the latest cumulative epoch
+    pub rewards_hash_version: u8,       // merkle hashing scheme new rewards epochs are created with
+    pub rewards_approval_threshold: u8, // rewards admin approvals needed to publish an epoch, 0 or 1 = no approvals
+    pub next_epoch_index: u64,          // index the next rewards epoch must use, 0 = any index (legacy)
+    pub max_epoch_rewards_bps: u16,     // cap on an epoch's new rewards as bps of the vault balance, 0 = none
+    pub max_epoch_rewards: u64,         // absolute cap on an epoch's new rewards, 0 = none
+    pub rewards_vesting_duration: i64,  // vesting period new classic rewards epochs are created with, 0 = instant
+    pub admin: Pubkey,                  // protocol admin, default = the upgrade authority administers (legacy)
+    pub pending_admin: Pubkey,          // admin proposed by propose_admin, awaiting accept_admin
+    pub config_change_delay: i64,      // timelock on config changes, 0 = setters apply instantly (legacy)
+    pub pause_guardians: Vec<Pubkey>,  // hot keys that can pause operations but not resume them
+    pub cumulative_rewards_claimed: u64, // paid out across all cumulative epochs, never above cumulative_rewards_total
 }
 
 impl Config {
     // The vectors have a max length of 5 each and must include the Borsh overhead of 4 bytes for
-    pub const LEN: usize = 8 + 32 + 32 + 8 + (4 + (32 * MAX_ADMINISTRATORS)) + (4 + (32 * MAX_ADMINISTRATORS)) + 1 + 1;
+    pub const LEN: usize = 8 + 32 + 32 + 8 + (4 + (32 * MAX_ADMINISTRATORS)) + (4 + (32 * MAX_ADMINISTRATORS)) + 1 + 1
+        + 2 + 32 + 8 + 8 + 8 + 1 + 8 + 1 + 8 + 1 + 1 + 8 + 2 + 8 + 8 + 32 + 32 + 8
+        + (4 + (32 * MAX_ADMINISTRATORS)) + 8;
+
+    // Stake tokens minted for `assets` vault tokens. Rounds down so the vault never over-mints.
+    pub fn to_shares(&self, assets: u64, supply: u64) -> Result<u64> {
+        if !self.exchange_rate_mode || supply == 0 {
+            return Ok(assets);
+        }
+        require!(self.total_assets > 0, CustomErrorCode::InvalidExchangeRate);
+        Ok((assets as u128 * supply as u128 / self.total_assets as u128) as u64)
+    }
+
+    // Vault tokens paid out for `shares` stake tokens. Rounds down so the vault never over-pays.
+    pub fn to_assets(&self, shares: u64, supply: u64) -> Result<u64> {
+        if !self.exchange_rate_mode || supply == 0 {
+            return Ok(shares);
+        }
+        let assets = shares as u128 * self.total_assets as u128 / supply as u128;
+        u64::try_from(assets).map_err(|_| ProgramError::ArithmeticOverflow.into())
+    }
+
+    // Vault tokens that came in and now back the stake token supply. Once every stake token has
+    // been burned, whatever rounding left in total assets is dropped so the next depositor starts
+    // at par instead of picking it up.
+    pub fn record_deposit(&mut self, assets: u64, supply: u64) -> Result<()> {
+        if self.exchange_rate_mode {
+            let backing = if supply == 0 { 0 } else { self.total_assets };
+            self.total_assets = backing
+                .checked_add(assets)
+                .ok_or(ProgramError::ArithmeticOverflow)?;
+        }
+        Ok(())
+    }
+
+    // Stake tokens minted for a rewards claim of `amount` vault tokens. Funded rewards already sit
+    // in the vault and start backing the supply as they are claimed, so the rate is unchanged.
+    pub fn record_reward_claim(&mut self, amount: u64, supply: u64) -> Result<u64> {
+        let shares = self.to_shares(amount, supply)?;
+        self.record_deposit(amount, supply)?;
+        Ok(shares)
+    }
+
+    // Vault tokens paid out against burned stake tokens
+    pub fn record_withdrawal(&mut self, assets: u64) {
+        if self.exchange_rate_mode {
+            self.total_assets = self.total_assets.saturating_sub(assets);
+        }
+    }
+
+    // With sequential epochs, a new epoch must take the next index
+    pub fn take_epoch_index(&mut self, index: u64) -> Result<()> {
+        if self.next_epoch_index == 0 {
+            return Ok(());
+        }
+        require!(
+            index == self.next_epoch_index,
+            CustomErrorCode::InvalidEpochIndex
+        );
+        self.next_epoch_index = index
+            .checked_add(1)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    // Circuit breaker on reward issuance. `vault_balance` is only needed for the bps cap.
+    pub fn check_epoch_rewards_cap(&self, amount: u64, vault_balance: Option<u64>) -> Result<()> {
+        if self.max_epoch_rewards > 0 {
+            require!(
+                amount <= self.max_epoch_rewards,
+                CustomErrorCode::EpochRewardsCapExceeded
+            );
+        }
+        if self.max_epoch_rewards_bps > 0 {
+            let vault_balance = vault_balance.ok_or(CustomErrorCode::VaultTokenAccountRequired)?;
+            let cap = vault_balance as u128 * self.max_epoch_rewards_bps as u128 / BPS_DENOMINATOR as u128;
+            require!(
+                amount as u128 <= cap,
+                CustomErrorCode::EpochRewardsCapExceeded
+            );
+        }
+        Ok(())
+    }
+
+    // Fails if `operation` (a PAUSE_* bit) or the whole protocol is paused
+    pub fn require_not_paused(&self, operation: u8) -> Result<()> {
+        require!(
+            self.pause_flags & (PAUSE_ALL | operation) == 0,
+            CustomErrorCode::ProtocolPaused
+        );
+        Ok(())
+    }
+
+    // While a timelock is set, config changes must go through propose_config_change
+    pub fn require_no_timelock(&self) -> Result<()> {
+        require!(
+            self.config_change_delay == 0,
+            CustomErrorCode::ConfigChangeTimelocked
+        );
+        Ok(())
+    }
+
+    // Index of the most recently created epoch, when epochs are sequential
+    pub fn latest_epoch_index(&self) -> Option<u64> {
+        self.next_epoch_index.checked_sub(1).filter(|&i| i > 0)
+    }
 }
 
 #[account]
@@ -26,10 +171,44 @@ pub struct UnbondingTicket {
     pub requested_amount: u64,
     pub start_balance: u64,
     pub start_ts: i64,
+    pub remaining_amount: u64, // stake tokens still held in the stake escrow, not yet redeemed
+    pub nonce: u64,            // per-user ticket number, part of the ticket PDA seeds
+    pub unlock_ts: i64,        // start_ts plus the unbonding period in effect at unbond time
+    pub queued: bool,          // waiting in the withdrawal queue for the vault to be funded
+    pub queue_position: u64,   // position in the withdrawal queue, only meaningful when queued
 }
 
 impl UnbondingTicket {
-    pub const LEN: usize = 8 + 32 + 8 + 8 + 8;
+    pub const LEN: usize = 8 + 32 + 8 + 8 + 8 + 8 + 8 + 8 + 1 + 8;
+}
+
+/// FIFO queue of matured tickets the vault could not pay out when they were redeemed.
+/// Tickets are settled strictly in order from `head`; new entries are appended at `tail`.
+#[account]
+pub struct WithdrawalQueue {
+    pub head: u64,          // position of the next ticket to settle
+    pub tail: u64,          // position the next enqueued ticket will take
+    pub queued_amount: u64, // stake tokens still owed to queued tickets
+    pub bump: u8,
+}
+
+impl WithdrawalQueue {
+    pub const LEN: usize = 8 + 8 + 8 + 8 + 1;
+
+    pub fn is_empty(&self) -> bool {
+        self.head == self.tail
+    }
+}
+
+#[account]
+pub struct UserStakeState {
+    pub owner: Pubkey,
+    pub next_ticket_nonce: u64, // nonce the user's next unbonding ticket will be created with
+    pub bump: u8,
+}
+
+impl UserStakeState {
+    pub const LEN: usize = 8 + 32 + 8 + 1;
 }
 
 #[account]
@@ -38,9 +217,124 @@ pub struct RewardsEpoch {
     pub merkle_root: [u8; 32], // sha256 root (sortPairs)
     pub total: u64,            // optional: sum of all allocations
     pub created_ts: i64,
+    pub claimed: u64,          // sum of claims so far, never above total
+    pub cumulative: bool,      // leaves commit to lifetime totals, claimed via RewardsClaimState
+    pub leaf_count: u64,       // leaves include their index and claims flip a ClaimBitmap bit, 0 uses ClaimRecords
+    pub hash_version: u8,      // merkle hashing scheme the root was built with
+    pub claim_start_ts: i64,   // claims open at this time, 0 = as soon as the epoch is created
+    pub claim_end_ts: i64,     // claims close at this time and the epoch can be closed, 0 = never
+    pub draft: bool,           // root and total can still be updated or revoked, claims rejected until published
+    pub vesting_duration: i64, // claims vest linearly over this many seconds in a VestingAccount, 0 = minted at once
 }
 impl RewardsEpoch {
-    pub const LEN: usize = 8 + 8 + 32 + 8 + 8;
+    pub const LEN: usize = 8 + 8 + 32 + 8 + 8 + 8 + 1 + 8 + 1 + 8 + 8 + 1 + 8;
+
+    pub fn check_claimable(&self, now: i64) -> Result<()> {
+        require!(!self.draft, CustomErrorCode::RewardsEpochNotPublished);
+        require!(now >= self.claim_start_ts, CustomErrorCode::ClaimWindowNotOpen);
+        require!(!self.is_expired(now), CustomErrorCode::ClaimWindowClosed);
+        Ok(())
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.claim_end_ts != 0 && now >= self.claim_end_ts
+    }
+}
+
+/// Rewards admins who approved a draft epoch. Approvals are for the root and total recorded here,
+/// so updating the draft starts the approvals over.
+#[account]
+pub struct RewardsEpochApprovals {
+    pub epoch: Pubkey,
+    pub merkle_root: [u8; 32],
+    pub total: u64,
+    pub approvers: Vec<Pubkey>,
+    pub bump: u8,
+}
+impl RewardsEpochApprovals {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + (4 + (32 * MAX_ADMINISTRATORS)) + 1;
+
+    // approvals that still count: for the epoch's current root and total, by current rewards admins
+    pub fn count(&self, epoch: &RewardsEpoch, rewards_administrators: &[Pubkey]) -> usize {
+        if self.merkle_root != epoch.merkle_root || self.total != epoch.total {
+            return 0;
+        }
+        self.approvers
+            .iter()
+            .filter(|a| rewards_administrators.contains(a))
+            .count()
+    }
+}
+
+/// A claim from a vesting epoch, released linearly by withdraw_vested.
+#[account]
+pub struct VestingAccount {
+    pub user: Pubkey,
+    pub epoch: Pubkey,
+    pub epoch_index: u64,
+    pub total: u64,    // amount claimed
+    pub released: u64, // amount minted to the user so far
+    pub start_ts: i64,
+    pub duration: i64,
+    pub bump: u8,
+}
+impl VestingAccount {
+    pub const LEN: usize = 8 + 32 + 32 + 8 + 8 + 8 + 8 + 8 + 1;
+
+    // Amount unlocked at `now`, rounded down until the vesting period is over
+    pub fn vested(&self, now: i64) -> u64 {
+        let elapsed = now.saturating_sub(self.start_ts).clamp(0, self.duration);
+        if elapsed >= self.duration {
+            return self.total;
+        }
+        (self.total as u128 * elapsed as u128 / self.duration as u128) as u64
+    }
+}
+
+/// A timelocked config change, applied by execute_config_change once `eta` has passed.
+/// Only one change can be pending at a time.
+#[account]
+pub struct PendingConfigChange {
+    pub proposer: Pubkey,
+    pub eta: i64,
+    pub change: ConfigChange,
+    pub bump: u8,
+}
+impl PendingConfigChange {
+    pub const LEN: usize = 8 + 32 + 8 + ConfigChange::LEN + 1;
+}
+
+/// Per-user watermark for cumulative rewards epochs.
+#[account]
+pub struct RewardsClaimState {
+    pub user: Pubkey,
+    pub claimed: u64, // lifetime rewards already paid out from cumulative epochs
+    pub bump: u8,
+}
+impl RewardsClaimState {
+    pub const LEN: usize = 8 + 32 + 8 + 1;
+}
+
+/// One bit per leaf of an indexed rewards epoch, set once that leaf is claimed.
+#[account]
+pub struct ClaimBitmap {
+    pub epoch: Pubkey,
+    pub bits: Vec<u8>,
+}
+impl ClaimBitmap {
+    pub const BASE_LEN: usize = 8 + 32 + 4;
+
+    pub fn space(leaf_count: u64) -> usize {
+        Self::BASE_LEN + leaf_count.div_ceil(8) as usize
+    }
+
+    pub fn is_claimed(&self, leaf_index: u64) -> bool {
+        self.bits[(leaf_index / 8) as usize] & (1 << (leaf_index % 8)) != 0
+    }
+
+    pub fn set_claimed(&mut self, leaf_index: u64) {
+        self.bits[(leaf_index / 8) as usize] |= 1 << (leaf_index % 8);
+    }
 }
 
 #[account]
@@ -49,6 +343,13 @@ impl ClaimRecord {
     pub const LEN: usize = 8;
 }
 
+/// One epoch's claim in a batch claim.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct EpochClaim {
+    pub amount: u64,
+    pub proof: Vec<ProofNode>,
+}
+
 /// One Merkle proof element.
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
 pub struct ProofNode {
@@ -56,3 +357,79 @@ pub struct ProofNode {
     pub is_left: bool,
 }
 
+
+/// Config values changed by a timelocked config change; `None` leaves a value as it is.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct ConfigChange {
+    pub unbonding_period: Option<i64>,
+    pub instant_unbond: Option<InstantUnbondParams>,
+    pub freeze_administrators: Option<Vec<Pubkey>>,
+    pub rewards_administrators: Option<Vec<Pubkey>>,
+    pub config_change_delay: Option<i64>,
+    pub pause_guardians: Option<Vec<Pubkey>>,
+    pub pending_admin: Option<Pubkey>, // becomes Config.pending_admin, accept_admin still completes the handover
+    pub rewards_approval_threshold: Option<u8>,
+    pub epoch_rewards_cap: Option<EpochRewardsCapParams>,
+}
+impl ConfigChange {
+    pub const LEN: usize = (1 + 8)
+        + (1 + InstantUnbondParams::LEN)
+        + (1 + 4 + (32 * MAX_ADMINISTRATORS))
+        + (1 + 4 + (32 * MAX_ADMINISTRATORS))
+        + (1 + 8)
+        + (1 + 4 + (32 * MAX_ADMINISTRATORS))
+        + (1 + 32)
+        + (1 + 1)
+        + (1 + EpochRewardsCapParams::LEN);
+
+    // The values in effect now for each field this change sets
+    pub fn current_values(&self, config: &Config) -> ConfigChange {
+        ConfigChange {
+            unbonding_period: self.unbonding_period.map(|_| config.unbonding_period),
+            instant_unbond: self.instant_unbond.as_ref().map(|_| InstantUnbondParams {
+                fee_bps: config.instant_unbond_fee_bps,
+                epoch_cap: config.instant_unbond_epoch_cap,
+                treasury: config.treasury,
+            }),
+            freeze_administrators: self
+                .freeze_administrators
+                .as_ref()
+                .map(|_| config.freeze_administrators.clone()),
+            rewards_administrators: self
+                .rewards_administrators
+                .as_ref()
+                .map(|_| config.rewards_administrators.clone()),
+            config_change_delay: self.config_change_delay.map(|_| config.config_change_delay),
+            pause_guardians: self.pause_guardians.as_ref().map(|_| config.pause_guardians.clone()),
+            pending_admin: self.pending_admin.map(|_| config.pending_admin),
+            rewards_approval_threshold: self
+                .rewards_approval_threshold
+                .map(|_| config.rewards_approval_threshold),
+            epoch_rewards_cap: self.epoch_rewards_cap.as_ref().map(|_| EpochRewardsCapParams {
+                max_bps: config.max_epoch_rewards_bps,
+                max_amount: config.max_epoch_rewards,
+            }),
+        }
+    }
+}
+
+/// Instant unbond settings, as set by update_instant_unbond_config.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct InstantUnbondParams {
+    pub fee_bps: u16,
+    pub epoch_cap: u64,
+    pub treasury: Pubkey,
+}
+impl InstantUnbondParams {
+    pub const LEN: usize = 2 + 8 + 32;
+}
+
+/// Epoch rewards cap, as set by update_epoch_rewards_cap.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct EpochRewardsCapParams {
+    pub max_bps: u16,
+    pub max_amount: u64,
+}
+impl EpochRewardsCapParams {
+    pub const LEN: usize = 2 + 8;
+}
@@ -0,0 +1,30 @@
+import * as anchor from "@coral-xyz/anchor";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+import {PublicKey} from "@solana/web3.js";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+// Run with the wallet of the pending admin proposed by propose_admin.ts
+const main = async () => {
+    const signer = provider.wallet.publicKey;
+    const [configPda] = PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId);
+    const config = await program.account.config.fetch(configPda);
+
+    console.log("Current admin:", config.admin.toBase58());
+    console.log("Pending admin:", config.pendingAdmin.toBase58());
+    console.log("Signer:", signer.toBase58());
+
+    const tx = await program.methods
+        .acceptAdmin()
+        .accounts({
+            signer: signer,
+        })
+        .rpc();
+    console.log("Transaction:", tx);
+};
+
+main().catch(console.error);
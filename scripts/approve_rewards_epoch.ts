@@ -0,0 +1,49 @@
+import * as anchor from "@coral-xyz/anchor";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+import yargs from "yargs";
+import {PublicKey} from "@solana/web3.js";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+const args = yargs(process.argv.slice(2))
+    .option("epoch", {
+        type: "number",
+        description: "Index of the draft epoch to approve",
+        required: true,
+    })
+    .parseSync();
+
+const main = async () => {
+    const [epochPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("epoch"), new anchor.BN(args.epoch).toArrayLike(Buffer, "le", 8)],
+        program.programId
+    );
+    const [approvalsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("epoch_approvals"), epochPda.toBuffer()],
+        program.programId
+    );
+
+    // approvals are for the root and total as they are now; review them before approving
+    const epoch = await program.account.rewardsEpoch.fetch(epochPda);
+    console.log("Epoch PDA:", epochPda.toBase58());
+    console.log("Merkle root:", Buffer.from(epoch.merkleRoot).toString("hex"));
+    console.log("Total:", epoch.total.toString());
+
+    const tx = await program.methods
+        .approveRewardsEpoch()
+        .accounts({
+            admin: provider.wallet.publicKey,
+            epoch: epochPda,
+        })
+        .rpc();
+    console.log("Transaction:", tx);
+
+    const approvals = await program.account.rewardsEpochApprovals.fetch(approvalsPda);
+    console.log("Approvers:", approvals.approvers.map(a => a.toBase58()));
+};
+
+main().catch(console.error);
@@ -0,0 +1,45 @@
+import * as anchor from "@coral-xyz/anchor";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+import {PublicKey} from "@solana/web3.js";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+// Drops the pending config change without applying it
+const main = async () => {
+    const signer = provider.wallet.publicKey;
+
+    // bpf_loader_upgradeable program id
+    const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+        "BPFLoaderUpgradeab1e11111111111111111111111"
+    );
+    // derive ProgramData PDA
+    const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_ID
+    );
+    const [pendingPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("pending_config_change")],
+        program.programId
+    );
+
+    const pending = await program.account.pendingConfigChange.fetch(pendingPda);
+    console.log("Pending config change:", JSON.stringify(pending.change));
+    console.log("ETA:", new Date(pending.eta.toNumber() * 1000).toISOString());
+    console.log("ProgramData PDA:", programData.toBase58());
+    console.log("Signer:", signer.toBase58());
+
+    const tx = await program.methods
+        .cancelConfigChange()
+        .accounts({
+            programData: programData,
+            signer: signer,
+        })
+        .rpc();
+    console.log("Transaction:", tx);
+};
+
+main().catch(console.error);
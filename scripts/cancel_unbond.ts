@@ -0,0 +1,76 @@
+import * as anchor from "@coral-xyz/anchor";
+import yargs from "yargs";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+const args = yargs(process.argv.slice(2))
+    .option("mint", {
+        type: "string",
+        description: "Mint token (e.g. PRIME) held in escrow for the ticket",
+        required: true,
+    })
+    .option("nonce", {
+        type: "number",
+        description: "Nonce of the unbonding ticket to cancel",
+        required: true,
+    })
+    .option("user_mint_token_account", {
+        type: "string",
+        description: "User's mint token account the escrowed tokens will be returned to. Must be associated token account for the mint token (e.g. PRIME)",
+        required: true,
+    })
+    .parseSync();
+
+const main = async () => {
+    const signer = provider.wallet.publicKey;
+
+    // Derive PDAs
+    const [configPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("config")],
+        program.programId
+    );
+    const [vaultAuthorityPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_authority")],
+        program.programId
+    );
+    const [stakeEscrowPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_escrow")],
+        program.programId
+    );
+    const [ticketPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("ticket"), signer.toBuffer(), new anchor.BN(args.nonce).toArrayLike(Buffer, "le", 8)],
+        program.programId
+    );
+
+    // Program args
+    const mint = new anchor.web3.PublicKey(args.mint);
+    const userMintTokenAccount = new anchor.web3.PublicKey(args.user_mint_token_account);
+
+    console.log("Mint (token returned from escrow e.g. PRIME):", mint.toBase58());
+    console.log("User Mint Token Account:", userMintTokenAccount.toBase58());
+    console.log("User Unbonding Ticket:", ticketPda.toBase58());
+    console.log("Stake Escrow PDA:", stakeEscrowPda.toBase58());
+    console.log("Config PDA:", configPda.toBase58());
+
+    const tx = await program.methods
+        .cancelUnbond()
+        .accountsStrict({
+            config: configPda,
+            signer: signer,
+            ticket: ticketPda,
+            userMintTokenAccount: userMintTokenAccount,
+            stakeEscrowTokenAccount: stakeEscrowPda,
+            vaultAuthority: vaultAuthorityPda,
+            mint: mint,
+            tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        }).rpc();
+
+    console.log("Transaction:", tx);
+};
+
+main().catch(console.error);
@@ -7,6 +7,7 @@ import {getAssociatedTokenAddressSync} from "@solana/spl-token";
 import {
     allocationsToMerkleTree,
     idl,
+    makeIndexedLeaf,
     makeLeaf
 } from "./cryptolib";
 
@@ -31,18 +32,39 @@ const args = yargs(process.argv.slice(2))
     })
     .option("amount", {
         type: "number",
-        description: "Amount to claim from this epoch index",
+        description: "Amount to claim from this epoch index (lifetime total for cumulative epochs)",
         required: false,
     })
+    .option("indexed", {
+        type: "boolean",
+        description: "Claim from an indexed epoch by flipping the user's bit in the epoch's claim bitmap",
+        required: false,
+        default: false,
+    })
+    .option("cumulative", {
+        type: "boolean",
+        description: "Claim from a cumulative epoch, paying the lifetime total minus what was already claimed",
+        required: false,
+        default: false,
+    })
     .parseSync();
 
 const program: Program<HastraSolVaultStake> = new anchor.Program(idl as anchor.Idl, provider) as Program<HastraSolVaultStake>;
 
 const main = async () => {
     const epochIndex = args.epoch;
-    const { tree } = allocationsToMerkleTree(args.reward_allocations, epochIndex);
+    const [epochPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("epoch"), new anchor.BN(epochIndex).toArrayLike(Buffer, "le", 8)],
+        program.programId
+    );
+    const { hashVersion, vestingDuration } = await program.account.rewardsEpoch.fetch(epochPda);
+    const { tree, allocations } = allocationsToMerkleTree(args.reward_allocations, epochIndex, args.indexed, hashVersion);
 
-    const leaf = makeLeaf(provider.wallet.publicKey, args.amount ?? 0, epochIndex);
+    // for indexed epochs the leaf index is the user's position in the allocations
+    const leafIndex = allocations.findIndex(a => a.user.equals(provider.wallet.publicKey));
+    const leaf = args.indexed
+        ? makeIndexedLeaf(provider.wallet.publicKey, args.amount ?? 0, epochIndex, leafIndex, hashVersion)
+        : makeLeaf(provider.wallet.publicKey, args.amount ?? 0, epochIndex, hashVersion);
 
     console.log("Leaf:", leaf.toString("hex"));
 
@@ -70,10 +92,6 @@ const main = async () => {
         [Buffer.from("config")],
         program.programId
     );
-    const [epochPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("epoch"), new anchor.BN(epochIndex).toArrayLike(Buffer, "le", 8)],
-        program.programId
-    );
     // derive claim record PDA
     const [claimPda] = PublicKey.findProgramAddressSync(
         [Buffer.from("claim"), epochPda.toBuffer(), provider.wallet.publicKey.toBuffer()],
@@ -92,6 +110,62 @@ const main = async () => {
         provider.wallet.publicKey,
     );
 
+    if (args.indexed) {
+        const [claimBitmapPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("claim_bitmap"), epochPda.toBuffer()],
+            program.programId
+        );
+        console.log("Leaf index:", leafIndex);
+        const tx = await program.methods
+            .claimIndexedRewards(new anchor.BN(leafIndex), new anchor.BN(args.amount), proof)
+            .accountsStrict({
+                config: configPda,
+                user: provider.wallet.publicKey,
+                epoch: epochPda,
+                claimBitmap: claimBitmapPda,
+                mintAuthority: mintAuthorityPda,
+                mint: mint,
+                userStakeTokenAccount: tokenAccount,
+                tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID
+            })
+            .rpc();
+
+        console.log("Transaction:", tx);
+        return;
+    }
+
+    if (args.cumulative) {
+        const [claimStatePda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("claim_state"), provider.wallet.publicKey.toBuffer()],
+            program.programId
+        );
+        const tx = await program.methods
+            .claimCumulativeRewards(new anchor.BN(args.amount), proof)
+            .accountsStrict({
+                config: configPda,
+                user: provider.wallet.publicKey,
+                epoch: epochPda,
+                claimState: claimStatePda,
+                mintAuthority: mintAuthorityPda,
+                mint: mint,
+                userStakeTokenAccount: tokenAccount,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID
+            })
+            .rpc();
+
+        console.log("Transaction:", tx);
+        return;
+    }
+
+    // claims from vesting epochs open a vesting account, released with withdraw_vested.ts
+    const [vestingPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vesting"), epochPda.toBuffer(), provider.wallet.publicKey.toBuffer()],
+        program.programId
+    );
+    const vesting = vestingDuration.gtn(0);
+    console.log("Vesting duration:", vestingDuration.toString());
+
     const tx = await program.methods
         .claimRewards(new anchor.BN(args.amount), proof)
         .accountsStrict({
@@ -99,6 +173,7 @@ const main = async () => {
             user: provider.wallet.publicKey,
             epoch: epochPda,
             claimRecord: claimPda,
+            vesting: vesting ? vestingPda : null,
             mintAuthority: mintAuthorityPda,
             mint: mint,
             userStakeTokenAccount: tokenAccount,
@@ -0,0 +1,101 @@
+import * as anchor from "@coral-xyz/anchor";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+import {PublicKey} from "@solana/web3.js";
+import yargs from "yargs";
+import {getAssociatedTokenAddressSync} from "@solana/spl-token";
+import {
+    allocationsToMerkleTree,
+    idl,
+    makeLeaf
+} from "./cryptolib";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const args = yargs(process.argv.slice(2))
+    .option("claims", {
+        type: "string",
+        description: "Claims to batch: [{\"epoch\": 1, \"amount\": 1000, \"reward_allocations\": \"{allocations: [...]}\"}, ...]",
+        required: true,
+    })
+    .option("mint", {
+        type: "string",
+        description: "Token that will be minted (e.g. PRIME) upon validation of the claim proofs",
+        required: true,
+    })
+    .parseSync();
+
+const program: Program<HastraSolVaultStake> = new anchor.Program(idl as anchor.Idl, provider) as Program<HastraSolVaultStake>;
+
+const main = async () => {
+    const user = provider.wallet.publicKey;
+    const claims: { epoch: number, amount: number, reward_allocations: string }[] = JSON.parse(args.claims);
+
+    const batch = [];
+    const remainingAccounts = [];
+    for (const claim of claims) {
+        const [epochPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("epoch"), new anchor.BN(claim.epoch).toArrayLike(Buffer, "le", 8)],
+            program.programId
+        );
+        const { hashVersion } = await program.account.rewardsEpoch.fetch(epochPda);
+        const { tree } = allocationsToMerkleTree(claim.reward_allocations, claim.epoch, false, hashVersion);
+        const leaf = makeLeaf(user, claim.amount, claim.epoch, hashVersion);
+        const treeProof = tree.getProof(leaf);
+        const verified = tree.verify(treeProof, leaf, tree.getRoot());
+        console.log(`Epoch ${claim.epoch}: amount ${claim.amount}, proof length ${treeProof.length}, verified ${verified}`);
+        if (!verified) {
+            console.warn(`\n!!Proof for epoch ${claim.epoch} is not valid!!\n`);
+        }
+
+        // derive claim record PDA
+        const [claimPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("claim"), epochPda.toBuffer(), user.toBuffer()],
+            program.programId
+        );
+
+        batch.push({
+            amount: new anchor.BN(claim.amount),
+            proof: treeProof.map(p => ({
+                sibling: Array.from(p.data),
+                isLeft: p.position === "left",
+            })),
+        });
+        remainingAccounts.push(
+            {pubkey: epochPda, isWritable: true, isSigner: false},
+            {pubkey: claimPda, isWritable: true, isSigner: false},
+        );
+    }
+
+    const [configPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("config")],
+        program.programId
+    );
+    const [mintAuthorityPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("mint_authority")],
+        program.programId
+    );
+
+    const mint = new anchor.web3.PublicKey(args.mint);
+    // Calculate the Associated Token Account address
+    const tokenAccount = getAssociatedTokenAddressSync(mint, user);
+
+    const tx = await program.methods
+        .claimRewardsBatch(batch)
+        .accountsStrict({
+            config: configPda,
+            user: user,
+            mint: mint,
+            mintAuthority: mintAuthorityPda,
+            userStakeTokenAccount: tokenAccount,
+            tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .remainingAccounts(remainingAccounts)
+        .rpc();
+
+    console.log("Transaction:", tx);
+};
+
+main().catch(console.error);
@@ -0,0 +1,56 @@
+import * as anchor from "@coral-xyz/anchor";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+import yargs from "yargs";
+import {PublicKey} from "@solana/web3.js";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+const args = yargs(process.argv.slice(2))
+    .option("epoch", {
+        type: "number",
+        description: "Index of the expired epoch to close",
+        required: true,
+    })
+    .parseSync();
+
+const main = async () => {
+    const admin = provider.wallet.publicKey;
+
+    const [epochPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("epoch"), new anchor.BN(args.epoch).toArrayLike(Buffer, "le", 8)],
+        program.programId
+    );
+    const [approvalsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("epoch_approvals"), epochPda.toBuffer()],
+        program.programId
+    );
+    const approved = (await provider.connection.getAccountInfo(approvalsPda)) !== null;
+    const [claimBitmapPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("claim_bitmap"), epochPda.toBuffer()],
+        program.programId
+    );
+
+    const epoch = await program.account.rewardsEpoch.fetch(epochPda);
+    const indexed = epoch.leafCount.gtn(0);
+    console.log("Epoch PDA:", epochPda.toBase58());
+    console.log("Claim window ends:", epoch.claimEndTs.toString());
+    console.log("Total:", epoch.total.toString(), "Claimed:", epoch.claimed.toString());
+    console.log("Unclaimed:", epoch.total.sub(epoch.claimed).toString());
+
+    const tx = await program.methods
+        .closeRewardsEpoch()
+        .accounts({
+            admin: admin,
+            epoch: epochPda,
+            approvals: approved ? approvalsPda : null,
+            claimBitmap: indexed ? claimBitmapPda : null,
+        })
+        .rpc();
+    console.log("Transaction:", tx);
+};
+
+main().catch(console.error);
@@ -210,6 +210,33 @@ initialize_program() {
   update_history_var "MINT_AUTHORITY_PDA"
   update_history_var "FREEZE_AUTHORITY_PDA"
   update_history_var "VAULT_AUTHORITY_PDA"
+
+  initialize_stake_escrow
+  initialize_withdrawal_queue
+}
+
+initialize_withdrawal_queue() {
+  INITIALIZE_QUEUE=$(yarn run ts-node scripts/initialize_withdrawal_queue.ts)
+
+  echo "$INITIALIZE_QUEUE"
+  WITHDRAWAL_QUEUE_PDA=$(echo $INITIALIZE_QUEUE | grep -oE 'Withdrawal Queue PDA: ([A-Za-z0-9]+)' | awk '{print $NF}')
+
+  update_history_var "WITHDRAWAL_QUEUE_PDA"
+}
+
+initialize_stake_escrow() {
+  if [ -z "$MINT_TOKEN" ]; then
+    prompt_with_default MINT_TOKEN "Enter Mint Token (staking token minted) address"
+  fi
+
+  INITIALIZE_ESCROW=$(
+    yarn run ts-node scripts/initialize_stake_escrow.ts \
+    --mint "$MINT_TOKEN")
+
+  echo "$INITIALIZE_ESCROW"
+  STAKE_ESCROW_PDA=$(echo $INITIALIZE_ESCROW | grep -oE 'Stake Escrow PDA: ([A-Za-z0-9]+)' | awk '{print $NF}')
+
+  update_history_var "STAKE_ESCROW_PDA"
 }
 
 build_deploy_initialize() {
@@ -283,6 +310,8 @@ show_accounts_and_pdas() {
   echo "Mint Authority PDA:                 $MINT_AUTHORITY_PDA"
   echo "Freeze Authority PDA:               $FREEZE_AUTHORITY_PDA"
   echo "Vault Token Authority PDA:          $VAULT_AUTHORITY_PDA"
+  echo "Stake Escrow PDA:                   $STAKE_ESCROW_PDA"
+  echo "Withdrawal Queue PDA:               $WITHDRAWAL_QUEUE_PDA"
   echo "Freeze Administrators:              $FREEZE_ADMINISTRATORS"
   echo "Rewards Administrators:             $REWARDS_ADMINISTRATORS"
   echo "Unbonding Period (in seconds):      $UNBONDING_PERIOD"
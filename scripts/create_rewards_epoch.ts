@@ -2,6 +2,7 @@ import * as anchor from "@coral-xyz/anchor";
 import {Program} from "@coral-xyz/anchor";
 import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
 import {PublicKey} from "@solana/web3.js";
+import {TOKEN_PROGRAM_ID} from "@solana/spl-token";
 import yargs from "yargs";
 import {
     allocationsToMerkleTree,
@@ -15,14 +16,54 @@ const program: Program<HastraSolVaultStake> = new anchor.Program(idl as anchor.I
 const args = yargs(process.argv.slice(2))
     .option("epoch", {
         type: "number",
-        description: "Epoch index",
-        required: true,
+        description: "Epoch index. Defaults to the config's next epoch index when epochs are sequential",
+        required: false,
     })
     .option("reward_allocations", {
         type: "json",
         description: "Allocations object: {allocations: [{\"account\": \"3m7...sKf\", \"amount\": 1000}, ...]}",
         required: true,
     })
+    .option("vault_token_account", {
+        type: "string",
+        description: "Vault Token Account that holds the Vault Token (e.g. wYLDS). Required when rewards are funded or capped in bps of the vault",
+        required: false,
+    })
+    .option("admin_vault_token_account", {
+        type: "string",
+        description: "Rewards admin's vault token account (e.g. wYLDS) the epoch total is transferred from. Required when rewards are funded",
+        required: false,
+    })
+    .option("cumulative", {
+        type: "boolean",
+        description: "Publish a cumulative epoch: allocations are lifetime totals per user",
+        required: false,
+        default: false,
+    })
+    .option("indexed", {
+        type: "boolean",
+        description: "Publish an indexed epoch: leaves include their index and claims are tracked in a claim bitmap",
+        required: false,
+        default: false,
+    })
+    .option("claim_start_ts", {
+        type: "number",
+        description: "Unix timestamp claims open at, 0 to open as soon as the epoch is created",
+        required: false,
+        default: 0,
+    })
+    .option("claim_end_ts", {
+        type: "number",
+        description: "Unix timestamp claims close at, after which the epoch can be closed. 0 never expires",
+        required: false,
+        default: 0,
+    })
+    .option("draft", {
+        type: "boolean",
+        description: "Create a draft that can be updated or revoked, and is funded and opened by publish_rewards_epoch.ts",
+        required: false,
+        default: false,
+    })
     .option("just_print", {
         type: "boolean",
         description: "If true, just print the leaves and root without creating the epoch on-chain",
@@ -32,8 +73,25 @@ const args = yargs(process.argv.slice(2))
     .parseSync();
 
 const main = async () => {
-    const epochIndex = args.epoch;
-    const { tree, leaves, allocations } = allocationsToMerkleTree(args.reward_allocations, epochIndex);
+    const [configPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("config")],
+        program.programId
+    );
+    const config = await program.account.config.fetch(configPda);
+
+    // with sequential epochs the program only accepts the next index
+    const nextEpochIndex = config.nextEpochIndex.toNumber();
+    if (args.epoch === undefined && nextEpochIndex === 0) {
+        throw new Error("--epoch is required when epochs are not sequential");
+    }
+    const epochIndex = args.epoch ?? nextEpochIndex;
+    if (nextEpochIndex !== 0 && epochIndex !== nextEpochIndex) {
+        throw new Error(`Epochs are sequential: the next epoch index is ${nextEpochIndex}`);
+    }
+    console.log("Epoch index:", epochIndex);
+
+    // new epochs are stamped with the config's hash version, so the tree must be built with it
+    const { tree, leaves, allocations } = allocationsToMerkleTree(args.reward_allocations, epochIndex, args.indexed, config.rewardsHashVersion);
     const root = tree.getRoot();
 
     if (args.just_print) {
@@ -51,22 +109,69 @@ const main = async () => {
     }
     const total = allocations.reduce((acc, a) => acc.add(a.amount), new anchor.BN(0));
 
-    const [configPda] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("config")],
-        program.programId
-    );
     const [epochPda] = PublicKey.findProgramAddressSync(
         [Buffer.from("epoch"), new anchor.BN(epochIndex).toArrayLike(Buffer, "le", 8)],
         program.programId
     );
 
-    const tx = await program.methods
-        .createRewardsEpoch(new anchor.BN(epochIndex), Array.from(root), total)
+    const [vaultAuthorityPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_authority")],
+        program.programId
+    );
+
+    const funded = config.fundedRewards;
+    if (funded && !args.draft && (!args.vault_token_account || !args.admin_vault_token_account)) {
+        throw new Error("Rewards are funded: --vault_token_account and --admin_vault_token_account are required");
+    }
+    console.log("Funded rewards:", funded);
+    console.log("Total:", total.toString());
+
+    console.log("Cumulative:", args.cumulative);
+    console.log("Indexed:", args.indexed);
+    console.log("Claim window:", args.claim_start_ts, "-", args.claim_end_ts);
+    console.log("Draft:", args.draft);
+    const claimStartTs = new anchor.BN(args.claim_start_ts);
+    const claimEndTs = new anchor.BN(args.claim_end_ts);
+
+    if (args.indexed) {
+        const [claimBitmapPda] = PublicKey.findProgramAddressSync(
+            [Buffer.from("claim_bitmap"), epochPda.toBuffer()],
+            program.programId
+        );
+        const tx = await program.methods
+            .createIndexedRewardsEpoch(new anchor.BN(epochIndex), Array.from(root), total, new anchor.BN(allocations.length), claimStartTs, claimEndTs, args.draft)
+            .accountsStrict({
+                config: configPda,
+                admin: provider.wallet.publicKey,
+                epoch: epochPda,
+                claimBitmap: claimBitmapPda,
+                systemProgram: anchor.web3.SystemProgram.programId,
+                vaultAuthority: vaultAuthorityPda,
+                vaultTokenAccount: args.vault_token_account ? new PublicKey(args.vault_token_account) : null,
+                adminVaultTokenAccount: funded ? new PublicKey(args.admin_vault_token_account) : null,
+                tokenProgram: funded ? TOKEN_PROGRAM_ID : null,
+            })
+            .rpc();
+
+        console.log("Claim Bitmap PDA:", claimBitmapPda.toBase58());
+        console.log("Transaction:", tx);
+        return;
+    }
+
+    const createEpoch = args.cumulative
+        ? program.methods.createCumulativeRewardsEpoch(new anchor.BN(epochIndex), Array.from(root), total, claimStartTs, claimEndTs, args.draft)
+        : program.methods.createRewardsEpoch(new anchor.BN(epochIndex), Array.from(root), total, claimStartTs, claimEndTs, args.draft);
+
+    const tx = await createEpoch
         .accountsStrict({
             config: configPda,
             admin: provider.wallet.publicKey,
             epoch: epochPda,
             systemProgram: anchor.web3.SystemProgram.programId,
+            vaultAuthority: vaultAuthorityPda,
+            vaultTokenAccount: args.vault_token_account ? new PublicKey(args.vault_token_account) : null,
+            adminVaultTokenAccount: funded ? new PublicKey(args.admin_vault_token_account) : null,
+            tokenProgram: funded ? TOKEN_PROGRAM_ID : null,
         })
         .rpc();
 
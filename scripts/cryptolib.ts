@@ -8,11 +8,31 @@ export const idl = require("../target/idl/hastra_sol_vault_stake.json");
 export const ZERO32 = Buffer.alloc(0);
 export const sha256 = (x: Buffer) => createHash("sha256").update(x).digest();
 
-export const makeLeaf = (user: PublicKey, amount: anchor.BN | number, epoch: number): Buffer => {
+// Merkle hashing schemes, matching HASH_VERSION_* in the program
+export const HASH_VERSION_LEGACY = 0;
+export const HASH_VERSION_PREFIXED_SORTED = 1;
+const LEAF_PREFIX = Buffer.from([0x00]);
+const NODE_PREFIX = Buffer.from([0x01]);
+
+const leafPrefix = (hashVersion: number): Buffer => hashVersion === HASH_VERSION_LEGACY ? Buffer.alloc(0) : LEAF_PREFIX;
+
+export const makeLeaf = (user: PublicKey, amount: anchor.BN | number, epoch: number, hashVersion: number = HASH_VERSION_PREFIXED_SORTED): Buffer => {
+    return sha256(Buffer.concat([
+        leafPrefix(hashVersion),
+        user.toBuffer(),
+        (anchor.BN.isBN(amount) ? amount : new anchor.BN(amount)).toArrayLike(Buffer, "le", 8),
+        new anchor.BN(epoch).toArrayLike(Buffer, "le", 8),
+    ]));
+}
+
+// Leaves of indexed epochs also commit to a leaf index, the user's bit in the epoch's claim bitmap
+export const makeIndexedLeaf = (user: PublicKey, amount: anchor.BN | number, epoch: number, leafIndex: number, hashVersion: number = HASH_VERSION_PREFIXED_SORTED): Buffer => {
     return sha256(Buffer.concat([
+        leafPrefix(hashVersion),
         user.toBuffer(),
         (anchor.BN.isBN(amount) ? amount : new anchor.BN(amount)).toArrayLike(Buffer, "le", 8),
         new anchor.BN(epoch).toArrayLike(Buffer, "le", 8),
+        new anchor.BN(leafIndex).toArrayLike(Buffer, "le", 8),
     ]));
 }
 
@@ -35,24 +55,30 @@ export const padToPowerOfTwo = (leaves: Buffer<ArrayBufferLike>[])=> {
     return padded;
 }
 
-export const allocationsToMerkleTree = (allocationString: string, epochIndex: number) => {
+export const allocationsToMerkleTree = (allocationString: string, epochIndex: number, indexed: boolean = false, hashVersion: number = HASH_VERSION_PREFIXED_SORTED) => {
     const allocations: {user: PublicKey, amount: anchor.BN}[] = (JSON.parse(allocationString).allocations as {account: string, amount: number}[]).map((a: {account: string, amount: number}) => {
         return {user: new PublicKey(a.account), amount: new anchor.BN(a.amount)};
     });
 
     console.log("Epoch:", epochIndex.toString());
+    console.log("Hash version:", hashVersion);
     console.log("Allocations:", allocations.map(a => ({user: a.user.toBase58(), amount: a.amount.toString()})));
 
-    const leaves = padToPowerOfTwo(allocations.map(a => makeLeaf(a.user, a.amount, epochIndex)));
+    // for indexed epochs the leaf index is the allocation's position in the list
+    const unpadded = allocations.map((a, i) => indexed
+        ? makeIndexedLeaf(a.user, a.amount, epochIndex, i, hashVersion)
+        : makeLeaf(a.user, a.amount, epochIndex, hashVersion));
+    // prefixed trees hash sorted pairs and carry odd nodes up unpaired, so they need no padding
+    const leaves = hashVersion === HASH_VERSION_LEGACY ? padToPowerOfTwo(unpadded) : unpadded.sort(Buffer.compare);
 
     console.log(`\nLeaves (${leaves.length}):`);
     leaves.forEach((leaf, i) => {
         console.log(`${i}: ${leaf.toString("hex")}`);
     });
 
-    const tree = new MerkleTree(leaves, sha256, {
-        sortPairs: false,
-    });
+    const tree = hashVersion === HASH_VERSION_LEGACY
+        ? new MerkleTree(leaves, sha256, {sortPairs: false})
+        : new MerkleTree(leaves, (x: Buffer) => sha256(Buffer.concat([NODE_PREFIX, x])), {sortPairs: true});
 
     console.log(`\nTree:`);
     console.log(`Depth: ${tree.getDepth()}`);
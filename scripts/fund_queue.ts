@@ -0,0 +1,80 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { HastraSolVaultStake } from "../target/types/hastra_sol_vault_stake";
+import yargs from "yargs";
+import {
+    PublicKey,
+} from "@solana/web3.js";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+const args = yargs(process.argv.slice(2))
+    .option("amount", {
+        type: "number",
+        description: "Amount of vault tokens (e.g. wYLDS) to transfer into the vault",
+        required: true,
+    })
+    .option("vault_token_account", {
+        type: "string",
+        description: "Vault Token Account that holds the Vault Token (e.g. wYLDS)",
+        required: true,
+    })
+    .option("funder_vault_token_account", {
+        type: "string",
+        description: "Admin's vault token account the tokens are transferred from",
+        required: true,
+    })
+    .parseSync();
+
+const main = async () => {
+    // bpf_loader_upgradeable program id
+    const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+        "BPFLoaderUpgradeab1e11111111111111111111111"
+    );
+    // derive ProgramData PDA
+    const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_ID
+    );
+    const [withdrawalQueuePda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("withdrawal_queue")],
+        program.programId
+    );
+
+    const amount = new anchor.BN(args.amount);
+    const vaultTokenAccount = new anchor.web3.PublicKey(args.vault_token_account);
+    const funderVaultTokenAccount = new anchor.web3.PublicKey(args.funder_vault_token_account);
+
+    const queue = await program.account.withdrawalQueue.fetch(withdrawalQueuePda);
+    console.log("Withdrawal Queue PDA:", withdrawalQueuePda.toBase58());
+    console.log("Queue head/tail:", queue.head.toString(), queue.tail.toString());
+    console.log("Queued amount:", queue.queuedAmount.toString());
+    console.log("Funding amount:", amount.toString());
+
+    await program.methods
+        .fundQueue(amount)
+        .accounts({
+            vaultTokenAccount: vaultTokenAccount,
+            funderVaultTokenAccount: funderVaultTokenAccount,
+            programData: programData,
+            signer: provider.wallet.publicKey,
+        })
+        .rpc()
+        .then((tx) => {
+            console.log("Transaction:", tx);
+        })
+        .catch(
+            (err) => {
+                if (err.getLogs) {
+                    console.dir(err.getLogs);
+                }
+                console.error("Transaction failed:", err);
+                throw err;
+            }
+        )
+};
+
+main().catch(console.error);
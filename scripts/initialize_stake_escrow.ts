@@ -0,0 +1,65 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { HastraSolVaultStake } from "../target/types/hastra_sol_vault_stake";
+import yargs from "yargs";
+import {
+    PublicKey,
+} from "@solana/web3.js";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+const args = yargs(process.argv.slice(2))
+    .option("mint", {
+        type: "string",
+        description: "Mint token (e.g. PRIME) that will be held in escrow while unbonding",
+        required: true,
+    })
+    .parseSync();
+
+const main = async () => {
+    // bpf_loader_upgradeable program id
+    const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+        "BPFLoaderUpgradeab1e11111111111111111111111"
+    );
+    // derive ProgramData PDA
+    const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_ID
+    );
+    const [stakeEscrowPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_escrow")],
+        program.programId
+    );
+
+    const mint = new anchor.web3.PublicKey(args.mint);
+
+    console.log("Program ID:", program.programId.toBase58());
+    console.log("Mint (token held in escrow):", mint.toBase58());
+    console.log("Stake Escrow PDA:", stakeEscrowPda.toBase58());
+
+    await program.methods
+        .initializeStakeEscrow()
+        .accounts({
+            mint: mint,
+            programData: programData,
+            signer: provider.wallet.publicKey,
+        })
+        .rpc()
+        .then((tx) => {
+            console.log("Transaction:", tx);
+        })
+        .catch(
+            (err) => {
+                if (err.getLogs) {
+                    console.dir(err.getLogs);
+                }
+                console.error("Transaction failed:", err);
+                throw err;
+            }
+        )
+};
+
+main().catch(console.error);
@@ -0,0 +1,52 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { HastraSolVaultStake } from "../target/types/hastra_sol_vault_stake";
+import {
+    PublicKey,
+} from "@solana/web3.js";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+const main = async () => {
+    // bpf_loader_upgradeable program id
+    const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+        "BPFLoaderUpgradeab1e11111111111111111111111"
+    );
+    // derive ProgramData PDA
+    const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_ID
+    );
+    const [withdrawalQueuePda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("withdrawal_queue")],
+        program.programId
+    );
+
+    console.log("Program ID:", program.programId.toBase58());
+    console.log("Withdrawal Queue PDA:", withdrawalQueuePda.toBase58());
+
+    await program.methods
+        .initializeWithdrawalQueue()
+        .accounts({
+            programData: programData,
+            signer: provider.wallet.publicKey,
+        })
+        .rpc()
+        .then((tx) => {
+            console.log("Transaction:", tx);
+        })
+        .catch(
+            (err) => {
+                if (err.getLogs) {
+                    console.dir(err.getLogs);
+                }
+                console.error("Transaction failed:", err);
+                throw err;
+            }
+        )
+};
+
+main().catch(console.error);
@@ -0,0 +1,74 @@
+import * as anchor from "@coral-xyz/anchor";
+import yargs from "yargs";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+const args = yargs(process.argv.slice(2))
+    .option("amount", {
+        type: "number",
+        description: "Amount of staking tokens (e.g. PRIME) to burn for an immediate payout",
+        required: true,
+    })
+    .option("mint", {
+        type: "string",
+        description: "Token that is minted when staking (e.g. PRIME)",
+        required: true,
+    })
+    .option("vault_token_account", {
+        type: "string",
+        description: "Vault Token Account that holds the Vault Token (e.g. wYLDS)",
+        required: true,
+    })
+    .option("user_mint_token_account", {
+        type: "string",
+        description: "User's staking token account (e.g. PRIME)",
+        required: true,
+    })
+    .option("user_vault_token_account", {
+        type: "string",
+        description: "User's vault token account (e.g. wYLDS) that receives the payout",
+        required: true,
+    })
+    .parseSync();
+
+const main = async () => {
+    const [configPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("config")],
+        program.programId
+    );
+    const config = await program.account.config.fetch(configPda);
+
+    const amount = new anchor.BN(args.amount);
+    const mint = new anchor.web3.PublicKey(args.mint);
+    const vaultTokenAccount = new anchor.web3.PublicKey(args.vault_token_account);
+    const userMintTokenAccount = new anchor.web3.PublicKey(args.user_mint_token_account);
+    const userVaultTokenAccount = new anchor.web3.PublicKey(args.user_vault_token_account);
+
+    console.log("Config PDA:", configPda.toBase58());
+    console.log("Amount:", amount.toString());
+    console.log("Exit fee (bps):", config.instantUnbondFeeBps.toString());
+    console.log("Treasury:", config.treasury.toBase58());
+    console.log("Epoch cap:", config.instantUnbondEpochCap.toString());
+    console.log("Used this epoch:", config.instantUnbondEpochUsed.toString(), "(epoch", config.instantUnbondEpoch.toString() + ")");
+
+    const tx = await program.methods
+        .instantUnbond(amount)
+        .accounts({
+            vaultTokenAccount: vaultTokenAccount,
+            treasuryTokenAccount: config.treasury,
+            signer: provider.wallet.publicKey,
+            userMintTokenAccount: userMintTokenAccount,
+            userVaultTokenAccount: userVaultTokenAccount,
+            mint: mint,
+        })
+        .rpc();
+
+    console.log("Transaction:", tx);
+};
+
+main().catch(console.error);
@@ -0,0 +1,45 @@
+import * as anchor from "@coral-xyz/anchor";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+import {PublicKey} from "@solana/web3.js";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+const main = async () => {
+    const signer = provider.wallet.publicKey;
+
+    const [configPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("config")],
+        program.programId
+    );
+    // bpf_loader_upgradeable program id
+    const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+        "BPFLoaderUpgradeab1e11111111111111111111111"
+    );
+    // derive ProgramData PDA
+    const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_ID
+    );
+
+    const before = await provider.connection.getAccountInfo(configPda);
+    console.log("Config PDA:", configPda.toBase58());
+    console.log("Config size before:", before?.data.length);
+
+    const tx = await program.methods
+        .migrateConfig()
+        .accounts({
+            programData: programData,
+            signer: signer,
+        })
+        .rpc();
+    console.log("Transaction:", tx);
+
+    const after = await provider.connection.getAccountInfo(configPda);
+    console.log("Config size after:", after?.data.length);
+};
+
+main().catch(console.error);
@@ -0,0 +1,62 @@
+import * as anchor from "@coral-xyz/anchor";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+import yargs from "yargs";
+import {PublicKey} from "@solana/web3.js";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+const args = yargs(process.argv.slice(2))
+    .option("epoch", {
+        type: "number",
+        description: "Epoch index",
+        required: true,
+    })
+    .option("claimed", {
+        type: "number",
+        description: "Rewards already claimed from the epoch, tallied from RewardsClaimed events",
+        required: true,
+    })
+    .parseSync();
+
+const main = async () => {
+    const signer = provider.wallet.publicKey;
+
+    // bpf_loader_upgradeable program id
+    const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+        "BPFLoaderUpgradeab1e11111111111111111111111"
+    );
+    // derive ProgramData PDA
+    const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_ID
+    );
+    const [epochPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("epoch"), new anchor.BN(args.epoch).toArrayLike(Buffer, "le", 8)],
+        program.programId
+    );
+
+    const claimed = new anchor.BN(args.claimed);
+    const before = await provider.connection.getAccountInfo(epochPda);
+    console.log("Epoch PDA:", epochPda.toBase58());
+    console.log("Epoch size before:", before?.data.length);
+    console.log("Claimed:", claimed.toString());
+
+    const tx = await program.methods
+        .migrateRewardsEpoch(claimed)
+        .accounts({
+            epoch: epochPda,
+            programData: programData,
+            signer: signer,
+        })
+        .rpc();
+    console.log("Transaction:", tx);
+
+    const epoch = await program.account.rewardsEpoch.fetch(epochPda);
+    console.log("Total:", epoch.total.toString(), "Claimed:", epoch.claimed.toString());
+};
+
+main().catch(console.error);
@@ -0,0 +1,71 @@
+import * as anchor from "@coral-xyz/anchor";
+import yargs from "yargs";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+const args = yargs(process.argv.slice(2))
+    .option("user_mint_token_account", {
+        type: "string",
+        description: "User's mint token account the ticket's stake tokens (e.g. PRIME) will be escrowed from",
+        required: true,
+    })
+    .parseSync();
+
+const main = async () => {
+    const signer = provider.wallet.publicKey;
+
+    const [configPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("config")],
+        program.programId
+    );
+    const [legacyTicketPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("ticket"), signer.toBuffer()],
+        program.programId
+    );
+    const [userStatePda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("user_state"), signer.toBuffer()],
+        program.programId
+    );
+    const userState = await program.account.userStakeState.fetchNullable(userStatePda);
+    const nonce = userState ? userState.nextTicketNonce : new anchor.BN(0);
+    const [ticketPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("ticket"), signer.toBuffer(), nonce.toArrayLike(Buffer, "le", 8)],
+        program.programId
+    );
+    const [stakeEscrowPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_escrow")],
+        program.programId
+    );
+
+    const userMintTokenAccount = new anchor.web3.PublicKey(args.user_mint_token_account);
+
+    console.log("User Mint Token Account:", userMintTokenAccount.toBase58());
+    console.log("Legacy Unbonding Ticket:", legacyTicketPda.toBase58());
+    console.log("User Unbonding Ticket:", ticketPda.toBase58());
+    console.log("Ticket Nonce:", nonce.toString());
+    console.log("Stake Escrow PDA:", stakeEscrowPda.toBase58());
+    console.log("Config PDA:", configPda.toBase58());
+
+    const tx = await program.methods
+        .migrateTicket()
+        .accountsStrict({
+            config: configPda,
+            signer: signer,
+            legacyTicket: legacyTicketPda,
+            userState: userStatePda,
+            ticket: ticketPda,
+            userMintTokenAccount: userMintTokenAccount,
+            stakeEscrowTokenAccount: stakeEscrowPda,
+            tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+            systemProgram: anchor.web3.SystemProgram.programId,
+        }).rpc();
+
+    console.log("Transaction:", tx);
+};
+
+main().catch(console.error);
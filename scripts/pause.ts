@@ -17,6 +17,11 @@ const args = yargs(process.argv.slice(2))
         description: "Set to true to pause the program, false to unpause",
         required: true,
     })
+    .option("reason", {
+        type: "number",
+        description: "Short reason code recorded in the PauseFlagsUpdated event",
+        default: 0,
+    })
     .parseSync();
 
 const main = async () => {
@@ -39,7 +44,7 @@ const main = async () => {
 
     // Call initialize
     await program.methods
-        .pause(args.pause)
+        .pause(args.pause, args.reason)
         .accounts({
             programData: programData,
         })
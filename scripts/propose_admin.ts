@@ -0,0 +1,47 @@
+import * as anchor from "@coral-xyz/anchor";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+import yargs from "yargs";
+import {PublicKey} from "@solana/web3.js";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+const args = yargs(process.argv.slice(2))
+    .option("new_admin", {
+        type: "string",
+        description: "Key that must run accept_admin.ts to take over administration. The default pubkey cancels a pending handover",
+        required: true,
+    })
+    .parseSync();
+
+const main = async () => {
+    const signer = provider.wallet.publicKey;
+
+    // bpf_loader_upgradeable program id
+    const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+        "BPFLoaderUpgradeab1e11111111111111111111111"
+    );
+    // derive ProgramData PDA
+    const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_ID
+    );
+
+    console.log("Proposed admin:", args.new_admin);
+    console.log("ProgramData PDA:", programData.toBase58());
+    console.log("Signer:", signer.toBase58());
+
+    const tx = await program.methods
+        .proposeAdmin(new PublicKey(args.new_admin))
+        .accounts({
+            programData: programData,
+            signer: signer,
+        })
+        .rpc();
+    console.log("Transaction:", tx);
+};
+
+main().catch(console.error);
@@ -0,0 +1,121 @@
+import * as anchor from "@coral-xyz/anchor";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+import yargs from "yargs";
+import {PublicKey} from "@solana/web3.js";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+// Only the options passed are changed; execute with execute_config_change.ts once the delay has passed
+const args = yargs(process.argv.slice(2))
+    .option("unbonding_period", {
+        type: "number",
+        description: "New unbonding period in seconds",
+    })
+    .option("fee_bps", {
+        type: "number",
+        description: "Instant unbond exit fee in basis points (max 5000). Requires epoch_cap and treasury_token_account",
+    })
+    .option("epoch_cap", {
+        type: "number",
+        description: "Max vault tokens paid out by instant unbond per epoch, 0 disables instant unbond",
+    })
+    .option("treasury_token_account", {
+        type: "string",
+        description: "Vault token account (e.g. wYLDS) that receives the exit fees",
+    })
+    .option("freeze_administrators", {
+        type: "string",
+        description: "Comma-separated list of freeze administrator public keys",
+    })
+    .option("rewards_administrators", {
+        type: "string",
+        description: "Comma-separated list of rewards administrator public keys",
+    })
+    .option("delay", {
+        type: "number",
+        description: "New config change delay in seconds",
+    })
+    .option("pause_guardians", {
+        type: "string",
+        description: "Comma-separated list of pause guardian public keys",
+    })
+    .option("approval_threshold", {
+        type: "number",
+        description: "Rewards admin approvals needed to publish an epoch, at most the number of rewards administrators",
+    })
+    .option("max_epoch_rewards_bps", {
+        type: "number",
+        description: "Epoch rewards cap in basis points of the vault balance (max 10000, 0 disables). Requires max_epoch_rewards",
+    })
+    .option("max_epoch_rewards", {
+        type: "number",
+        description: "Absolute epoch rewards cap, 0 disables. Requires max_epoch_rewards_bps",
+    })
+    .option("new_admin", {
+        type: "string",
+        description: "Admin to hand over to, who then calls accept_admin.ts. The default pubkey cancels a pending handover",
+    })
+    .parseSync();
+
+const toKeys = (list?: string) => list === undefined
+    ? null
+    : list.split(",").filter((s) => s.length > 0).map((s: string) => new PublicKey(s));
+
+const main = async () => {
+    const signer = provider.wallet.publicKey;
+
+    // bpf_loader_upgradeable program id
+    const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+        "BPFLoaderUpgradeab1e11111111111111111111111"
+    );
+    // derive ProgramData PDA
+    const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_ID
+    );
+
+    const setsInstantUnbond = args.fee_bps !== undefined;
+    if (setsInstantUnbond && (args.epoch_cap === undefined || args.treasury_token_account === undefined)) {
+        throw new Error("fee_bps requires epoch_cap and treasury_token_account");
+    }
+    const treasury = setsInstantUnbond ? new PublicKey(args.treasury_token_account) : null;
+    const setsRewardsCap = args.max_epoch_rewards_bps !== undefined || args.max_epoch_rewards !== undefined;
+    if (setsRewardsCap && (args.max_epoch_rewards_bps === undefined || args.max_epoch_rewards === undefined)) {
+        throw new Error("max_epoch_rewards_bps and max_epoch_rewards must be passed together");
+    }
+    const change = {
+        unbondingPeriod: args.unbonding_period !== undefined ? new anchor.BN(args.unbonding_period) : null,
+        instantUnbond: setsInstantUnbond
+            ? {feeBps: args.fee_bps, epochCap: new anchor.BN(args.epoch_cap), treasury: treasury}
+            : null,
+        freezeAdministrators: toKeys(args.freeze_administrators),
+        rewardsAdministrators: toKeys(args.rewards_administrators),
+        configChangeDelay: args.delay !== undefined ? new anchor.BN(args.delay) : null,
+        pauseGuardians: toKeys(args.pause_guardians),
+        pendingAdmin: args.new_admin !== undefined ? new PublicKey(args.new_admin) : null,
+        rewardsApprovalThreshold: args.approval_threshold !== undefined ? args.approval_threshold : null,
+        epochRewardsCap: setsRewardsCap
+            ? {maxBps: args.max_epoch_rewards_bps, maxAmount: new anchor.BN(args.max_epoch_rewards)}
+            : null,
+    };
+
+    console.log("Config change:", JSON.stringify(change));
+    console.log("ProgramData PDA:", programData.toBase58());
+    console.log("Signer:", signer.toBase58());
+
+    const tx = await program.methods
+        .proposeConfigChange(change)
+        .accounts({
+            treasuryTokenAccount: treasury,
+            programData: programData,
+            signer: signer,
+        })
+        .rpc();
+    console.log("Transaction:", tx);
+};
+
+main().catch(console.error);
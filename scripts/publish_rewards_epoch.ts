@@ -0,0 +1,72 @@
+import * as anchor from "@coral-xyz/anchor";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+import {PublicKey} from "@solana/web3.js";
+import {TOKEN_PROGRAM_ID} from "@solana/spl-token";
+import yargs from "yargs";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+const args = yargs(process.argv.slice(2))
+    .option("epoch", {
+        type: "number",
+        description: "Index of the draft epoch to publish",
+        required: true,
+    })
+    .option("vault_token_account", {
+        type: "string",
+        description: "Vault Token Account that holds the Vault Token (e.g. wYLDS). Required when rewards are funded or capped in bps of the vault",
+        required: false,
+    })
+    .option("admin_vault_token_account", {
+        type: "string",
+        description: "Rewards admin's Vault Token account the epoch total is transferred from. Required when rewards are funded",
+        required: false,
+    })
+    .parseSync();
+
+const main = async () => {
+    const [configPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("config")],
+        program.programId
+    );
+    const [epochPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("epoch"), new anchor.BN(args.epoch).toArrayLike(Buffer, "le", 8)],
+        program.programId
+    );
+    const [approvalsPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("epoch_approvals"), epochPda.toBuffer()],
+        program.programId
+    );
+    const approved = (await provider.connection.getAccountInfo(approvalsPda)) !== null;
+
+    const config = await program.account.config.fetch(configPda);
+    const funded = config.fundedRewards;
+    if (funded && (!args.vault_token_account || !args.admin_vault_token_account)) {
+        throw new Error("Rewards are funded: --vault_token_account and --admin_vault_token_account are required");
+    }
+
+    const epoch = await program.account.rewardsEpoch.fetch(epochPda);
+    console.log("Epoch PDA:", epochPda.toBase58());
+    console.log("Merkle root:", Buffer.from(epoch.merkleRoot).toString("hex"));
+    console.log("Total:", epoch.total.toString());
+    console.log("Funded rewards:", funded);
+
+    const tx = await program.methods
+        .publishRewardsEpoch()
+        .accounts({
+            admin: provider.wallet.publicKey,
+            epoch: epochPda,
+            approvals: approved ? approvalsPda : null,
+            vaultTokenAccount: args.vault_token_account ? new PublicKey(args.vault_token_account) : null,
+            adminVaultTokenAccount: funded ? new PublicKey(args.admin_vault_token_account) : null,
+            tokenProgram: funded ? TOKEN_PROGRAM_ID : null,
+        })
+        .rpc();
+    console.log("Transaction:", tx);
+};
+
+main().catch(console.error);
@@ -11,10 +11,21 @@ const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultSt
 const args = yargs(process.argv.slice(2))
     .option("mint", {
         type: "string",
-        description: "The staking mint token that will be burned (e.g. PRIME) at redeem.",
+        description: "The staking mint token (e.g. PRIME) escrowed at unbond that will be burned at redeem.",
         required: true,
     })
 
+    .option("nonce", {
+        type: "number",
+        description: "Nonce of the unbonding ticket to redeem",
+        required: true,
+    })
+    .option("partial", {
+        type: "boolean",
+        description: "Redeem as much as the vault currently holds and keep the ticket open for the rest",
+        required: false,
+        default: false,
+    })
     .option("vault_token_account", {
         type: "string",
         description: "Vault Token Account that holds the Vault Token (e.g. wYLDS)",
@@ -27,7 +38,7 @@ const args = yargs(process.argv.slice(2))
     })
     .option("user_mint_token_account", {
         type: "string",
-        description: "User's mint token account address where the staking mint tokens (e.g. PRIME) will be burned. Must be associated token account for the mint token (e.g. PRIME)",
+        description: "User's mint token account (e.g. PRIME). Redeeming fails while it is frozen",
         required: true,
     })
 
@@ -48,7 +59,17 @@ const main = async () => {
     );
 
     const [ticketPda] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("ticket"), signer.toBuffer()],
+        [Buffer.from("ticket"), signer.toBuffer(), new anchor.BN(args.nonce).toArrayLike(Buffer, "le", 8)],
+        program.programId
+    );
+
+    const [stakeEscrowPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_escrow")],
+        program.programId
+    );
+
+    const [withdrawalQueuePda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("withdrawal_queue")],
         program.programId
     );
 
@@ -65,9 +86,10 @@ const main = async () => {
     console.log(`Config PDA: ${configPda.toBase58()}`);
     console.log(`Vault Authority PDA: ${vaultAuthorityPda.toBase58()}`);
     console.log(`Ticket PDA: ${ticketPda.toBase58()}`);
+    console.log(`Stake Escrow PDA: ${stakeEscrowPda.toBase58()}`);
 
-    const tx = await program.methods
-        .redeem()
+    const redeem = args.partial ? program.methods.redeemPartial() : program.methods.redeem();
+    const tx = await redeem
         .accountsStrict({
             config: configPda,
             vaultTokenAccount: vaultTokenAccount,
@@ -75,6 +97,8 @@ const main = async () => {
             signer: signer,
             userVaultTokenAccount: userVaultTokenAccount,
             userMintTokenAccount: userMintTokenAccount,
+            stakeEscrowTokenAccount: stakeEscrowPda,
+            withdrawalQueue: withdrawalQueuePda,
             mint: mint,
             tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
             ticket: ticketPda
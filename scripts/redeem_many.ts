@@ -0,0 +1,103 @@
+import * as anchor from "@coral-xyz/anchor";
+import yargs from "yargs";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+const args = yargs(process.argv.slice(2))
+    .option("mint", {
+        type: "string",
+        description: "The staking mint token (e.g. PRIME) escrowed at unbond that will be burned at redeem.",
+        required: true,
+    })
+    .option("nonces", {
+        type: "string",
+        description: "Comma separated list of matured unbonding ticket nonces to redeem",
+        required: true,
+    })
+    .option("vault_token_account", {
+        type: "string",
+        description: "Vault Token Account that holds the Vault Token (e.g. wYLDS)",
+        required: true,
+    })
+    .option("user_vault_token_account", {
+        type: "string",
+        description: "User's vault token account address where the vaulted tokens will be sent to. Must be associated token account for the vault token (e.g. wYLDS)",
+        required: true,
+    })
+    .option("user_mint_token_account", {
+        type: "string",
+        description: "User's mint token account (e.g. PRIME). Redeeming fails while it is frozen",
+        required: true,
+    })
+    .parseSync();
+
+const main = async () => {
+    const signer = provider.wallet.publicKey;
+
+    // Derive PDAs
+    const [configPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("config")],
+        program.programId
+    );
+
+    const [vaultAuthorityPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("vault_authority")],
+        program.programId
+    );
+
+    const [stakeEscrowPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_escrow")],
+        program.programId
+    );
+
+    const [withdrawalQueuePda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("withdrawal_queue")],
+        program.programId
+    );
+
+    const ticketPdas = args.nonces.split(",").map((n: string) =>
+        anchor.web3.PublicKey.findProgramAddressSync(
+            [Buffer.from("ticket"), signer.toBuffer(), new anchor.BN(parseInt(n)).toArrayLike(Buffer, "le", 8)],
+            program.programId
+        )[0]
+    );
+
+    // Program args
+    const mint = new anchor.web3.PublicKey(args.mint);
+    const vaultTokenAccount = new anchor.web3.PublicKey(args.vault_token_account);
+    const userVaultTokenAccount = new anchor.web3.PublicKey(args.user_vault_token_account);
+    const userMintTokenAccount = new anchor.web3.PublicKey(args.user_mint_token_account);
+
+    console.log(`Mint (token to be burned e.g. PRIME): ${mint.toBase58()}`);
+    console.log(`Vault Token Account (e.g. wYLDS): ${vaultTokenAccount.toBase58()}`);
+    console.log(`User Vault Token Account: ${userVaultTokenAccount.toBase58()}`);
+    console.log(`Config PDA: ${configPda.toBase58()}`);
+    console.log(`Vault Authority PDA: ${vaultAuthorityPda.toBase58()}`);
+    console.log(`Ticket PDAs: ${ticketPdas.map((t) => t.toBase58()).join(", ")}`);
+
+    const tx = await program.methods
+        .redeemMany()
+        .accountsStrict({
+            config: configPda,
+            vaultTokenAccount: vaultTokenAccount,
+            vaultAuthority: vaultAuthorityPda,
+            signer: signer,
+            userVaultTokenAccount: userVaultTokenAccount,
+            userMintTokenAccount: userMintTokenAccount,
+            stakeEscrowTokenAccount: stakeEscrowPda,
+            withdrawalQueue: withdrawalQueuePda,
+            mint: mint,
+            tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(ticketPdas.map((pubkey) => ({pubkey, isWritable: true, isSigner: false})))
+        .rpc();
+
+    console.log("Transaction:", tx);
+};
+
+main().catch(console.error);
@@ -0,0 +1,63 @@
+import * as anchor from "@coral-xyz/anchor";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+import yargs from "yargs";
+import {PublicKey} from "@solana/web3.js";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+const args = yargs(process.argv.slice(2))
+    .option("amount", {
+        type: "number",
+        description: "Vault tokens (e.g. wYLDS) of yield to credit to stake token holders",
+        required: true,
+    })
+    .option("mint", {
+        type: "string",
+        description: "Token that is minted when staking (e.g. PRIME)",
+        required: true,
+    })
+    .parseSync();
+
+const main = async () => {
+    const signer = provider.wallet.publicKey;
+
+    const [configPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("config")],
+        program.programId
+    );
+    // bpf_loader_upgradeable program id
+    const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+        "BPFLoaderUpgradeab1e11111111111111111111111"
+    );
+    // derive ProgramData PDA
+    const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_ID
+    );
+
+    const amount = new anchor.BN(args.amount);
+    const mint = new PublicKey(args.mint);
+
+    const before = await program.account.config.fetch(configPda);
+    console.log("Total assets before:", before.totalAssets.toString());
+    console.log("Yield:", amount.toString());
+
+    const tx = await program.methods
+        .reportYield(amount)
+        .accounts({
+            mint: mint,
+            programData: programData,
+            signer: signer,
+        })
+        .rpc();
+    console.log("Transaction:", tx);
+
+    const after = await program.account.config.fetch(configPda);
+    console.log("Total assets after:", after.totalAssets.toString());
+};
+
+main().catch(console.error);
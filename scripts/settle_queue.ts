@@ -0,0 +1,66 @@
+import * as anchor from "@coral-xyz/anchor";
+import yargs from "yargs";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+import {getAssociatedTokenAddressSync} from "@solana/spl-token";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+const args = yargs(process.argv.slice(2))
+    .option("vault_token_account", {
+        type: "string",
+        description: "Vault Token Account that holds the Vault Token (e.g. wYLDS)",
+        required: true,
+    })
+    .parseSync();
+
+const main = async () => {
+    const [withdrawalQueuePda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("withdrawal_queue")],
+        program.programId
+    );
+    const queue = await program.account.withdrawalQueue.fetch(withdrawalQueuePda);
+    if (queue.head.eq(queue.tail)) {
+        console.log("Withdrawal queue is empty");
+        return;
+    }
+
+    // Find the ticket at the head of the queue
+    const tickets = await program.account.unbondingTicket.all();
+    const head = tickets.find((t) => t.account.queued && t.account.queuePosition.eq(queue.head));
+    if (!head) {
+        throw new Error(`No ticket found at queue position ${queue.head.toString()}`);
+    }
+
+    const config = await program.account.config.fetch(
+        anchor.web3.PublicKey.findProgramAddressSync([Buffer.from("config")], program.programId)[0]
+    );
+    const vaultTokenAccount = new anchor.web3.PublicKey(args.vault_token_account);
+    const ownerVaultTokenAccount = getAssociatedTokenAddressSync(config.vault, head.account.owner);
+    const ownerMintTokenAccount = getAssociatedTokenAddressSync(config.mint, head.account.owner);
+
+    console.log("Queue head/tail:", queue.head.toString(), queue.tail.toString());
+    console.log("Head Ticket:", head.publicKey.toBase58());
+    console.log("Ticket Owner:", head.account.owner.toBase58());
+    console.log("Remaining:", head.account.remainingAmount.toString());
+
+    const tx = await program.methods
+        .settleQueue()
+        .accounts({
+            ticket: head.publicKey,
+            owner: head.account.owner,
+            vaultTokenAccount: vaultTokenAccount,
+            ownerVaultTokenAccount: ownerVaultTokenAccount,
+            ownerMintTokenAccount: ownerMintTokenAccount,
+            mint: config.mint,
+            signer: provider.wallet.publicKey,
+        })
+        .rpc();
+
+    console.log("Transaction:", tx);
+};
+
+main().catch(console.error);
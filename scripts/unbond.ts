@@ -11,17 +11,17 @@ const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultSt
 const args = yargs(process.argv.slice(2))
     .option("mint", {
         type: "string",
-        description: "Mint token that will be burned (e.g. PRIME) after unbonding period.",
+        description: "Mint token that will be escrowed at unbond and burned (e.g. PRIME) after unbonding period.",
         required: true,
     })
     .option("amount", {
         type: "number",
-        description: "Amount of mint tokens to burn at unbond",
+        description: "Amount of mint tokens to escrow at unbond",
         required: true,
     })
     .option("user_mint_token_account", {
         type: "string",
-        description: "User's mint token account where tokens will be burned from. Must be associated token account for the burned mint token (e.g. PRIME)",
+        description: "User's mint token account where tokens will be escrowed from. Must be associated token account for the mint token (e.g. PRIME)",
         required: true,
     })
     .parseSync();
@@ -35,9 +35,23 @@ const main = async () => {
         program.programId
     );
 
+    // Derive user state PDA that tracks the nonce of the user's next ticket
+    const [userStatePda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("user_state"), signer.toBuffer()],
+        program.programId
+    );
+    const userState = await program.account.userStakeState.fetchNullable(userStatePda);
+    const nonce = userState ? userState.nextTicketNonce : new anchor.BN(0);
+
     // Derive ticket PDA
     const [ticketPda] = anchor.web3.PublicKey.findProgramAddressSync(
-        [Buffer.from("ticket"), signer.toBuffer()],
+        [Buffer.from("ticket"), signer.toBuffer(), nonce.toArrayLike(Buffer, "le", 8)],
+        program.programId
+    );
+
+    // Derive stake escrow PDA that holds the unbonding stake tokens
+    const [stakeEscrowPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_escrow")],
         program.programId
     );
 
@@ -48,8 +62,10 @@ const main = async () => {
 
     console.log("Burned Mint (token to be burned e.g. PRIME)", mint.toBase58());
     console.log("Amount:", amount.toString());
-    console.log("User Mint Token Account to be burned:", userMintTokenAccount.toBase58());
+    console.log("User Mint Token Account to be escrowed:", userMintTokenAccount.toBase58());
     console.log("User Unbonding Ticket:", ticketPda.toBase58());
+    console.log("Ticket Nonce:", nonce.toString());
+    console.log("Stake Escrow PDA:", stakeEscrowPda.toBase58());
     console.log("Config PDA:", configPda.toBase58());
 
     const tx = await program.methods
@@ -59,7 +75,10 @@ const main = async () => {
             mint: mint,
             signer: signer,
             userMintTokenAccount: userMintTokenAccount,
+            stakeEscrowTokenAccount: stakeEscrowPda,
+            userState: userStatePda,
             ticket: ticketPda,
+            tokenProgram: anchor.utils.token.TOKEN_PROGRAM_ID,
             systemProgram: anchor.web3.SystemProgram.programId,
         }).rpc();
 
@@ -0,0 +1,63 @@
+import * as anchor from "@coral-xyz/anchor";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+import yargs from "yargs";
+import {PublicKey} from "@solana/web3.js";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+const args = yargs(process.argv.slice(2))
+    .option("fee_bps", {
+        type: "number",
+        description: "Instant unbond exit fee in basis points (max 5000)",
+        required: true,
+    })
+    .option("epoch_cap", {
+        type: "number",
+        description: "Max vault tokens paid out by instant unbond per epoch, 0 disables instant unbond",
+        required: true,
+    })
+    .option("treasury_token_account", {
+        type: "string",
+        description: "Vault token account (e.g. wYLDS) that receives the exit fees",
+        required: true,
+    })
+    .parseSync();
+
+const main = async () => {
+    const signer = provider.wallet.publicKey;
+
+    // bpf_loader_upgradeable program id
+    const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+        "BPFLoaderUpgradeab1e11111111111111111111111"
+    );
+    // derive ProgramData PDA
+    const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_ID
+    );
+
+    const epochCap = new anchor.BN(args.epoch_cap);
+    const treasuryTokenAccount = new PublicKey(args.treasury_token_account);
+
+    console.log("Fee (bps):", args.fee_bps);
+    console.log("Epoch Cap:", epochCap.toString());
+    console.log("Treasury Token Account:", treasuryTokenAccount.toBase58());
+    console.log("ProgramData PDA:", programData.toBase58());
+    console.log("Signer:", signer.toBase58());
+
+    const tx = await program.methods
+        .updateInstantUnbondConfig(args.fee_bps, epochCap)
+        .accounts({
+            treasuryTokenAccount: treasuryTokenAccount,
+            programData: programData,
+            signer: signer,
+        })
+        .rpc();
+    console.log("Transaction:", tx);
+};
+
+main().catch(console.error);
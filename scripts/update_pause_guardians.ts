@@ -0,0 +1,56 @@
+import * as anchor from "@coral-xyz/anchor";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+import {PublicKey} from "@solana/web3.js";
+import yargs from "yargs";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+const args = yargs(process.argv.slice(2))
+    .option("pause_guardians", {
+        type: "string",
+        description: "Comma separated list of guardian public keys that can pause but not unpause the protocol",
+        required: true,
+    })
+    .parseSync();
+
+const main = async () => {
+    const pauseGuardians: PublicKey[] = (args.pause_guardians.split(",")).map((s: string) => new anchor.web3.PublicKey(s));
+    if(pauseGuardians.length > 5) {
+        throw new Error(`Number of pause guardians (${pauseGuardians.length}) exceeds maximum 5`);
+    }
+
+    const [configPda, bump] = PublicKey.findProgramAddressSync([
+        Buffer.from("config")
+    ], program.programId);
+
+    // bpf_loader_upgradeable program id
+    const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
+        "BPFLoaderUpgradeab1e11111111111111111111111"
+    );
+    // derive ProgramData PDA
+    const [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        BPF_LOADER_UPGRADEABLE_ID
+    );
+
+    console.log("Config PDA:", configPda.toBase58());
+    console.log("ProgramData PDA:", programData.toBase58());
+    console.log("Pause Guardians:", pauseGuardians.map((a) => a.toBase58()));
+
+    const tx = await program.methods
+        .updatePauseGuardians(pauseGuardians)
+        .accountsStrict({
+            config: configPda,
+            signer: provider.wallet.publicKey,
+            programData: programData,
+        })
+        .rpc();
+
+    console.log("Transaction:", tx);
+};
+
+main().catch(console.error); 
@@ -0,0 +1,56 @@
+import * as anchor from "@coral-xyz/anchor";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+import {PublicKey} from "@solana/web3.js";
+import yargs from "yargs";
+import {
+    allocationsToMerkleTree,
+} from "./cryptolib";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+const args = yargs(process.argv.slice(2))
+    .option("epoch", {
+        type: "number",
+        description: "Index of the draft epoch to correct",
+        required: true,
+    })
+    .option("reward_allocations", {
+        type: "json",
+        description: "Corrected allocations object: {allocations: [{\"account\": \"3m7...sKf\", \"amount\": 1000}, ...]}",
+        required: true,
+    })
+    .parseSync();
+
+const main = async () => {
+    const epochIndex = args.epoch;
+    const [epochPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("epoch"), new anchor.BN(epochIndex).toArrayLike(Buffer, "le", 8)],
+        program.programId
+    );
+
+    // rebuild the tree the same way the epoch was created
+    const epoch = await program.account.rewardsEpoch.fetch(epochPda);
+    const indexed = epoch.leafCount.gtn(0);
+    const { tree, allocations } = allocationsToMerkleTree(args.reward_allocations, epochIndex, indexed, epoch.hashVersion);
+    if (indexed && !epoch.leafCount.eqn(allocations.length)) {
+        throw new Error(`Indexed epoch has ${epoch.leafCount.toString()} leaves, got ${allocations.length} allocations`);
+    }
+    const root = tree.getRoot();
+    const total = allocations.reduce((acc, a) => acc.add(a.amount), new anchor.BN(0));
+    console.log("Total:", total.toString());
+
+    const tx = await program.methods
+        .updateRewardsEpoch(Array.from(root), total)
+        .accounts({
+            admin: provider.wallet.publicKey,
+            epoch: epochPda,
+        })
+        .rpc();
+    console.log("Transaction:", tx);
+};
+
+main().catch(console.error);
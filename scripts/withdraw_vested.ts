@@ -0,0 +1,55 @@
+import * as anchor from "@coral-xyz/anchor";
+import {Program} from "@coral-xyz/anchor";
+import {HastraSolVaultStake} from "../target/types/hastra_sol_vault_stake";
+import {PublicKey} from "@solana/web3.js";
+import {getAssociatedTokenAddressSync} from "@solana/spl-token";
+import yargs from "yargs";
+
+const provider = anchor.AnchorProvider.env();
+anchor.setProvider(provider);
+
+const program = anchor.workspace.HastraSolVaultStake as Program<HastraSolVaultStake>;
+
+const args = yargs(process.argv.slice(2))
+    .option("epoch", {
+        type: "number",
+        description: "Index of the vesting epoch the rewards were claimed from",
+        required: true,
+    })
+    .option("mint", {
+        type: "string",
+        description: "Staking Token Mint (e.g. PRIME)",
+        required: true,
+    })
+    .parseSync();
+
+const main = async () => {
+    const user = provider.wallet.publicKey;
+    const [epochPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("epoch"), new anchor.BN(args.epoch).toArrayLike(Buffer, "le", 8)],
+        program.programId
+    );
+    const [vestingPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("vesting"), epochPda.toBuffer(), user.toBuffer()],
+        program.programId
+    );
+
+    const vesting = await program.account.vestingAccount.fetch(vestingPda);
+    console.log("Vesting PDA:", vestingPda.toBase58());
+    console.log("Total:", vesting.total.toString(), "Released:", vesting.released.toString());
+    console.log("Vesting ends:", vesting.startTs.add(vesting.duration).toString());
+
+    const mint = new PublicKey(args.mint);
+    const tx = await program.methods
+        .withdrawVested()
+        .accounts({
+            user: user,
+            vesting: vestingPda,
+            mint: mint,
+            userStakeTokenAccount: getAssociatedTokenAddressSync(mint, user),
+        })
+        .rpc();
+    console.log("Transaction:", tx);
+};
+
+main().catch(console.error);
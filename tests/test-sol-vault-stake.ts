@@ -6,10 +6,13 @@ import {
   createAccount,
   mintTo,
   getAccount,
+  getMint,
+  transfer,
   TOKEN_PROGRAM_ID,
 } from "@solana/spl-token";
 import { assert } from "chai";
 import { Keypair, PublicKey, SystemProgram } from "@solana/web3.js";
+import { createHash } from "crypto";
 
 describe("hastra-sol-vault-stake", () => {
   const provider = anchor.AnchorProvider.env();
@@ -27,7 +30,13 @@ describe("hastra-sol-vault-stake", () => {
   let vaultAuthorityPda: PublicKey;
   let mintAuthorityPda: PublicKey;
   let freezeAuthorityPda: PublicKey;
+  let userStatePda: PublicKey;
   let ticketPda: PublicKey;
+  let stakeEscrowPda: PublicKey;
+  let withdrawalQueuePda: PublicKey;
+  let treasuryTokenAccount: PublicKey;
+  let rewardsAdminVaultTokenAccount: PublicKey;
+  let programData: PublicKey;
 
   const user = provider.wallet;
   const freezeAdmin = Keypair.generate();
@@ -48,12 +57,16 @@ describe("hastra-sol-vault-stake", () => {
         6
     );
 
-    // Create stake mint (PRIME)
+    // Create stake mint (PRIME), freezable by the program's freeze authority PDA
+    [freezeAuthorityPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("freeze_authority")],
+        program.programId
+    );
     stakeMint = await createMint(
         provider.connection,
         user.payer,
         user.publicKey,
-        null,
+        freezeAuthorityPda,
         6
     );
 
@@ -79,6 +92,31 @@ describe("hastra-sol-vault-stake", () => {
         user.publicKey
     );
 
+    // Receives instant unbond fees
+    treasuryTokenAccount = await createAccount(
+        provider.connection,
+        user.payer,
+        vaultMint,
+        rewardsAdmin.publicKey
+    );
+
+    // Funds rewards epochs
+    rewardsAdminVaultTokenAccount = await createAccount(
+        provider.connection,
+        user.payer,
+        vaultMint,
+        rewardsAdmin.publicKey,
+        Keypair.generate()
+    );
+    await mintTo(
+        provider.connection,
+        user.payer,
+        vaultMint,
+        rewardsAdminVaultTokenAccount,
+        user.publicKey,
+        100000
+    );
+
     // Mint some vault tokens to user
     await mintTo(
         provider.connection,
@@ -105,17 +143,137 @@ describe("hastra-sol-vault-stake", () => {
         program.programId
     );
 
-    [freezeAuthorityPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("freeze_authority")],
+    [userStatePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("user_state"), user.publicKey.toBuffer()],
         program.programId
     );
 
     [ticketPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("ticket"), user.publicKey.toBuffer()],
+        [Buffer.from("ticket"), user.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+        program.programId
+    );
+
+    [stakeEscrowPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("stake_escrow")],
+        program.programId
+    );
+
+    [withdrawalQueuePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("withdrawal_queue")],
         program.programId
     );
+
+    // Program data account holding the upgrade authority, passed to every admin instruction
+    [programData] = PublicKey.findProgramAddressSync(
+        [program.programId.toBuffer()],
+        new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    );
   });
 
+  const epochPdaFor = (epochIndex: anchor.BN) => PublicKey.findProgramAddressSync(
+      [Buffer.from("epoch"), epochIndex.toArrayLike(Buffer, "le", 8)],
+      program.programId
+  )[0];
+  const claimRecordPdaFor = (epochPda: PublicKey) => PublicKey.findProgramAddressSync(
+      [Buffer.from("claim"), epochPda.toBuffer(), user.publicKey.toBuffer()],
+      program.programId
+  )[0];
+  // Epoch indexes are sequential once the "sequential epochs" tests switch them on
+  const nextEpochIndex = async () => (await program.account.config.fetch(configPda)).nextEpochIndex;
+  const chainTime = async () => provider.connection.getBlockTime(await provider.connection.getSlot());
+  const sha256 = (data: Buffer) => createHash("sha256").update(data).digest();
+
+  // Leaf of a claim record epoch. A tree with a single leaf has it as its root and an empty proof.
+  const rewardsLeaf = (epochIndex: anchor.BN, amount: anchor.BN, account: PublicKey = user.publicKey) => sha256(Buffer.concat([
+    Buffer.from([0x00]),
+    account.toBuffer(),
+    amount.toArrayLike(Buffer, "le", 8),
+    epochIndex.toArrayLike(Buffer, "le", 8)
+  ]));
+
+  // Create a rewards epoch signed by the rewards admin and funded from their vault token account.
+  // Without options it is published straight away and claimable for as long as it exists.
+  const createRewardsEpoch = (opts: {
+    index: anchor.BN,
+    total: anchor.BN,
+    merkleRoot?: number[],
+    claimStartTs?: number,
+    claimEndTs?: number,
+    draft?: boolean,
+    funded?: boolean,
+  }) => {
+    const funded = opts.funded ?? true;
+    return program.methods
+        .createRewardsEpoch(
+            opts.index,
+            opts.merkleRoot ?? Array(32).fill(0),
+            opts.total,
+            new anchor.BN(opts.claimStartTs ?? 0),
+            new anchor.BN(opts.claimEndTs ?? 0),
+            opts.draft ?? false
+        )
+        .accounts({
+          admin: rewardsAdmin.publicKey,
+          vaultTokenAccount: funded ? vaultTokenAccount : null,
+          adminVaultTokenAccount: funded ? rewardsAdminVaultTokenAccount : null,
+          tokenProgram: funded ? TOKEN_PROGRAM_ID : null,
+        })
+        .signers([rewardsAdmin])
+        .rpc();
+  };
+
+  // Published epoch with a single leaf paying `amount` to the test user
+  const createSingleClaimEpoch = async (epochIndex: anchor.BN, amount: anchor.BN) => {
+    await createRewardsEpoch({
+      index: epochIndex,
+      total: amount,
+      merkleRoot: Array.from(rewardsLeaf(epochIndex, amount)),
+    });
+    const epochPda = epochPdaFor(epochIndex);
+    return {epochPda, claimRecordPda: claimRecordPdaFor(epochPda)};
+  };
+
+  const ticketPdaFor = (nonce: anchor.BN) => PublicKey.findProgramAddressSync(
+      [Buffer.from("ticket"), user.publicKey.toBuffer(), nonce.toArrayLike(Buffer, "le", 8)],
+      program.programId
+  )[0];
+  const setUnbondingPeriod = (period: number) => program.methods
+      .updateConfig(new anchor.BN(period))
+      .accounts({
+        programData: programData,
+        signer: user.publicKey,
+      })
+      .rpc();
+
+  // Unbond `amount` stake tokens and return the new ticket once its unbonding period has passed
+  const openMaturedTicket = async (amount: number) => {
+    const {nextTicketNonce} = await program.account.userStakeState.fetch(userStatePda);
+    await program.methods
+        .unbond(new anchor.BN(amount))
+        .accounts({
+          signer: user.publicKey,
+          mint: stakeMint,
+          userMintTokenAccount: userStakeTokenAccount,
+        })
+        .rpc();
+    const ticketPda = ticketPdaFor(nextTicketNonce);
+    const {unlockTs} = await program.account.unbondingTicket.fetch(ticketPda);
+    while ((await chainTime()) < unlockTs.toNumber()) {
+      await new Promise((resolve) => setTimeout(resolve, 500));
+    }
+    return ticketPda;
+  };
+
+  // Find an event emitted by a transaction sent with `.rpc({commitment: "confirmed"})`
+  const findEvent = async (tx: string, name: string) => {
+    const txInfo = await provider.connection.getTransaction(tx, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const parser = new anchor.EventParser(program.programId, program.coder);
+    return [...parser.parseLogs(txInfo.meta.logMessages)].find(e => e.name === name);
+  };
+
   it("Initializes the program", async () => {
     const tx = await program.methods
         .initialize(
@@ -140,6 +298,101 @@ describe("hastra-sol-vault-stake", () => {
     assert.equal(config.unbondingPeriod.toNumber(), unbondingPeriod);
     assert.equal(config.freezeAdministrators.length, 1);
     assert.equal(config.rewardsAdministrators.length, 1);
+    assert.isTrue(config.fundedRewards);
+    assert.equal(config.rewardsHashVersion, 1);
+  });
+
+  it("Initializes the stake escrow", async () => {
+    const tx = await program.methods
+        .initializeStakeEscrow()
+        .accounts({
+          mint: stakeMint,
+          programData: programData,
+          signer: user.publicKey,
+        })
+        .rpc({commitment: "confirmed"});
+
+    // Verify the escrow is owned by the vault authority PDA
+    const escrowAccount = await getAccount(provider.connection, stakeEscrowPda);
+    assert.equal(escrowAccount.mint.toBase58(), stakeMint.toBase58());
+    assert.equal(escrowAccount.owner.toBase58(), vaultAuthorityPda.toBase58());
+    assert.equal(escrowAccount.amount.toString(), "0");
+
+    const initialized = await findEvent(tx, "stakeEscrowInitialized");
+    assert.isDefined(initialized);
+    assert.equal(initialized.data.stakeEscrow.toBase58(), stakeEscrowPda.toBase58());
+    assert.equal(initialized.data.admin.toBase58(), user.publicKey.toBase58());
+  });
+
+  it("Initializes the withdrawal queue", async () => {
+    const tx = await program.methods
+        .initializeWithdrawalQueue()
+        .accounts({
+          programData: programData,
+          signer: user.publicKey,
+        })
+        .rpc({commitment: "confirmed"});
+
+    const queue = await program.account.withdrawalQueue.fetch(withdrawalQueuePda);
+    assert.equal(queue.head.toNumber(), 0);
+    assert.equal(queue.tail.toNumber(), 0);
+    assert.equal(queue.queuedAmount.toNumber(), 0);
+
+    const initialized = await findEvent(tx, "withdrawalQueueInitialized");
+    assert.isDefined(initialized);
+    assert.equal(initialized.data.withdrawalQueue.toBase58(), withdrawalQueuePda.toBase58());
+  });
+
+  it("Leaves an up-to-date config unchanged when migrating it", async () => {
+    const before = await provider.connection.getAccountInfo(configPda);
+
+    const tx = await program.methods
+        .migrateConfig()
+        .accounts({
+          programData: programData,
+          signer: user.publicKey,
+        })
+        .rpc({commitment: "confirmed"});
+
+    const after = await provider.connection.getAccountInfo(configPda);
+    assert.isTrue(after.data.equals(before.data));
+
+    const migrated = await findEvent(tx, "configMigrated");
+    assert.isDefined(migrated);
+    assert.equal(migrated.data.oldLen.toNumber(), before.data.length);
+    assert.equal(migrated.data.newLen.toNumber(), before.data.length);
+    assert.equal(migrated.data.vault.toBase58(), vaultMint.toBase58());
+  });
+
+  it("Rejects reporting yield while no stake tokens are outstanding", async () => {
+    const setMode = (enabled: boolean) => program.methods
+        .setExchangeRateMode(enabled)
+        .accounts({
+          mint: stakeMint,
+          programData: programData,
+          signer: user.publicKey,
+        })
+        .rpc();
+    await setMode(true);
+
+    // nobody holds stake tokens yet, so the yield would go to the first depositor
+    try {
+      await program.methods
+          .reportYield(new anchor.BN(1000))
+          .accounts({
+            mint: stakeMint,
+            programData: programData,
+            signer: user.publicKey,
+          })
+          .rpc();
+      assert.fail("Should have failed because the stake token supply is zero");
+    } catch (error) {
+      assert.include(error.toString(), "NoStakeTokenSupply");
+    }
+
+    const config = await program.account.config.fetch(configPda);
+    assert.equal(config.totalAssets.toString(), "0");
+    await setMode(false);
   });
 
   it("Deposits vault tokens and mints stake tokens", async () => {
@@ -180,13 +433,55 @@ describe("hastra-sol-vault-stake", () => {
     const ticket = await program.account.unbondingTicket.fetch(ticketPda);
     assert.equal(ticket.owner.toBase58(), user.publicKey.toBase58());
     assert.equal(ticket.requestedAmount.toString(), unbondAmount.toString());
+    assert.equal(ticket.remainingAmount.toString(), unbondAmount.toString());
+    assert.equal(ticket.nonce.toNumber(), 0);
     assert.isTrue(ticket.startTs.toNumber() > 0);
+    assert.equal(ticket.unlockTs.toNumber(), ticket.startTs.toNumber() + unbondingPeriod);
+
+    const userState = await program.account.userStakeState.fetch(userStatePda);
+    assert.equal(userState.nextTicketNonce.toNumber(), 1);
+
+    // Verify the stake tokens moved from the user into escrow
+    const escrowAccount = await getAccount(provider.connection, stakeEscrowPda);
+    const userStakeAccount = await getAccount(provider.connection, userStakeTokenAccount);
+    assert.equal(escrowAccount.amount.toString(), unbondAmount.toString());
+    assert.equal(userStakeAccount.amount.toString(), "50000");
   });
 
-  it("Fails to redeem before unbonding period", async () => {
+  it("Opens a second concurrent unbonding ticket", async () => {
+    const unbondAmount = new anchor.BN(10000); // 0.01 token
+
+    await program.methods
+        .unbond(unbondAmount)
+        .accounts({
+          signer: user.publicKey,
+          mint: stakeMint,
+          userMintTokenAccount: userStakeTokenAccount,
+        })
+        .rpc();
+
+    const [secondTicketPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("ticket"), user.publicKey.toBuffer(), new anchor.BN(1).toArrayLike(Buffer, "le", 8)],
+        program.programId
+    );
+    const ticket = await program.account.unbondingTicket.fetch(secondTicketPda);
+    assert.equal(ticket.nonce.toNumber(), 1);
+    assert.equal(ticket.remainingAmount.toString(), unbondAmount.toString());
+
+    // Both tickets are backed by the escrow
+    const escrowAccount = await getAccount(provider.connection, stakeEscrowPda);
+    assert.equal(escrowAccount.amount.toString(), "60000");
+  });
+
+  it("Fails to redeem many tickets before unbonding period", async () => {
+    const [secondTicketPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("ticket"), user.publicKey.toBuffer(), new anchor.BN(1).toArrayLike(Buffer, "le", 8)],
+        program.programId
+    );
+
     try {
       await program.methods
-          .redeem()
+          .redeemMany()
           .accounts({
             vaultTokenAccount: vaultTokenAccount,
             signer: user.publicKey,
@@ -194,6 +489,10 @@ describe("hastra-sol-vault-stake", () => {
             userMintTokenAccount: userStakeTokenAccount,
             mint: stakeMint,
           })
+          .remainingAccounts([
+            { pubkey: ticketPda, isWritable: true, isSigner: false },
+            { pubkey: secondTicketPda, isWritable: true, isSigner: false },
+          ])
           .rpc();
 
       assert.fail("Should have failed due to unbonding period not elapsed");
@@ -202,167 +501,344 @@ describe("hastra-sol-vault-stake", () => {
     }
   });
 
-  it("Creates rewards epoch", async () => {
-    const epochIndex = new anchor.BN(1);
-    const merkleRoot = Array(32).fill(0); // Mock merkle root
-    const totalRewards = new anchor.BN(10000);
-
-    const [epochPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("epoch"), epochIndex.toArrayLike(Buffer, "le", 8)],
+  it("Cancels an unbonding ticket and returns the escrowed stake tokens", async () => {
+    const [secondTicketPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("ticket"), user.publicKey.toBuffer(), new anchor.BN(1).toArrayLike(Buffer, "le", 8)],
         program.programId
     );
 
-    const tx = await program.methods
-        .createRewardsEpoch(epochIndex, merkleRoot, totalRewards)
+    await program.methods
+        .cancelUnbond()
         .accounts({
-          admin: rewardsAdmin.publicKey,
+          signer: user.publicKey,
+          ticket: secondTicketPda,
+          userMintTokenAccount: userStakeTokenAccount,
+          mint: stakeMint,
         })
-        .signers([rewardsAdmin])
         .rpc();
 
-    // Verify epoch was created
-    const epoch = await program.account.rewardsEpoch.fetch(epochPda);
-    assert.equal(epoch.index.toString(), epochIndex.toString());
-    assert.equal(epoch.total.toString(), totalRewards.toString());
-  });
+    // Verify the ticket was closed and the stake tokens are back with the user
+    const closedTicket = await program.account.unbondingTicket.fetchNullable(secondTicketPda);
+    assert.isNull(closedTicket);
 
-  it("Fails unauthorized freeze attempt", async () => {
-    const unauthorizedUser = Keypair.generate();
+    const escrowAccount = await getAccount(provider.connection, stakeEscrowPda);
+    const userStakeAccount = await getAccount(provider.connection, userStakeTokenAccount);
+    assert.equal(escrowAccount.amount.toString(), "50000");
+    assert.equal(userStakeAccount.amount.toString(), "50000");
+  });
 
+  it("Fails to redeem before unbonding period", async () => {
     try {
       await program.methods
-          .freezeTokenAccount()
+          .redeem()
           .accounts({
-            tokenAccount: userStakeTokenAccount,
+            ticket: ticketPda,
+            vaultTokenAccount: vaultTokenAccount,
+            signer: user.publicKey,
+            userVaultTokenAccount: userVaultTokenAccount,
+            userMintTokenAccount: userStakeTokenAccount,
             mint: stakeMint,
-            signer: unauthorizedUser.publicKey,
           })
-          .signers([unauthorizedUser])
           .rpc();
 
-      assert.fail("Should have failed due to unauthorized freeze administrator");
+      assert.fail("Should have failed due to unbonding period not elapsed");
     } catch (error) {
-      assert.include(error.toString(), "UnauthorizedFreezeAdministrator");
+      assert.include(error.toString(), "UnbondingPeriodNotElapsed");
     }
   });
 
-  it("Updates configuration", async () => {
-    const newUnbondingPeriod = new anchor.BN(14 * 24 * 60 * 60); // 14 days
-
-    // Get program data PDA for authority validation
-    const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
-        "BPFLoaderUpgradeab1e11111111111111111111111"
-    );
-    const [programData] = PublicKey.findProgramAddressSync(
-        [program.programId.toBuffer()],
-        BPF_LOADER_UPGRADEABLE_ID
-    );
-
-    const tx = await program.methods
-        .updateConfig(newUnbondingPeriod)
-        .accounts({
-          programData: programData,
-          signer: user.publicKey,
-        })
-        .rpc();
+  it("Fails to partially redeem before unbonding period", async () => {
+    try {
+      await program.methods
+          .redeemPartial()
+          .accounts({
+            ticket: ticketPda,
+            vaultTokenAccount: vaultTokenAccount,
+            signer: user.publicKey,
+            userVaultTokenAccount: userVaultTokenAccount,
+            userMintTokenAccount: userStakeTokenAccount,
+            mint: stakeMint,
+          })
+          .rpc();
 
-    // Verify configuration was updated
-    const config = await program.account.config.fetch(configPda);
-    assert.equal(config.unbondingPeriod.toString(), newUnbondingPeriod.toString());
+      assert.fail("Should have failed due to unbonding period not elapsed");
+    } catch (error) {
+      assert.include(error.toString(), "UnbondingPeriodNotElapsed");
+    }
   });
 
-  it("Updates freeze administrators", async () => {
-    const newFreezeAdmin = Keypair.generate();
-    await provider.connection.requestAirdrop(newFreezeAdmin.publicKey, 1000000000);
-
-    const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
-        "BPFLoaderUpgradeab1e11111111111111111111111"
-    );
-    const [programData] = PublicKey.findProgramAddressSync(
-        [program.programId.toBuffer()],
-        BPF_LOADER_UPGRADEABLE_ID
-    );
+  it("Funds the vault for queued withdrawals", async () => {
+    const fundAmount = new anchor.BN(20000);
+    const vaultBefore = await getAccount(provider.connection, vaultTokenAccount);
 
-    const tx = await program.methods
-        .updateFreezeAdministrators([freezeAdmin.publicKey, newFreezeAdmin.publicKey])
+    await program.methods
+        .fundQueue(fundAmount)
         .accounts({
+          vaultTokenAccount: vaultTokenAccount,
+          funderVaultTokenAccount: userVaultTokenAccount,
           programData: programData,
           signer: user.publicKey,
         })
         .rpc();
 
-    // Verify administrators were updated
-    const config = await program.account.config.fetch(configPda);
-    assert.equal(config.freezeAdministrators.length, 2);
-  });
-
-  it("Prevents too many administrators", async () => {
-    const tooManyAdmins = Array(6).fill(0).map(() => Keypair.generate().publicKey);
-
-    const BPF_LOADER_UPGRADEABLE_ID = new PublicKey(
-        "BPFLoaderUpgradeab1e11111111111111111111111"
-    );
-    const [programData] = PublicKey.findProgramAddressSync(
-        [program.programId.toBuffer()],
-        BPF_LOADER_UPGRADEABLE_ID
+    const vaultAfter = await getAccount(provider.connection, vaultTokenAccount);
+    assert.equal(
+        (vaultAfter.amount - vaultBefore.amount).toString(),
+        fundAmount.toString()
     );
+  });
 
+  it("Fails to instant unbond while it is disabled", async () => {
     try {
       await program.methods
-          .updateFreezeAdministrators(tooManyAdmins)
+          .instantUnbond(new anchor.BN(1000))
           .accounts({
-            programData: programData,
+            vaultTokenAccount: vaultTokenAccount,
+            treasuryTokenAccount: treasuryTokenAccount,
             signer: user.publicKey,
+            userMintTokenAccount: userStakeTokenAccount,
+            userVaultTokenAccount: userVaultTokenAccount,
+            mint: stakeMint,
           })
           .rpc();
-
-      assert.fail("Should have failed due to too many administrators");
+      assert.fail("Should have failed because the epoch cap is zero");
     } catch (error) {
-      assert.include(error.toString(), "TooManyAdministrators");
+      assert.include(error.toString(), "InstantUnbondDisabled");
     }
   });
 
-  it("Claims rewards with valid merkle proof", async () => {
-    const epochIndex = new anchor.BN(2);
-    const claimAmount = new anchor.BN(5000);
+  it("Instantly unbonds with an exit fee", async () => {
+    const feeBps = 50; // 0.5%
+    const epochCap = new anchor.BN(15000);
+    const amount = new anchor.BN(10000);
 
-    // Create epoch first
-    const [epochPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("epoch"), epochIndex.toArrayLike(Buffer, "le", 8)],
-        program.programId
-    );
+    try {
+      await program.methods
+          .updateInstantUnbondConfig(5001, epochCap)
+          .accounts({
+            treasuryTokenAccount: treasuryTokenAccount,
+            programData: programData,
+            signer: user.publicKey,
+          })
+          .rpc();
+      assert.fail("Should have failed because the fee is above 50%");
+    } catch (error) {
+      assert.include(error.toString(), "InvalidFee");
+    }
 
-    // Mock merkle proof - in real implementation, this would be computed off-chain
-    const mockProof: number[][] = [];
+    await program.methods
+        .updateInstantUnbondConfig(feeBps, epochCap)
+        .accounts({
+          treasuryTokenAccount: treasuryTokenAccount,
+          programData: programData,
+          signer: user.publicKey,
+        })
+        .rpc();
 
-    // For testing, create a simple merkle root that validates our claim
-    const crypto = require('crypto');
-    const userData = Buffer.concat([
-      user.publicKey.toBuffer(),
-      claimAmount.toArrayLike(Buffer, "le", 8),
-      epochIndex.toArrayLike(Buffer, "le", 8)
-    ]);
-    const leafHash = crypto.createHash('sha256').update(userData).digest();
+    const userStakeBefore = await getAccount(provider.connection, userStakeTokenAccount);
+    const userVaultBefore = await getAccount(provider.connection, userVaultTokenAccount);
 
     await program.methods
-        .createRewardsEpoch(epochIndex, Array.from(leafHash), claimAmount)
+        .instantUnbond(amount)
         .accounts({
-          admin: rewardsAdmin.publicKey,
+          vaultTokenAccount: vaultTokenAccount,
+          treasuryTokenAccount: treasuryTokenAccount,
+          signer: user.publicKey,
+          userMintTokenAccount: userStakeTokenAccount,
+          userVaultTokenAccount: userVaultTokenAccount,
+          mint: stakeMint,
         })
-        .signers([rewardsAdmin])
         .rpc();
 
-    // Claim rewards
-    const [claimRecordPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("claim"), epochPda.toBuffer(), user.publicKey.toBuffer()],
-        program.programId
-    );
+    const userStakeAfter = await getAccount(provider.connection, userStakeTokenAccount);
+    const userVaultAfter = await getAccount(provider.connection, userVaultTokenAccount);
+    const treasury = await getAccount(provider.connection, treasuryTokenAccount);
+    assert.equal((userStakeBefore.amount - userStakeAfter.amount).toString(), "10000");
+    assert.equal((userVaultAfter.amount - userVaultBefore.amount).toString(), "9950");
+    assert.equal(treasury.amount.toString(), "50");
 
-    const tx = await program.methods
+    const config = await program.account.config.fetch(configPda);
+    assert.equal(config.instantUnbondEpochUsed.toString(), amount.toString());
+
+    // A second exit of the same size would exceed the epoch cap
+    try {
+      await program.methods
+          .instantUnbond(amount)
+          .accounts({
+            vaultTokenAccount: vaultTokenAccount,
+            treasuryTokenAccount: treasuryTokenAccount,
+            signer: user.publicKey,
+            userMintTokenAccount: userStakeTokenAccount,
+            userVaultTokenAccount: userVaultTokenAccount,
+            mint: stakeMint,
+          })
+          .rpc();
+      assert.fail("Should have failed due to the epoch cap");
+    } catch (error) {
+      assert.include(error.toString(), "InstantUnbondCapExceeded");
+    }
+
+    // the fee rounds up, so a single token would pay out nothing
+    try {
+      await program.methods
+          .instantUnbond(new anchor.BN(1))
+          .accounts({
+            vaultTokenAccount: vaultTokenAccount,
+            treasuryTokenAccount: treasuryTokenAccount,
+            signer: user.publicKey,
+            userMintTokenAccount: userStakeTokenAccount,
+            userVaultTokenAccount: userVaultTokenAccount,
+            mint: stakeMint,
+          })
+          .rpc();
+      assert.fail("Should have failed because the payout is zero");
+    } catch (error) {
+      assert.include(error.toString(), "InvalidAmount");
+    }
+  });
+
+  it("Creates rewards epoch", async () => {
+    const epochIndex = new anchor.BN(1);
+    const merkleRoot = Array(32).fill(0); // Mock merkle root
+    const totalRewards = new anchor.BN(10000);
+
+    const epochPda = epochPdaFor(epochIndex);
+
+    const vaultBefore = await getAccount(provider.connection, vaultTokenAccount);
+
+    await createRewardsEpoch({index: epochIndex, merkleRoot, total: totalRewards});
+
+    // Verify epoch was created
+    const epoch = await program.account.rewardsEpoch.fetch(epochPda);
+    assert.equal(epoch.index.toString(), epochIndex.toString());
+    assert.equal(epoch.total.toString(), totalRewards.toString());
+
+    // Verify the epoch total was transferred into the vault
+    const vaultAfter = await getAccount(provider.connection, vaultTokenAccount);
+    assert.equal(
+        (vaultAfter.amount - vaultBefore.amount).toString(),
+        totalRewards.toString()
+    );
+  });
+
+  it("Fails to create an unfunded rewards epoch", async () => {
+    const epochIndex = new anchor.BN(3);
+    try {
+      await createRewardsEpoch({index: epochIndex, total: new anchor.BN(10000), funded: false});
+      assert.fail("Should have failed because rewards must be funded");
+    } catch (error) {
+      assert.include(error.toString(), "RewardsFundingRequired");
+    }
+  });
+
+  it("Fails unauthorized freeze attempt", async () => {
+    const unauthorizedUser = Keypair.generate();
+
+    try {
+      await program.methods
+          .freezeTokenAccount()
+          .accounts({
+            tokenAccount: userStakeTokenAccount,
+            mint: stakeMint,
+            signer: unauthorizedUser.publicKey,
+          })
+          .signers([unauthorizedUser])
+          .rpc();
+
+      assert.fail("Should have failed due to unauthorized freeze administrator");
+    } catch (error) {
+      assert.include(error.toString(), "UnauthorizedFreezeAdministrator");
+    }
+  });
+
+  it("Updates configuration", async () => {
+    const newUnbondingPeriod = new anchor.BN(14 * 24 * 60 * 60); // 14 days
+
+    const tx = await program.methods
+        .updateConfig(newUnbondingPeriod)
+        .accounts({
+          programData: programData,
+          signer: user.publicKey,
+        })
+        .rpc({commitment: "confirmed"});
+
+    // Verify configuration was updated
+    const config = await program.account.config.fetch(configPda);
+    assert.equal(config.unbondingPeriod.toString(), newUnbondingPeriod.toString());
+
+    // Verify the event carries the period before the update
+    const updated = await findEvent(tx, "unbondingPeriodUpdated");
+    assert.isDefined(updated);
+    assert.equal(updated.data.oldPeriod.toNumber(), unbondingPeriod);
+    assert.equal(updated.data.newPeriod.toString(), newUnbondingPeriod.toString());
+
+    // Verify the open ticket keeps the unlock time snapshotted at unbond
+    const ticket = await program.account.unbondingTicket.fetch(ticketPda);
+    assert.equal(ticket.unlockTs.toNumber(), ticket.startTs.toNumber() + unbondingPeriod);
+  });
+
+  it("Updates freeze administrators", async () => {
+    const newFreezeAdmin = Keypair.generate();
+    await provider.connection.requestAirdrop(newFreezeAdmin.publicKey, 1000000000);
+
+    const tx = await program.methods
+        .updateFreezeAdministrators([freezeAdmin.publicKey, newFreezeAdmin.publicKey])
+        .accounts({
+          programData: programData,
+          signer: user.publicKey,
+        })
+        .rpc({commitment: "confirmed"});
+
+    // Verify administrators were updated
+    const config = await program.account.config.fetch(configPda);
+    assert.equal(config.freezeAdministrators.length, 2);
+
+    // Verify the event carries the lists before and after
+    const updated = await findEvent(tx, "freezeAdministratorsUpdated");
+    assert.isDefined(updated);
+    assert.deepEqual(updated.data.oldAdministrators.map((a: PublicKey) => a.toBase58()), [freezeAdmin.publicKey.toBase58()]);
+    assert.deepEqual(
+        updated.data.newAdministrators.map((a: PublicKey) => a.toBase58()),
+        [freezeAdmin.publicKey.toBase58(), newFreezeAdmin.publicKey.toBase58()]
+    );
+    assert.equal(updated.data.admin.toBase58(), user.publicKey.toBase58());
+  });
+
+  it("Prevents too many administrators", async () => {
+    const tooManyAdmins = Array(6).fill(0).map(() => Keypair.generate().publicKey);
+
+    try {
+      await program.methods
+          .updateFreezeAdministrators(tooManyAdmins)
+          .accounts({
+            programData: programData,
+            signer: user.publicKey,
+          })
+          .rpc();
+
+      assert.fail("Should have failed due to too many administrators");
+    } catch (error) {
+      assert.include(error.toString(), "TooManyAdministrators");
+    }
+  });
+
+  it("Claims rewards with valid merkle proof", async () => {
+    const epochIndex = new anchor.BN(2);
+    const claimAmount = new anchor.BN(5000);
+
+    // Mock merkle proof - in real implementation, this would be computed off-chain
+    const mockProof: number[][] = [];
+
+    // For testing, create a single-leaf merkle root that validates our claim
+    const {epochPda, claimRecordPda} = await createSingleClaimEpoch(epochIndex, claimAmount);
+
+    // Claim rewards
+    const tx = await program.methods
         .claimRewards(claimAmount, mockProof)
         .accounts({
           user: user.publicKey,
           epoch: epochPda,
+          vesting: null,
           mint: stakeMint,
           userStakeTokenAccount: userStakeTokenAccount,
         })
@@ -371,6 +847,10 @@ describe("hastra-sol-vault-stake", () => {
     // Verify claim record was created
     const claimRecord = await program.account.claimRecord.fetch(claimRecordPda);
     assert.isNotNull(claimRecord);
+
+    // Verify the claim counts against the epoch total
+    const epoch = await program.account.rewardsEpoch.fetch(epochPda);
+    assert.equal(epoch.claimed.toString(), claimAmount.toString());
   });
 
   it("Prevents double claiming", async () => {
@@ -378,15 +858,7 @@ describe("hastra-sol-vault-stake", () => {
     const claimAmount = new anchor.BN(5000);
     const mockProof: number[][] = [];
 
-    const [epochPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("epoch"), epochIndex.toArrayLike(Buffer, "le", 8)],
-        program.programId
-    );
-
-    const [claimRecordPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from("claim"), epochPda.toBuffer(), user.publicKey.toBuffer()],
-        program.programId
-    );
+    const epochPda = epochPdaFor(epochIndex);
 
     try {
       await program.methods
@@ -394,6 +866,7 @@ describe("hastra-sol-vault-stake", () => {
           .accounts({
             user: user.publicKey,
             epoch: epochPda,
+            vesting: null,
             mint: stakeMint,
             userStakeTokenAccount: userStakeTokenAccount,
           })
@@ -406,4 +879,1876 @@ describe("hastra-sol-vault-stake", () => {
           error.toString().includes("RewardsAlreadyClaimed"));
     }
   });
+
+  it("Claims several rewards epochs in one transaction", async () => {
+    const first = await createSingleClaimEpoch(new anchor.BN(5), new anchor.BN(2000));
+    const second = await createSingleClaimEpoch(new anchor.BN(6), new anchor.BN(3000));
+
+    const userStakeBefore = await getAccount(provider.connection, userStakeTokenAccount);
+
+    await program.methods
+        .claimRewardsBatch([
+          {amount: new anchor.BN(2000), proof: []},
+          {amount: new anchor.BN(3000), proof: []},
+        ])
+        .accounts({
+          user: user.publicKey,
+          mint: stakeMint,
+          userStakeTokenAccount: userStakeTokenAccount,
+        })
+        .remainingAccounts([
+          {pubkey: first.epochPda, isWritable: true, isSigner: false},
+          {pubkey: first.claimRecordPda, isWritable: true, isSigner: false},
+          {pubkey: second.epochPda, isWritable: true, isSigner: false},
+          {pubkey: second.claimRecordPda, isWritable: true, isSigner: false},
+        ])
+        .rpc();
+
+    const userStakeAfter = await getAccount(provider.connection, userStakeTokenAccount);
+    assert.equal((userStakeAfter.amount - userStakeBefore.amount).toString(), "5000");
+
+    assert.isNotNull(await program.account.claimRecord.fetch(first.claimRecordPda));
+    assert.isNotNull(await program.account.claimRecord.fetch(second.claimRecordPda));
+    const firstEpoch = await program.account.rewardsEpoch.fetch(first.epochPda);
+    const secondEpoch = await program.account.rewardsEpoch.fetch(second.epochPda);
+    assert.equal(firstEpoch.claimed.toString(), "2000");
+    assert.equal(secondEpoch.claimed.toString(), "3000");
+  });
+
+  it("Fails the whole batch if any proof is invalid", async () => {
+    const valid = await createSingleClaimEpoch(new anchor.BN(7), new anchor.BN(1000));
+    const invalid = await createSingleClaimEpoch(new anchor.BN(8), new anchor.BN(1000));
+
+    try {
+      await program.methods
+          .claimRewardsBatch([
+            {amount: new anchor.BN(1000), proof: []},
+            // not the amount committed to in the epoch root
+            {amount: new anchor.BN(999), proof: []},
+          ])
+          .accounts({
+            user: user.publicKey,
+            mint: stakeMint,
+            userStakeTokenAccount: userStakeTokenAccount,
+          })
+          .remainingAccounts([
+            {pubkey: valid.epochPda, isWritable: true, isSigner: false},
+            {pubkey: valid.claimRecordPda, isWritable: true, isSigner: false},
+            {pubkey: invalid.epochPda, isWritable: true, isSigner: false},
+            {pubkey: invalid.claimRecordPda, isWritable: true, isSigner: false},
+          ])
+          .rpc();
+      assert.fail("Should have failed due to the invalid proof");
+    } catch (error) {
+      assert.include(error.toString(), "InvalidMerkleProof");
+    }
+
+    // Nothing from the valid claim was kept
+    assert.isNull(await provider.connection.getAccountInfo(valid.claimRecordPda));
+    const epoch = await program.account.rewardsEpoch.fetch(valid.epochPda);
+    assert.equal(epoch.claimed.toString(), "0");
+  });
+
+  it("Pays only the increase in cumulative rewards", async () => {
+    const [claimStatePda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("claim_state"), user.publicKey.toBuffer()],
+        program.programId
+    );
+
+    const claimCumulative = async (epochIndex: anchor.BN, cumulative: anchor.BN) => {
+      const leafHash = rewardsLeaf(epochIndex, cumulative);
+      const epochPda = epochPdaFor(epochIndex);
+
+      // single user, so the lifetime total equals the user's cumulative amount
+      await program.methods
+          .createCumulativeRewardsEpoch(epochIndex, Array.from(leafHash), cumulative, new anchor.BN(0), new anchor.BN(0), false)
+          .accounts({
+            admin: rewardsAdmin.publicKey,
+            vaultTokenAccount: vaultTokenAccount,
+            adminVaultTokenAccount: rewardsAdminVaultTokenAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([rewardsAdmin])
+          .rpc();
+
+      const userStakeBefore = await getAccount(provider.connection, userStakeTokenAccount);
+      await program.methods
+          .claimCumulativeRewards(cumulative, [])
+          .accounts({
+            user: user.publicKey,
+            epoch: epochPda,
+            mint: stakeMint,
+            userStakeTokenAccount: userStakeTokenAccount,
+          })
+          .rpc();
+      const userStakeAfter = await getAccount(provider.connection, userStakeTokenAccount);
+      return {epochPda, minted: userStakeAfter.amount - userStakeBefore.amount};
+    };
+
+    const vaultBefore = await getAccount(provider.connection, vaultTokenAccount);
+
+    const first = await claimCumulative(new anchor.BN(9), new anchor.BN(1000));
+    assert.equal(first.minted.toString(), "1000");
+
+    const second = await claimCumulative(new anchor.BN(10), new anchor.BN(2500));
+    assert.equal(second.minted.toString(), "1500");
+
+    const claimState = await program.account.rewardsClaimState.fetch(claimStatePda);
+    assert.equal(claimState.claimed.toString(), "2500");
+
+    // only the increase in the lifetime total was funded
+    const vaultAfter = await getAccount(provider.connection, vaultTokenAccount);
+    assert.equal((vaultAfter.amount - vaultBefore.amount).toString(), "2500");
+
+    try {
+      await program.methods
+          .claimCumulativeRewards(new anchor.BN(2500), [])
+          .accounts({
+            user: user.publicKey,
+            epoch: second.epochPda,
+            mint: stakeMint,
+            userStakeTokenAccount: userStakeTokenAccount,
+          })
+          .rpc();
+      assert.fail("Should have failed because everything was already claimed");
+    } catch (error) {
+      assert.include(error.toString(), "NothingToClaim");
+    }
+  });
+
+  it("Never pays cumulative rewards beyond the cumulative total", async () => {
+    const otherClaimer = Keypair.generate();
+    const sig = await provider.connection.requestAirdrop(otherClaimer.publicKey, 1000000000);
+    await provider.connection.confirmTransaction(sig);
+    const otherStakeTokenAccount = await createAccount(provider.connection, user.payer, stakeMint, otherClaimer.publicKey);
+
+    // a wrong epoch whose single leaf alone uses up its whole total, although 2500 was already paid
+    const epochIndex = new anchor.BN(18);
+    const total = new anchor.BN(3000);
+    await program.methods
+        .createCumulativeRewardsEpoch(epochIndex, Array.from(rewardsLeaf(epochIndex, total, otherClaimer.publicKey)), total, new anchor.BN(0), new anchor.BN(0), false)
+        .accounts({
+          admin: rewardsAdmin.publicKey,
+          vaultTokenAccount: vaultTokenAccount,
+          adminVaultTokenAccount: rewardsAdminVaultTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([rewardsAdmin])
+        .rpc();
+
+    try {
+      await program.methods
+          .claimCumulativeRewards(total, [])
+          .accounts({
+            user: otherClaimer.publicKey,
+            epoch: epochPdaFor(epochIndex),
+            mint: stakeMint,
+            userStakeTokenAccount: otherStakeTokenAccount,
+          })
+          .signers([otherClaimer])
+          .rpc();
+      assert.fail("Should have failed because only 500 of the cumulative total is unclaimed");
+    } catch (error) {
+      assert.include(error.toString(), "CumulativeRewardsExceeded");
+    }
+
+    const config = await program.account.config.fetch(configPda);
+    assert.equal(config.cumulativeRewardsTotal.toString(), "3000");
+    assert.equal(config.cumulativeRewardsClaimed.toString(), "2500");
+  });
+
+  it("Claims an indexed epoch by flipping its bitmap bit", async () => {
+    const epochIndex = new anchor.BN(11);
+    const otherUser = Keypair.generate().publicKey;
+    const indexedLeaf = (account: PublicKey, amount: anchor.BN, leafIndex: number) => sha256(Buffer.concat([
+      Buffer.from([0x00]),
+      account.toBuffer(),
+      amount.toArrayLike(Buffer, "le", 8),
+      epochIndex.toArrayLike(Buffer, "le", 8),
+      new anchor.BN(leafIndex).toArrayLike(Buffer, "le", 8),
+    ]));
+
+    const otherAmount = new anchor.BN(500);
+    const claimAmount = new anchor.BN(1500);
+    const leaf0 = indexedLeaf(otherUser, otherAmount, 0);
+    const leaf1 = indexedLeaf(user.publicKey, claimAmount, 1);
+    // prefixed node over the sorted pair
+    const [left, right] = Buffer.compare(leaf0, leaf1) <= 0 ? [leaf0, leaf1] : [leaf1, leaf0];
+    const root = sha256(Buffer.concat([Buffer.from([0x01]), left, right]));
+
+    const epochPda = epochPdaFor(epochIndex);
+    const [claimBitmapPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from("claim_bitmap"), epochPda.toBuffer()],
+        program.programId
+    );
+
+    await program.methods
+        .createIndexedRewardsEpoch(epochIndex, Array.from(root), otherAmount.add(claimAmount), new anchor.BN(2), new anchor.BN(0), new anchor.BN(0), false)
+        .accounts({
+          admin: rewardsAdmin.publicKey,
+          vaultTokenAccount: vaultTokenAccount,
+          adminVaultTokenAccount: rewardsAdminVaultTokenAccount,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([rewardsAdmin])
+        .rpc();
+
+    const proof = [{sibling: Array.from(leaf0), isLeft: true}];
+    const userStakeBefore = await getAccount(provider.connection, userStakeTokenAccount);
+    await program.methods
+        .claimIndexedRewards(new anchor.BN(1), claimAmount, proof)
+        .accounts({
+          user: user.publicKey,
+          epoch: epochPda,
+          mint: stakeMint,
+          userStakeTokenAccount: userStakeTokenAccount,
+        })
+        .rpc();
+
+    const userStakeAfter = await getAccount(provider.connection, userStakeTokenAccount);
+    assert.equal((userStakeAfter.amount - userStakeBefore.amount).toString(), claimAmount.toString());
+
+    const bitmap = await program.account.claimBitmap.fetch(claimBitmapPda);
+    assert.equal(Buffer.from(bitmap.bits)[0], 0b10);
+
+    try {
+      await program.methods
+          .claimIndexedRewards(new anchor.BN(1), claimAmount, proof)
+          .accounts({
+            user: user.publicKey,
+            epoch: epochPda,
+            mint: stakeMint,
+            userStakeTokenAccount: userStakeTokenAccount,
+          })
+          .rpc();
+      assert.fail("Should have failed due to double claim attempt");
+    } catch (error) {
+      assert.include(error.toString(), "RewardsAlreadyClaimed");
+    }
+  });
+
+  it("Rejects claims beyond the epoch total", async () => {
+    const epochIndex = new anchor.BN(4);
+    const declaredTotal = new anchor.BN(1000);
+    // the merkle tree allocates more than the epoch declares
+    const claimAmount = new anchor.BN(5000);
+
+    const epochPda = epochPdaFor(epochIndex);
+    await createRewardsEpoch({
+      index: epochIndex,
+      total: declaredTotal,
+      merkleRoot: Array.from(rewardsLeaf(epochIndex, claimAmount)),
+    });
+
+    try {
+      await program.methods
+          .claimRewards(claimAmount, [])
+          .accounts({
+            user: user.publicKey,
+            epoch: epochPda,
+            vesting: null,
+            mint: stakeMint,
+            userStakeTokenAccount: userStakeTokenAccount,
+          })
+          .rpc();
+      assert.fail("Should have failed because the claim exceeds the epoch total");
+    } catch (error) {
+      assert.include(error.toString(), "RewardsEpochTotalExceeded");
+    }
+  });
+
+  it("Stamps new epochs with the configured hash version", async () => {
+    try {
+      await program.methods
+          .setRewardsHashVersion(2)
+          .accounts({
+            programData: programData,
+            signer: user.publicKey,
+          })
+          .rpc();
+      assert.fail("Should have failed with an unknown hash version");
+    } catch (error) {
+      assert.include(error.toString(), "InvalidHashVersion");
+    }
+
+    await program.methods
+        .setRewardsHashVersion(0)
+        .accounts({
+          programData: programData,
+          signer: user.publicKey,
+        })
+        .rpc();
+
+    const epochIndex = new anchor.BN(12);
+    await createRewardsEpoch({index: epochIndex, merkleRoot: Array(32).fill(1), total: new anchor.BN(100)});
+    assert.equal((await program.account.rewardsEpoch.fetch(epochPdaFor(epochIndex))).hashVersion, 0);
+
+    await program.methods
+        .setRewardsHashVersion(1)
+        .accounts({
+          programData: programData,
+          signer: user.publicKey,
+        })
+        .rpc();
+    const config = await program.account.config.fetch(configPda);
+    assert.equal(config.rewardsHashVersion, 1);
+  });
+
+  describe("redeeming matured tickets", () => {
+    // a second vault token account holding less than the ticket stands in for an illiquid vault
+    const illiquidVault = Keypair.generate();
+    const redeemPartial = (ticket: PublicKey, vault: PublicKey) => program.methods
+        .redeemPartial()
+        .accounts({
+          ticket: ticket,
+          vaultTokenAccount: vault,
+          signer: user.publicKey,
+          userVaultTokenAccount: userVaultTokenAccount,
+          userMintTokenAccount: userStakeTokenAccount,
+          mint: stakeMint,
+        })
+        .rpc();
+    let partialTicket: PublicKey;
+
+    before(async () => {
+      await setUnbondingPeriod(1);
+      await createAccount(provider.connection, user.payer, vaultMint, vaultAuthorityPda, illiquidVault);
+      await mintTo(provider.connection, user.payer, vaultMint, illiquidVault.publicKey, user.publicKey, 300);
+    });
+
+    after(async () => {
+      await setUnbondingPeriod(unbondingPeriod);
+    });
+
+    it("Partially redeems a matured ticket and keeps it open for the rest", async () => {
+      partialTicket = await openMaturedTicket(1000);
+      const userVaultBefore = await getAccount(provider.connection, userVaultTokenAccount);
+      const escrowBefore = await getAccount(provider.connection, stakeEscrowPda);
+
+      await redeemPartial(partialTicket, illiquidVault.publicKey);
+
+      const ticket = await program.account.unbondingTicket.fetch(partialTicket);
+      assert.equal(ticket.remainingAmount.toNumber(), 700);
+      assert.equal(ticket.requestedAmount.toNumber(), 1000);
+      assert.isFalse(ticket.queued);
+
+      const userVaultAfter = await getAccount(provider.connection, userVaultTokenAccount);
+      const escrowAfter = await getAccount(provider.connection, stakeEscrowPda);
+      assert.equal((userVaultAfter.amount - userVaultBefore.amount).toString(), "300");
+      assert.equal((escrowBefore.amount - escrowAfter.amount).toString(), "300");
+      assert.equal((await getAccount(provider.connection, illiquidVault.publicKey)).amount.toString(), "0");
+    });
+
+    it("Closes the ticket once the rest is redeemed", async () => {
+      const userVaultBefore = await getAccount(provider.connection, userVaultTokenAccount);
+
+      await program.methods
+          .redeem()
+          .accounts({
+            ticket: partialTicket,
+            vaultTokenAccount: vaultTokenAccount,
+            signer: user.publicKey,
+            userVaultTokenAccount: userVaultTokenAccount,
+            userMintTokenAccount: userStakeTokenAccount,
+            mint: stakeMint,
+          })
+          .rpc();
+
+      assert.isNull(await program.account.unbondingTicket.fetchNullable(partialTicket));
+      const userVaultAfter = await getAccount(provider.connection, userVaultTokenAccount);
+      assert.equal((userVaultAfter.amount - userVaultBefore.amount).toString(), "700");
+    });
+
+    it("Redeems several matured tickets in one transaction", async () => {
+      const first = await openMaturedTicket(200);
+      const second = await openMaturedTicket(300);
+      const userVaultBefore = await getAccount(provider.connection, userVaultTokenAccount);
+      const supplyBefore = (await getMint(provider.connection, stakeMint)).supply;
+
+      await program.methods
+          .redeemMany()
+          .accounts({
+            vaultTokenAccount: vaultTokenAccount,
+            signer: user.publicKey,
+            userVaultTokenAccount: userVaultTokenAccount,
+            userMintTokenAccount: userStakeTokenAccount,
+            mint: stakeMint,
+          })
+          .remainingAccounts([
+            { pubkey: first, isWritable: true, isSigner: false },
+            { pubkey: second, isWritable: true, isSigner: false },
+          ])
+          .rpc();
+
+      assert.isNull(await program.account.unbondingTicket.fetchNullable(first));
+      assert.isNull(await program.account.unbondingTicket.fetchNullable(second));
+      const userVaultAfter = await getAccount(provider.connection, userVaultTokenAccount);
+      assert.equal((userVaultAfter.amount - userVaultBefore.amount).toString(), "500");
+      // the escrowed stake tokens are burned with a single burn
+      const supplyAfter = (await getMint(provider.connection, stakeMint)).supply;
+      assert.equal((supplyBefore - supplyAfter).toString(), "500");
+    });
+
+    it("Rejects redeeming while the owner's stake token account is frozen", async () => {
+      const ticket = await openMaturedTicket(100);
+      const setFrozen = (frozen: boolean) =>
+          (frozen ? program.methods.freezeTokenAccount() : program.methods.thawTokenAccount())
+              .accounts({
+                tokenAccount: userStakeTokenAccount,
+                mint: stakeMint,
+                signer: freezeAdmin.publicKey,
+              })
+              .signers([freezeAdmin])
+              .rpc();
+
+      await setFrozen(true);
+      try {
+        await redeemPartial(ticket, vaultTokenAccount);
+        assert.fail("Should have failed because the stake token account is frozen");
+      } catch (error) {
+        assert.include(error.toString(), "TokenAccountFrozen");
+      }
+      try {
+        await program.methods
+            .redeemMany()
+            .accounts({
+              vaultTokenAccount: vaultTokenAccount,
+              signer: user.publicKey,
+              userVaultTokenAccount: userVaultTokenAccount,
+              userMintTokenAccount: userStakeTokenAccount,
+              mint: stakeMint,
+            })
+            .remainingAccounts([{ pubkey: ticket, isWritable: true, isSigner: false }])
+            .rpc();
+        assert.fail("Should have failed because the stake token account is frozen");
+      } catch (error) {
+        assert.include(error.toString(), "TokenAccountFrozen");
+      }
+
+      // the escrowed tokens are released again once the account is thawed
+      await setFrozen(false);
+      await redeemPartial(ticket, vaultTokenAccount);
+      assert.isNull(await program.account.unbondingTicket.fetchNullable(ticket));
+    });
+  });
+
+  describe("withdrawal queue", () => {
+    // an empty vault token account stands in for a vault whose tokens are deployed elsewhere
+    const emptyVault = Keypair.generate();
+    const fundQueue = (amount: number) => program.methods
+        .fundQueue(new anchor.BN(amount))
+        .accounts({
+          vaultTokenAccount: emptyVault.publicKey,
+          funderVaultTokenAccount: userVaultTokenAccount,
+          programData: programData,
+          signer: user.publicKey,
+        })
+        .rpc();
+    const settle = (ticket: PublicKey) => program.methods
+        .settleQueue()
+        .accounts({
+          ticket: ticket,
+          owner: user.publicKey,
+          vaultTokenAccount: emptyVault.publicKey,
+          ownerVaultTokenAccount: userVaultTokenAccount,
+          ownerMintTokenAccount: userStakeTokenAccount,
+          mint: stakeMint,
+          signer: user.publicKey,
+        })
+        .rpc({commitment: "confirmed"});
+    let firstTicket: PublicKey;
+    let secondTicket: PublicKey;
+    let firstPosition: number;
+
+    before(async () => {
+      await setUnbondingPeriod(1);
+      await createAccount(provider.connection, user.payer, vaultMint, vaultAuthorityPda, emptyVault);
+    });
+
+    after(async () => {
+      await setUnbondingPeriod(unbondingPeriod);
+    });
+
+    it("Queues matured tickets the vault cannot pay, in order", async () => {
+      firstTicket = await openMaturedTicket(400);
+      secondTicket = await openMaturedTicket(600);
+      const queueBefore = await program.account.withdrawalQueue.fetch(withdrawalQueuePda);
+      firstPosition = queueBefore.tail.toNumber();
+
+      const tx = await program.methods
+          .redeem()
+          .accounts({
+            ticket: firstTicket,
+            vaultTokenAccount: emptyVault.publicKey,
+            signer: user.publicKey,
+            userVaultTokenAccount: userVaultTokenAccount,
+            userMintTokenAccount: userStakeTokenAccount,
+            mint: stakeMint,
+          })
+          .rpc({commitment: "confirmed"});
+
+      const enqueued = await findEvent(tx, "withdrawalEnqueued");
+      assert.isDefined(enqueued);
+      assert.equal(enqueued.data.position.toNumber(), firstPosition);
+      assert.equal(enqueued.data.amount.toNumber(), 400);
+
+      // the vault could pay this one, but it has to wait behind the ticket already queued
+      await program.methods
+          .redeemPartial()
+          .accounts({
+            ticket: secondTicket,
+            vaultTokenAccount: vaultTokenAccount,
+            signer: user.publicKey,
+            userVaultTokenAccount: userVaultTokenAccount,
+            userMintTokenAccount: userStakeTokenAccount,
+            mint: stakeMint,
+          })
+          .rpc();
+
+      const first = await program.account.unbondingTicket.fetch(firstTicket);
+      const second = await program.account.unbondingTicket.fetch(secondTicket);
+      assert.isTrue(first.queued);
+      assert.isTrue(second.queued);
+      assert.equal(first.queuePosition.toNumber(), firstPosition);
+      assert.equal(second.queuePosition.toNumber(), firstPosition + 1);
+
+      const queue = await program.account.withdrawalQueue.fetch(withdrawalQueuePda);
+      assert.equal(queue.head.toNumber(), firstPosition);
+      assert.equal(queue.tail.toNumber(), firstPosition + 2);
+      assert.equal(queue.queuedAmount.sub(queueBefore.queuedAmount).toNumber(), 1000);
+    });
+
+    it("Settles the queue first in, first out", async () => {
+      await fundQueue(500);
+
+      try {
+        await settle(secondTicket);
+        assert.fail("Should have failed because the ticket is not at the head of the queue");
+      } catch (error) {
+        assert.include(error.toString(), "NotQueueHead");
+      }
+
+      const escrowBefore = await getAccount(provider.connection, stakeEscrowPda);
+      let tx = await settle(firstTicket);
+      let settled = await findEvent(tx, "withdrawalSettled");
+      assert.isDefined(settled);
+      assert.equal(settled.data.position.toNumber(), firstPosition);
+      assert.equal(settled.data.amount.toNumber(), 400);
+      assert.equal(settled.data.remaining.toNumber(), 0);
+      assert.isNull(await program.account.unbondingTicket.fetchNullable(firstTicket));
+
+      // the head moves on only once its ticket is fully paid
+      let queue = await program.account.withdrawalQueue.fetch(withdrawalQueuePda);
+      assert.equal(queue.head.toNumber(), firstPosition + 1);
+
+      tx = await settle(secondTicket);
+      settled = await findEvent(tx, "withdrawalSettled");
+      assert.equal(settled.data.amount.toNumber(), 100);
+      assert.equal(settled.data.remaining.toNumber(), 500);
+      queue = await program.account.withdrawalQueue.fetch(withdrawalQueuePda);
+      assert.equal(queue.head.toNumber(), firstPosition + 1);
+
+      try {
+        await settle(secondTicket);
+        assert.fail("Should have failed because the vault is empty again");
+      } catch (error) {
+        assert.include(error.toString(), "InsufficientVaultBalance");
+      }
+
+      await fundQueue(500);
+      await settle(secondTicket);
+      assert.isNull(await program.account.unbondingTicket.fetchNullable(secondTicket));
+
+      queue = await program.account.withdrawalQueue.fetch(withdrawalQueuePda);
+      assert.equal(queue.head.toNumber(), firstPosition + 2);
+      assert.equal(queue.head.toNumber(), queue.tail.toNumber());
+
+      // both tickets' escrowed stake tokens were burned as they were paid
+      const escrowAfter = await getAccount(provider.connection, stakeEscrowPda);
+      assert.equal((escrowBefore.amount - escrowAfter.amount).toString(), "1000");
+    });
+  });
+
+  describe("legacy ticket migration", () => {
+    // tests/fixtures/legacy-ticket.json holds a ticket in the oldest layout at [b"ticket", owner],
+    // requesting 1000 stake tokens, with its stake tokens still in the owner's wallet
+    const legacyOwner = Keypair.fromSecretKey(Uint8Array.from(require("./fixtures/legacy-ticket-owner.json")));
+    const legacyStartTs = 1700000000;
+    let legacyTicketPda: PublicKey;
+    let ownerStakeTokenAccount: PublicKey;
+
+    before(async function () {
+      [legacyTicketPda] = PublicKey.findProgramAddressSync(
+          [Buffer.from("ticket"), legacyOwner.publicKey.toBuffer()],
+          program.programId
+      );
+      // fixtures are only loaded by the local test validator
+      if (!(await provider.connection.getAccountInfo(legacyTicketPda))) {
+        this.skip();
+      }
+
+      const sig = await provider.connection.requestAirdrop(legacyOwner.publicKey, 1000000000);
+      await provider.connection.confirmTransaction(sig);
+      ownerStakeTokenAccount = await createAccount(provider.connection, user.payer, stakeMint, legacyOwner.publicKey);
+      // less than the ticket requested, so only the wallet balance can be escrowed
+      await transfer(provider.connection, user.payer, userStakeTokenAccount, ownerStakeTokenAccount, user.payer, 600);
+    });
+
+    it("Moves a legacy ticket onto the owner's next nonce and escrows its stake tokens", async () => {
+      const [ownerStatePda] = PublicKey.findProgramAddressSync(
+          [Buffer.from("user_state"), legacyOwner.publicKey.toBuffer()],
+          program.programId
+      );
+      const [ticketPda] = PublicKey.findProgramAddressSync(
+          [Buffer.from("ticket"), legacyOwner.publicKey.toBuffer(), new anchor.BN(0).toArrayLike(Buffer, "le", 8)],
+          program.programId
+      );
+      const escrowBefore = await getAccount(provider.connection, stakeEscrowPda);
+
+      const tx = await program.methods
+          .migrateTicket()
+          .accountsPartial({
+            signer: legacyOwner.publicKey,
+            legacyTicket: legacyTicketPda,
+            userState: ownerStatePda,
+            ticket: ticketPda,
+            userMintTokenAccount: ownerStakeTokenAccount,
+          })
+          .signers([legacyOwner])
+          .rpc({commitment: "confirmed"});
+
+      assert.isNull(await provider.connection.getAccountInfo(legacyTicketPda));
+
+      const config = await program.account.config.fetch(configPda);
+      const ticket = await program.account.unbondingTicket.fetch(ticketPda);
+      assert.equal(ticket.owner.toBase58(), legacyOwner.publicKey.toBase58());
+      assert.equal(ticket.requestedAmount.toNumber(), 1000);
+      assert.equal(ticket.startBalance.toNumber(), 5000);
+      assert.equal(ticket.startTs.toNumber(), legacyStartTs);
+      assert.equal(ticket.remainingAmount.toNumber(), 600);
+      assert.equal(ticket.nonce.toNumber(), 0);
+      assert.equal(ticket.unlockTs.toNumber(), legacyStartTs + config.unbondingPeriod.toNumber());
+      assert.isFalse(ticket.queued);
+
+      const ownerState = await program.account.userStakeState.fetch(ownerStatePda);
+      assert.equal(ownerState.nextTicketNonce.toNumber(), 1);
+
+      const escrowAfter = await getAccount(provider.connection, stakeEscrowPda);
+      const ownerStake = await getAccount(provider.connection, ownerStakeTokenAccount);
+      assert.equal((escrowAfter.amount - escrowBefore.amount).toString(), "600");
+      assert.equal(ownerStake.amount.toString(), "0");
+
+      const migrated = await findEvent(tx, "ticketMigrated");
+      assert.isDefined(migrated);
+      assert.equal(migrated.data.legacyTicket.toBase58(), legacyTicketPda.toBase58());
+      assert.equal(migrated.data.ticket.toBase58(), ticketPda.toBase58());
+      assert.equal(migrated.data.escrowedAmount.toNumber(), 600);
+    });
+  });
+
+  describe("legacy rewards epoch migration", () => {
+    // tests/fixtures/legacy-rewards-epoch.json holds epoch 30 in the layout from before the claimed
+    // counter, with a total of 500
+    const legacyIndex = 30;
+    const legacyCreatedTs = 1700000000;
+    let legacyEpochPda: PublicKey;
+
+    before(async function () {
+      legacyEpochPda = epochPdaFor(new anchor.BN(legacyIndex));
+      // fixtures are only loaded by the local test validator
+      if (!(await provider.connection.getAccountInfo(legacyEpochPda))) {
+        this.skip();
+      }
+    });
+
+    it("Grows a legacy epoch to the current layout and backfills what was claimed", async () => {
+      try {
+        await program.methods
+            .migrateRewardsEpoch(new anchor.BN(501))
+            .accounts({
+              epoch: legacyEpochPda,
+              programData: programData,
+              signer: user.publicKey,
+            })
+            .rpc();
+        assert.fail("Should have thrown error");
+      } catch (err) {
+        assert.include(err.message, "RewardsEpochTotalExceeded");
+      }
+
+      const tx = await program.methods
+          .migrateRewardsEpoch(new anchor.BN(200))
+          .accounts({
+            epoch: legacyEpochPda,
+            programData: programData,
+            signer: user.publicKey,
+          })
+          .rpc({commitment: "confirmed"});
+
+      const epoch = await program.account.rewardsEpoch.fetch(legacyEpochPda);
+      assert.equal(epoch.index.toNumber(), legacyIndex);
+      assert.deepEqual(Array.from(epoch.merkleRoot), new Array(32).fill(5));
+      assert.equal(epoch.total.toNumber(), 500);
+      assert.equal(epoch.createdTs.toNumber(), legacyCreatedTs);
+      assert.equal(epoch.claimed.toNumber(), 200);
+      // fields added since read as zero: legacy hashing, claim records, no window, published
+      assert.isFalse(epoch.cumulative);
+      assert.equal(epoch.leafCount.toNumber(), 0);
+      assert.equal(epoch.hashVersion, 0);
+      assert.equal(epoch.claimStartTs.toNumber(), 0);
+      assert.equal(epoch.claimEndTs.toNumber(), 0);
+      assert.isFalse(epoch.draft);
+      assert.equal(epoch.vestingDuration.toNumber(), 0);
+
+      const migrated = await findEvent(tx, "rewardsEpochMigrated");
+      assert.isDefined(migrated);
+      assert.equal(migrated.data.epoch.toNumber(), legacyIndex);
+      assert.equal(migrated.data.oldLen.toNumber(), 64);
+      assert.equal(migrated.data.claimed.toNumber(), 200);
+      const info = await provider.connection.getAccountInfo(legacyEpochPda);
+      assert.equal(info.data.length, migrated.data.newLen.toNumber());
+
+      try {
+        await program.methods
+            .migrateRewardsEpoch(new anchor.BN(0))
+            .accounts({
+              epoch: legacyEpochPda,
+              programData: programData,
+              signer: user.publicKey,
+            })
+            .rpc();
+        assert.fail("Should have thrown error");
+      } catch (err) {
+        assert.include(err.message, "AccountAlreadyMigrated");
+      }
+    });
+  });
+
+  describe("draft epochs", () => {
+    const amount = new anchor.BN(100);
+    const createDraft = (epochIndex: anchor.BN, root: number[]) =>
+        createRewardsEpoch({index: epochIndex, merkleRoot: root, total: amount, draft: true});
+
+    it("Corrects a draft, rejects claims until it is published, then funds it", async () => {
+      const epochIndex = new anchor.BN(15);
+      const epochPda = epochPdaFor(epochIndex);
+      const vaultBefore = await getAccount(provider.connection, vaultTokenAccount);
+      await createDraft(epochIndex, Array(32).fill(7));
+
+      // drafts are not funded
+      const vaultAfterCreate = await getAccount(provider.connection, vaultTokenAccount);
+      assert.equal(vaultAfterCreate.amount.toString(), vaultBefore.amount.toString());
+
+      const leafHash = rewardsLeaf(epochIndex, amount);
+      const updateTx = await program.methods
+          .updateRewardsEpoch(Array.from(leafHash), amount)
+          .accounts({
+            admin: rewardsAdmin.publicKey,
+            epoch: epochPda,
+          })
+          .signers([rewardsAdmin])
+          .rpc({commitment: "confirmed"});
+
+      let epoch = await program.account.rewardsEpoch.fetch(epochPda);
+      assert.isTrue(epoch.draft);
+      assert.deepEqual(epoch.merkleRoot, Array.from(leafHash));
+      const proposed = await findEvent(updateTx, "rewardsEpochProposed");
+      assert.deepEqual(proposed.data.merkleRoot, Array.from(leafHash));
+      assert.isTrue(proposed.data.mint.equals(stakeMint));
+      assert.isTrue(proposed.data.vault.equals(vaultMint));
+
+      try {
+        await program.methods
+            .claimRewards(amount, [])
+            .accounts({
+              user: user.publicKey,
+              epoch: epochPda,
+              vesting: null,
+              mint: stakeMint,
+              userStakeTokenAccount: userStakeTokenAccount,
+            })
+            .rpc();
+        assert.fail("Should have failed because the epoch is a draft");
+      } catch (error) {
+        assert.include(error.toString(), "RewardsEpochNotPublished");
+      }
+
+      await program.methods
+          .publishRewardsEpoch()
+          .accounts({
+            admin: rewardsAdmin.publicKey,
+            epoch: epochPda,
+            approvals: null,
+            vaultTokenAccount: vaultTokenAccount,
+            adminVaultTokenAccount: rewardsAdminVaultTokenAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([rewardsAdmin])
+          .rpc();
+
+      epoch = await program.account.rewardsEpoch.fetch(epochPda);
+      assert.isFalse(epoch.draft);
+      const vaultAfterPublish = await getAccount(provider.connection, vaultTokenAccount);
+      assert.equal(
+          (vaultAfterPublish.amount - vaultBefore.amount).toString(),
+          amount.toString()
+      );
+
+      try {
+        await program.methods
+            .updateRewardsEpoch(Array(32).fill(0), amount)
+            .accounts({
+              admin: rewardsAdmin.publicKey,
+              epoch: epochPda,
+            })
+            .signers([rewardsAdmin])
+            .rpc();
+        assert.fail("Should have failed because the epoch is published");
+      } catch (error) {
+        assert.include(error.toString(), "RewardsEpochAlreadyPublished");
+      }
+    });
+
+    it("Revokes a draft and frees its index", async () => {
+      const epochIndex = new anchor.BN(16);
+      const epochPda = epochPdaFor(epochIndex);
+      await createDraft(epochIndex, Array(32).fill(7));
+
+      await program.methods
+          .revokeRewardsEpoch()
+          .accounts({
+            admin: rewardsAdmin.publicKey,
+            epoch: epochPda,
+            claimBitmap: null,
+            approvals: null,
+          })
+          .signers([rewardsAdmin])
+          .rpc();
+      assert.isNull(await provider.connection.getAccountInfo(epochPda));
+
+      await createDraft(epochIndex, Array(32).fill(8));
+      const epoch = await program.account.rewardsEpoch.fetch(epochPda);
+      assert.deepEqual(epoch.merkleRoot, Array(32).fill(8));
+    });
+  });
+
+  describe("approval threshold", () => {
+    const secondRewardsAdmin = Keypair.generate();
+
+    before(async () => {
+      const sig = await provider.connection.requestAirdrop(secondRewardsAdmin.publicKey, 1000000000);
+      await provider.connection.confirmTransaction(sig);
+    });
+
+    const setThreshold = (threshold: number) => program.methods
+        .setRewardsApprovalThreshold(threshold)
+        .accounts({
+          programData: programData,
+          signer: user.publicKey,
+        })
+        .rpc();
+    const setRewardsAdmins = (admins: PublicKey[]) => program.methods
+        .updateRewardsAdministrators(admins)
+        .accounts({
+          programData: programData,
+          signer: user.publicKey,
+        })
+        .rpc();
+
+    it("Rejects a threshold above the number of rewards admins", async () => {
+      try {
+        await setThreshold(2);
+        assert.fail("Should have failed with only one rewards admin");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidApprovalThreshold");
+      }
+    });
+
+    it("Publishes a draft only once enough distinct rewards admins approve", async () => {
+      await setRewardsAdmins([rewardsAdmin.publicKey, secondRewardsAdmin.publicKey]);
+      await setThreshold(2);
+
+      const epochIndex = new anchor.BN(17);
+      const epochPda = epochPdaFor(epochIndex);
+      const [approvalsPda] = PublicKey.findProgramAddressSync(
+          [Buffer.from("epoch_approvals"), epochPda.toBuffer()],
+          program.programId
+      );
+      const createEpoch = (draft: boolean) =>
+          createRewardsEpoch({index: epochIndex, merkleRoot: Array(32).fill(9), total: new anchor.BN(100), draft});
+      const approve = (admin: Keypair) => program.methods
+          .approveRewardsEpoch()
+          .accounts({
+            admin: admin.publicKey,
+            epoch: epochPda,
+          })
+          .signers([admin])
+          .rpc({commitment: "confirmed"});
+      const publish = () => program.methods
+          .publishRewardsEpoch()
+          .accounts({
+            admin: rewardsAdmin.publicKey,
+            epoch: epochPda,
+            approvals: approvalsPda,
+            vaultTokenAccount: vaultTokenAccount,
+            adminVaultTokenAccount: rewardsAdminVaultTokenAccount,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([rewardsAdmin])
+          .rpc({commitment: "confirmed"});
+
+      try {
+        await createEpoch(false);
+        assert.fail("Should have failed because epochs need approval");
+      } catch (error) {
+        assert.include(error.toString(), "RewardsApprovalRequired");
+      }
+
+      await createEpoch(true);
+      await approve(rewardsAdmin);
+
+      try {
+        await approve(rewardsAdmin);
+        assert.fail("Should have failed because the admin already approved");
+      } catch (error) {
+        assert.include(error.toString(), "AlreadyApproved");
+      }
+
+      try {
+        await publish();
+        assert.fail("Should have failed with one of two approvals");
+      } catch (error) {
+        assert.include(error.toString(), "InsufficientApprovals");
+      }
+
+      const approveTx = await approve(secondRewardsAdmin);
+      const approvals = await program.account.rewardsEpochApprovals.fetch(approvalsPda);
+      assert.equal(approvals.approvers.length, 2);
+      const approved = await findEvent(approveTx, "rewardsEpochApproved");
+      assert.equal(approved.data.approvals, 2);
+      assert.isTrue(approved.data.mint.equals(stakeMint));
+      assert.isTrue(approved.data.vault.equals(vaultMint));
+
+      const publishTx = await publish();
+      const epoch = await program.account.rewardsEpoch.fetch(epochPda);
+      assert.isFalse(epoch.draft);
+      const published = await findEvent(publishTx, "rewardsEpochPublished");
+      assert.equal(published.data.epoch.toString(), epochIndex.toString());
+      assert.isTrue(published.data.mint.equals(stakeMint));
+      assert.isTrue(published.data.vault.equals(vaultMint));
+
+      // later tests publish epochs directly
+      await setThreshold(0);
+      await setRewardsAdmins([rewardsAdmin.publicKey]);
+    });
+  });
+
+  describe("claim windows", () => {
+    const claimAmount = new anchor.BN(100);
+    const createWindowedEpoch = (epochIndex: anchor.BN, start: number, end: number) => createRewardsEpoch({
+      index: epochIndex,
+      merkleRoot: Array.from(rewardsLeaf(epochIndex, claimAmount)),
+      total: claimAmount,
+      claimStartTs: start,
+      claimEndTs: end,
+    });
+    const claim = (epochIndex: anchor.BN) => program.methods
+        .claimRewards(claimAmount, [])
+        .accounts({
+          user: user.publicKey,
+          epoch: epochPdaFor(epochIndex),
+          vesting: null,
+          mint: stakeMint,
+          userStakeTokenAccount: userStakeTokenAccount,
+        })
+        .rpc();
+
+    it("Rejects a window that ends before it starts", async () => {
+      const now = await chainTime();
+      try {
+        await createWindowedEpoch(new anchor.BN(13), now + 100, now + 50);
+        assert.fail("Should have failed with an invalid claim window");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidClaimWindow");
+      }
+    });
+
+    it("Rejects claims before the window opens", async () => {
+      const epochIndex = new anchor.BN(13);
+      const now = await chainTime();
+      await createWindowedEpoch(epochIndex, now + 3600, 0);
+
+      const epoch = await program.account.rewardsEpoch.fetch(epochPdaFor(epochIndex));
+      assert.equal(epoch.claimStartTs.toNumber(), now + 3600);
+      assert.equal(epoch.claimEndTs.toNumber(), 0);
+
+      try {
+        await claim(epochIndex);
+        assert.fail("Should have failed because the window has not opened");
+      } catch (error) {
+        assert.include(error.toString(), "ClaimWindowNotOpen");
+      }
+    });
+
+    it("Closes an expired epoch and rejects late claims", async () => {
+      const epochIndex = new anchor.BN(14);
+      const epochPda = epochPdaFor(epochIndex);
+      const now = await chainTime();
+      await createWindowedEpoch(epochIndex, 0, now + 2);
+
+      try {
+        await program.methods
+            .closeRewardsEpoch()
+            .accounts({
+              admin: rewardsAdmin.publicKey,
+              epoch: epochPda,
+              claimBitmap: null,
+              approvals: null,
+            })
+            .signers([rewardsAdmin])
+            .rpc();
+        assert.fail("Should have failed because the epoch has not expired");
+      } catch (error) {
+        assert.include(error.toString(), "RewardsEpochNotExpired");
+      }
+
+      while ((await chainTime()) < now + 3) {
+        await new Promise((resolve) => setTimeout(resolve, 500));
+      }
+
+      try {
+        await claim(epochIndex);
+        assert.fail("Should have failed because the window has closed");
+      } catch (error) {
+        assert.include(error.toString(), "ClaimWindowClosed");
+      }
+
+      const tx = await program.methods
+          .closeRewardsEpoch()
+          .accounts({
+            admin: rewardsAdmin.publicKey,
+            epoch: epochPda,
+            claimBitmap: null,
+            approvals: null,
+          })
+          .signers([rewardsAdmin])
+          .rpc({commitment: "confirmed"});
+      assert.isNull(await provider.connection.getAccountInfo(epochPda));
+
+      const closed = await findEvent(tx, "rewardsEpochClosed");
+      assert.isDefined(closed);
+      assert.equal(closed.data.unclaimed.toString(), claimAmount.toString());
+    });
+  });
+
+  describe("sequential epochs", () => {
+    const setNextEpochIndex = (next: number) => program.methods
+        .setNextEpochIndex(new anchor.BN(next))
+        .accounts({
+          programData: programData,
+          signer: user.publicKey,
+        })
+        .rpc();
+    const createEpoch = (index: number, draft: boolean) =>
+        createRewardsEpoch({index: new anchor.BN(index), merkleRoot: Array(32).fill(3), total: new anchor.BN(100), draft});
+
+    it("Requires the next index and advances it", async () => {
+      // existing epochs go up to 18, so sequential indexes start past them
+      await setNextEpochIndex(20);
+
+      try {
+        await createEpoch(21, false);
+        assert.fail("Should have failed because 20 is the next index");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidEpochIndex");
+      }
+
+      await createEpoch(20, false);
+      let config = await program.account.config.fetch(configPda);
+      assert.equal(config.nextEpochIndex.toNumber(), 21);
+
+      // revoking the latest draft hands its index back
+      await createEpoch(21, true);
+      await program.methods
+          .revokeRewardsEpoch()
+          .accounts({
+            admin: rewardsAdmin.publicKey,
+            epoch: epochPdaFor(new anchor.BN(21)),
+            claimBitmap: null,
+            approvals: null,
+          })
+          .signers([rewardsAdmin])
+          .rpc();
+      config = await program.account.config.fetch(configPda);
+      assert.equal(config.nextEpochIndex.toNumber(), 21);
+    });
+
+    it("Never moves the next index back", async () => {
+      for (const next of [20, 0]) {
+        try {
+          await setNextEpochIndex(next);
+          assert.fail("Should have failed because the next index is 21");
+        } catch (error) {
+          assert.include(error.toString(), "InvalidEpochIndex");
+        }
+      }
+
+      // sequential indexes stay on for the remaining tests
+      const config = await program.account.config.fetch(configPda);
+      assert.equal(config.nextEpochIndex.toNumber(), 21);
+    });
+  });
+
+  describe("epoch rewards cap", () => {
+    const setCap = (maxBps: number, maxAmount: number) => program.methods
+        .updateEpochRewardsCap(maxBps, new anchor.BN(maxAmount))
+        .accounts({
+          programData: programData,
+          signer: user.publicKey,
+        })
+        .rpc();
+    const createEpoch = (index: number, total: anchor.BN) =>
+        createRewardsEpoch({index: new anchor.BN(index), merkleRoot: Array(32).fill(4), total});
+
+    it("Rejects epochs above the absolute cap", async () => {
+      await setCap(0, 50);
+      try {
+        await createEpoch(22, new anchor.BN(51));
+        assert.fail("Should have failed above the absolute cap");
+      } catch (error) {
+        assert.include(error.toString(), "EpochRewardsCapExceeded");
+      }
+      await createEpoch(22, new anchor.BN(50));
+    });
+
+    it("Rejects epochs above the bps cap of the vault balance", async () => {
+      await setCap(1, 0);
+      const vault = await getAccount(provider.connection, vaultTokenAccount);
+      const cap = new anchor.BN(vault.amount.toString()).divn(10000);
+      try {
+        await createEpoch(23, cap.addn(1));
+        assert.fail("Should have failed above the bps cap");
+      } catch (error) {
+        assert.include(error.toString(), "EpochRewardsCapExceeded");
+      }
+
+      try {
+        await setCap(10001, 0);
+        assert.fail("Should have failed with a cap above 100%");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidRewardsCap");
+      }
+      await setCap(0, 0);
+    });
+  });
+
+  describe("vesting", () => {
+    it("Vests a claim and releases it through withdraw_vested", async () => {
+      const setVestingDuration = (duration: number) => program.methods
+          .setRewardsVestingDuration(new anchor.BN(duration))
+          .accounts({
+            programData: programData,
+            signer: user.publicKey,
+          })
+          .rpc();
+
+      const epochIndex = await nextEpochIndex();
+      const claimAmount = new anchor.BN(1000);
+      const duration = 3;
+      const epochPda = epochPdaFor(epochIndex);
+      const [vestingPda] = PublicKey.findProgramAddressSync(
+          [Buffer.from("vesting"), epochPda.toBuffer(), user.publicKey.toBuffer()],
+          program.programId
+      );
+
+      await setVestingDuration(duration);
+      await createSingleClaimEpoch(epochIndex, claimAmount);
+      await setVestingDuration(0);
+
+      const userStakeBefore = await getAccount(provider.connection, userStakeTokenAccount);
+      await program.methods
+          .claimRewards(claimAmount, [])
+          .accounts({
+            user: user.publicKey,
+            epoch: epochPda,
+            vesting: vestingPda,
+            mint: stakeMint,
+            userStakeTokenAccount: userStakeTokenAccount,
+          })
+          .rpc();
+
+      // nothing is minted at claim time
+      const userStakeAfterClaim = await getAccount(provider.connection, userStakeTokenAccount);
+      assert.equal(userStakeAfterClaim.amount.toString(), userStakeBefore.amount.toString());
+      const vesting = await program.account.vestingAccount.fetch(vestingPda);
+      assert.equal(vesting.total.toString(), claimAmount.toString());
+      assert.equal(vesting.released.toNumber(), 0);
+      assert.equal(vesting.duration.toNumber(), duration);
+
+      const endTs = vesting.startTs.toNumber() + duration;
+      while ((await chainTime()) <= endTs) {
+        await new Promise((resolve) => setTimeout(resolve, 500));
+      }
+
+      await program.methods
+          .withdrawVested()
+          .accounts({
+            user: user.publicKey,
+            vesting: vestingPda,
+            mint: stakeMint,
+            userStakeTokenAccount: userStakeTokenAccount,
+          })
+          .rpc();
+
+      const userStakeAfter = await getAccount(provider.connection, userStakeTokenAccount);
+      assert.equal(
+          (userStakeAfter.amount - userStakeBefore.amount).toString(),
+          claimAmount.toString()
+      );
+      // fully released, so the vesting account is closed
+      assert.isNull(await provider.connection.getAccountInfo(vestingPda));
+    });
+  });
+
+  describe("admin handover", () => {
+    const newAdmin = Keypair.generate();
+
+    const proposeAdmin = (signer: Keypair | null, pending: PublicKey) => program.methods
+        .proposeAdmin(pending)
+        .accounts({
+          programData: programData,
+          signer: signer ? signer.publicKey : user.publicKey,
+        })
+        .signers(signer ? [signer] : [])
+        .rpc();
+    const acceptAdmin = (signer: Keypair | null) => program.methods
+        .acceptAdmin()
+        .accounts({
+          signer: signer ? signer.publicKey : user.publicKey,
+        })
+        .signers(signer ? [signer] : [])
+        .rpc();
+    // keeps the current index, only the signer matters here
+    const setNextEpochIndex = async (signer: Keypair | null) => program.methods
+        .setNextEpochIndex(await nextEpochIndex())
+        .accounts({
+          programData: programData,
+          signer: signer ? signer.publicKey : user.publicKey,
+        })
+        .signers(signer ? [signer] : [])
+        .rpc();
+
+    it("Initialize makes the upgrade authority the admin", async () => {
+      const config = await program.account.config.fetch(configPda);
+      assert.equal(config.admin.toBase58(), user.publicKey.toBase58());
+      assert.equal(config.pendingAdmin.toBase58(), PublicKey.default.toBase58());
+    });
+
+    it("Hands administration over in two steps", async () => {
+      await proposeAdmin(null, newAdmin.publicKey);
+
+      try {
+        await acceptAdmin(rewardsAdmin);
+        assert.fail("Should have failed because only the pending admin can accept");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidPendingAdmin");
+      }
+
+      // the old admin keeps control until the handover is accepted
+      await setNextEpochIndex(null);
+
+      await acceptAdmin(newAdmin);
+      let config = await program.account.config.fetch(configPda);
+      assert.equal(config.admin.toBase58(), newAdmin.publicKey.toBase58());
+      assert.equal(config.pendingAdmin.toBase58(), PublicKey.default.toBase58());
+
+      try {
+        await setNextEpochIndex(null);
+        assert.fail("Should have failed because the upgrade authority is no longer the admin");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidAdmin");
+      }
+      await setNextEpochIndex(newAdmin);
+
+      // hand administration back for the remaining tests
+      await proposeAdmin(newAdmin, user.publicKey);
+      await acceptAdmin(null);
+      config = await program.account.config.fetch(configPda);
+      assert.equal(config.admin.toBase58(), user.publicKey.toBase58());
+    });
+  });
+
+  describe("config timelock", () => {
+    let pendingPda: PublicKey;
+    const noChange = {
+      unbondingPeriod: null,
+      instantUnbond: null,
+      freezeAdministrators: null,
+      rewardsAdministrators: null,
+      configChangeDelay: null,
+      pauseGuardians: null,
+      pendingAdmin: null,
+      rewardsApprovalThreshold: null,
+      epochRewardsCap: null,
+    };
+
+    before(() => {
+      [pendingPda] = PublicKey.findProgramAddressSync([Buffer.from("pending_config_change")], program.programId);
+    });
+
+    const setDelay = (delay: number) => program.methods
+        .setConfigChangeDelay(new anchor.BN(delay))
+        .accounts({
+          programData: programData,
+          signer: user.publicKey,
+        })
+        .rpc();
+    const propose = (change: any) => program.methods
+        .proposeConfigChange({...noChange, ...change})
+        .accounts({
+          treasuryTokenAccount: null,
+          programData: programData,
+          signer: user.publicKey,
+        })
+        .rpc({commitment: "confirmed"});
+    const execute = () => program.methods
+        .executeConfigChange()
+        .accounts({
+          programData: programData,
+          signer: user.publicKey,
+        })
+        .rpc();
+
+    it("Applies config changes only after the delay", async () => {
+      await setDelay(3);
+
+      try {
+        await program.methods
+            .updateConfig(new anchor.BN(unbondingPeriod))
+            .accounts({
+              programData: programData,
+              signer: user.publicKey,
+            })
+            .rpc();
+        assert.fail("Should have failed because config changes are timelocked");
+      } catch (error) {
+        assert.include(error.toString(), "ConfigChangeTimelocked");
+      }
+
+      try {
+        await setDelay(0);
+        assert.fail("Should have failed because lowering the delay is timelocked");
+      } catch (error) {
+        assert.include(error.toString(), "ConfigChangeTimelocked");
+      }
+
+      const before = await program.account.config.fetch(configPda);
+      const newPeriod = before.unbondingPeriod.addn(60);
+      const tx = await propose({unbondingPeriod: newPeriod});
+      const pending = await program.account.pendingConfigChange.fetch(pendingPda);
+      assert.equal(pending.change.unbondingPeriod.toString(), newPeriod.toString());
+
+      // the event shows what will change, and from what, while the delay runs
+      const proposed = await findEvent(tx, "configChangeProposed");
+      assert.isDefined(proposed);
+      assert.equal(proposed.data.eta.toString(), pending.eta.toString());
+      assert.equal(proposed.data.change.unbondingPeriod.toString(), newPeriod.toString());
+      assert.equal(proposed.data.current.unbondingPeriod.toString(), before.unbondingPeriod.toString());
+      assert.isNull(proposed.data.change.freezeAdministrators);
+      assert.isNull(proposed.data.current.freezeAdministrators);
+
+      try {
+        await execute();
+        assert.fail("Should have failed because the delay has not passed");
+      } catch (error) {
+        assert.include(error.toString(), "ConfigChangeNotReady");
+      }
+
+      while ((await chainTime()) < pending.eta.toNumber()) {
+        await new Promise((resolve) => setTimeout(resolve, 500));
+      }
+      await execute();
+
+      const config = await program.account.config.fetch(configPda);
+      assert.equal(config.unbondingPeriod.toString(), newPeriod.toString());
+      assert.isNull(await provider.connection.getAccountInfo(pendingPda));
+    });
+
+    it("Cancels a pending change", async () => {
+      await propose({freezeAdministrators: []});
+      await program.methods
+          .cancelConfigChange()
+          .accounts({
+            programData: programData,
+            signer: user.publicKey,
+          })
+          .rpc();
+      assert.isNull(await provider.connection.getAccountInfo(pendingPda));
+
+      const config = await program.account.config.fetch(configPda);
+      assert.isAbove(config.freezeAdministrators.length, 0);
+    });
+
+    it("Rejects a proposed instant unbond fee above 50%", async () => {
+      try {
+        await propose({instantUnbond: {feeBps: 5001, epochCap: new anchor.BN(0), treasury: treasuryTokenAccount}});
+        assert.fail("Should have failed because the fee is above 50%");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidFee");
+      }
+      assert.isNull(await provider.connection.getAccountInfo(pendingPda));
+    });
+
+    it("Timelocks pause guardian updates and admin handovers", async () => {
+      const guardian = Keypair.generate().publicKey;
+      const nextAdmin = Keypair.generate().publicKey;
+
+      try {
+        await program.methods
+            .updatePauseGuardians([guardian])
+            .accounts({
+              programData: programData,
+              signer: user.publicKey,
+            })
+            .rpc();
+        assert.fail("Should have failed because pause guardian updates are timelocked");
+      } catch (error) {
+        assert.include(error.toString(), "ConfigChangeTimelocked");
+      }
+
+      try {
+        await program.methods
+            .proposeAdmin(nextAdmin)
+            .accounts({
+              programData: programData,
+              signer: user.publicKey,
+            })
+            .rpc();
+        assert.fail("Should have failed because admin handovers are timelocked");
+      } catch (error) {
+        assert.include(error.toString(), "ConfigChangeTimelocked");
+      }
+
+      await propose({pauseGuardians: [guardian], pendingAdmin: nextAdmin});
+      const pending = await program.account.pendingConfigChange.fetch(pendingPda);
+      while ((await chainTime()) < pending.eta.toNumber()) {
+        await new Promise((resolve) => setTimeout(resolve, 500));
+      }
+      await execute();
+
+      const config = await program.account.config.fetch(configPda);
+      assert.deepEqual(config.pauseGuardians.map((k) => k.toBase58()), [guardian.toBase58()]);
+      assert.equal(config.pendingAdmin.toBase58(), nextAdmin.toBase58());
+      // the handover still has to be accepted
+      assert.equal(config.admin.toBase58(), user.publicKey.toBase58());
+    });
+
+    it("Timelocks the rewards approval threshold and the epoch rewards cap", async () => {
+      try {
+        await program.methods
+            .setRewardsApprovalThreshold(1)
+            .accounts({
+              programData: programData,
+              signer: user.publicKey,
+            })
+            .rpc();
+        assert.fail("Should have failed because the approval threshold is timelocked");
+      } catch (error) {
+        assert.include(error.toString(), "ConfigChangeTimelocked");
+      }
+
+      try {
+        await program.methods
+            .updateEpochRewardsCap(0, new anchor.BN(1000))
+            .accounts({
+              programData: programData,
+              signer: user.publicKey,
+            })
+            .rpc();
+        assert.fail("Should have failed because the epoch rewards cap is timelocked");
+      } catch (error) {
+        assert.include(error.toString(), "ConfigChangeTimelocked");
+      }
+
+      try {
+        await propose({rewardsApprovalThreshold: 6});
+        assert.fail("Should have failed because there are at most 5 rewards admins");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidApprovalThreshold");
+      }
+
+      await propose({rewardsApprovalThreshold: 1, epochRewardsCap: {maxBps: 0, maxAmount: new anchor.BN(1000)}});
+      const pending = await program.account.pendingConfigChange.fetch(pendingPda);
+      while ((await chainTime()) < pending.eta.toNumber()) {
+        await new Promise((resolve) => setTimeout(resolve, 500));
+      }
+      await execute();
+
+      const config = await program.account.config.fetch(configPda);
+      assert.equal(config.rewardsApprovalThreshold, 1);
+      assert.equal(config.maxEpochRewardsBps, 0);
+      assert.equal(config.maxEpochRewards.toNumber(), 1000);
+    });
+
+    it("Lowers the delay through a timelocked change", async () => {
+      // also undo the changes the previous tests made through the timelock
+      await propose({
+        configChangeDelay: new anchor.BN(0),
+        pauseGuardians: [],
+        pendingAdmin: PublicKey.default,
+        rewardsApprovalThreshold: 0,
+        epochRewardsCap: {maxBps: 0, maxAmount: new anchor.BN(0)},
+      });
+      const pending = await program.account.pendingConfigChange.fetch(pendingPda);
+      while ((await chainTime()) < pending.eta.toNumber()) {
+        await new Promise((resolve) => setTimeout(resolve, 500));
+      }
+      await execute();
+
+      const config = await program.account.config.fetch(configPda);
+      assert.equal(config.configChangeDelay.toNumber(), 0);
+      assert.equal(config.pauseGuardians.length, 0);
+      assert.equal(config.pendingAdmin.toBase58(), PublicKey.default.toBase58());
+      assert.equal(config.rewardsApprovalThreshold, 0);
+      assert.equal(config.maxEpochRewards.toNumber(), 0);
+    });
+  });
+
+  describe("pause flags", () => {
+    const PAUSE_ALL = 1 << 0;
+    const PAUSE_DEPOSIT = 1 << 1;
+    const PAUSE_CLAIM = 1 << 4;
+
+    const pauseGuardian = Keypair.generate();
+    const setPauseFlags = (flags: number, signer: Keypair | null = null) => program.methods
+        .setPauseFlags(flags, 7)
+        .accounts({
+          programData: programData,
+          signer: signer ? signer.publicKey : user.publicKey,
+        })
+        .signers(signer ? [signer] : [])
+        .rpc();
+    const deposit = () => program.methods
+        .deposit(new anchor.BN(1))
+        .accounts({
+          vaultTokenAccount: vaultTokenAccount,
+          mint: stakeMint,
+          signer: user.publicKey,
+          userVaultTokenAccount: userVaultTokenAccount,
+          userMintTokenAccount: userStakeTokenAccount,
+        })
+        .rpc();
+
+    it("Pauses only the flagged operations", async () => {
+      await setPauseFlags(PAUSE_DEPOSIT | PAUSE_CLAIM);
+      let config = await program.account.config.fetch(configPda);
+      assert.equal(config.pauseFlags, PAUSE_DEPOSIT | PAUSE_CLAIM);
+
+      try {
+        await deposit();
+        assert.fail("Should have failed because deposits are paused");
+      } catch (error) {
+        assert.include(error.toString(), "ProtocolPaused");
+      }
+
+      try {
+        await setPauseFlags(1 << 6);
+        assert.fail("Should have failed because the flag is unknown");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidPauseFlags");
+      }
+
+      await setPauseFlags(0);
+      config = await program.account.config.fetch(configPda);
+      assert.equal(config.pauseFlags, 0);
+    });
+
+    it("pause() pauses and resumes everything", async () => {
+      await program.methods
+          .pause(true, 0)
+          .accounts({
+            programData: programData,
+            signer: user.publicKey,
+          })
+          .rpc();
+      let config = await program.account.config.fetch(configPda);
+      assert.equal(config.pauseFlags, PAUSE_ALL);
+
+      try {
+        await deposit();
+        assert.fail("Should have failed because the protocol is paused");
+      } catch (error) {
+        assert.include(error.toString(), "ProtocolPaused");
+      }
+
+      await program.methods
+          .pause(false, 0)
+          .accounts({
+            programData: programData,
+            signer: user.publicKey,
+          })
+          .rpc();
+      config = await program.account.config.fetch(configPda);
+      assert.equal(config.pauseFlags, 0);
+    });
+
+    it("pause(false) keeps granular pauses in place", async () => {
+      const pause = (paused: boolean) => program.methods
+          .pause(paused, 0)
+          .accounts({
+            programData: programData,
+            signer: user.publicKey,
+          })
+          .rpc();
+
+      await setPauseFlags(PAUSE_DEPOSIT | PAUSE_CLAIM);
+      await pause(true);
+      await pause(false);
+      const config = await program.account.config.fetch(configPda);
+      assert.equal(config.pauseFlags, PAUSE_DEPOSIT | PAUSE_CLAIM);
+
+      try {
+        await deposit();
+        assert.fail("Should have failed because deposits are still paused");
+      } catch (error) {
+        assert.include(error.toString(), "ProtocolPaused");
+      }
+
+      await setPauseFlags(0);
+    });
+
+    it("Pause guardians can pause but not resume", async () => {
+      try {
+        await setPauseFlags(PAUSE_DEPOSIT, pauseGuardian);
+        assert.fail("Should have failed because the signer is not a guardian yet");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidAdmin");
+      }
+
+      await program.methods
+          .updatePauseGuardians([pauseGuardian.publicKey])
+          .accounts({
+            programData: programData,
+            signer: user.publicKey,
+          })
+          .rpc();
+
+      await setPauseFlags(PAUSE_DEPOSIT, pauseGuardian);
+      await program.methods
+          .pause(true, 3)
+          .accounts({
+            programData: programData,
+            signer: pauseGuardian.publicKey,
+          })
+          .signers([pauseGuardian])
+          .rpc();
+      let config = await program.account.config.fetch(configPda);
+      assert.equal(config.pauseFlags, PAUSE_ALL | PAUSE_DEPOSIT);
+
+      try {
+        await setPauseFlags(0, pauseGuardian);
+        assert.fail("Should have failed because guardians cannot resume operations");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidAdmin");
+      }
+
+      await setPauseFlags(0);
+      config = await program.account.config.fetch(configPda);
+      assert.equal(config.pauseFlags, 0);
+    });
+  });
+
+  describe("exchange-rate mode", () => {
+    it("Starts the exchange rate at par", async () => {
+      await program.methods
+          .setExchangeRateMode(true)
+          .accounts({
+            mint: stakeMint,
+            programData: programData,
+            signer: user.publicKey,
+          })
+          .rpc();
+
+      const config = await program.account.config.fetch(configPda);
+      const mint = await getMint(provider.connection, stakeMint);
+      assert.isTrue(config.exchangeRateMode);
+      assert.equal(config.totalAssets.toString(), mint.supply.toString());
+    });
+
+    it("Mints fewer shares after yield is reported, rounding down", async () => {
+      const yieldAmount = new anchor.BN(33333);
+      await program.methods
+          .reportYield(yieldAmount)
+          .accounts({
+            mint: stakeMint,
+            programData: programData,
+            signer: user.publicKey,
+          })
+          .rpc();
+
+      const configBefore = await program.account.config.fetch(configPda);
+      const supply = new anchor.BN((await getMint(provider.connection, stakeMint)).supply.toString());
+      assert.isTrue(configBefore.totalAssets.gt(supply));
+
+      // a deposit that doesn't divide evenly, so the rounding is exercised
+      let depositAmount = new anchor.BN(1000);
+      while (depositAmount.mul(supply).mod(configBefore.totalAssets).isZero()) {
+        depositAmount = depositAmount.addn(1);
+      }
+      // floor(amount * supply / assets)
+      const expectedShares = depositAmount.mul(supply).div(configBefore.totalAssets);
+
+      const userStakeBefore = await getAccount(provider.connection, userStakeTokenAccount);
+      await program.methods
+          .deposit(depositAmount)
+          .accounts({
+            vaultTokenAccount: vaultTokenAccount,
+            mint: stakeMint,
+            signer: user.publicKey,
+            userVaultTokenAccount: userVaultTokenAccount,
+            userMintTokenAccount: userStakeTokenAccount,
+          })
+          .rpc();
+
+      const userStakeAfter = await getAccount(provider.connection, userStakeTokenAccount);
+      assert.equal(
+          (userStakeAfter.amount - userStakeBefore.amount).toString(),
+          expectedShares.toString()
+      );
+
+      const configAfter = await program.account.config.fetch(configPda);
+      assert.equal(
+          configAfter.totalAssets.sub(configBefore.totalAssets).toString(),
+          depositAmount.toString()
+      );
+    });
+
+    it("Pays out fewer vault tokens than the shares are worth on paper, rounding down", async () => {
+      const configBefore = await program.account.config.fetch(configPda);
+      const supply = new anchor.BN((await getMint(provider.connection, stakeMint)).supply.toString());
+      let shares = new anchor.BN(1000);
+      while (shares.mul(configBefore.totalAssets).mod(supply).isZero()) {
+        shares = shares.addn(1);
+      }
+
+      // floor(shares * assets / supply), then the exit fee is rounded up
+      const expectedAssets = shares.mul(configBefore.totalAssets).div(supply);
+      const feeBps = new anchor.BN(configBefore.instantUnbondFeeBps);
+      const feeNumerator = expectedAssets.mul(feeBps);
+      const expectedFee = feeNumerator.add(new anchor.BN(9999)).div(new anchor.BN(10000));
+
+      const userVaultBefore = await getAccount(provider.connection, userVaultTokenAccount);
+      const treasuryBefore = await getAccount(provider.connection, treasuryTokenAccount);
+
+      await program.methods
+          .instantUnbond(shares)
+          .accounts({
+            vaultTokenAccount: vaultTokenAccount,
+            treasuryTokenAccount: treasuryTokenAccount,
+            signer: user.publicKey,
+            userMintTokenAccount: userStakeTokenAccount,
+            userVaultTokenAccount: userVaultTokenAccount,
+            mint: stakeMint,
+          })
+          .rpc();
+
+      const userVaultAfter = await getAccount(provider.connection, userVaultTokenAccount);
+      const treasuryAfter = await getAccount(provider.connection, treasuryTokenAccount);
+      assert.equal(
+          (userVaultAfter.amount - userVaultBefore.amount).toString(),
+          expectedAssets.sub(expectedFee).toString()
+      );
+      assert.equal(
+          (treasuryAfter.amount - treasuryBefore.amount).toString(),
+          expectedFee.toString()
+      );
+
+      const configAfter = await program.account.config.fetch(configPda);
+      assert.equal(
+          configBefore.totalAssets.sub(configAfter.totalAssets).toString(),
+          expectedAssets.toString()
+      );
+    });
+
+    it("Mints reward claims at the exchange rate without moving the share price", async () => {
+      const claimAmount = new anchor.BN(777);
+      const {epochPda} = await createSingleClaimEpoch(await nextEpochIndex(), claimAmount);
+
+      const configBefore = await program.account.config.fetch(configPda);
+      const supplyBefore = new anchor.BN((await getMint(provider.connection, stakeMint)).supply.toString());
+      const userStakeBefore = await getAccount(provider.connection, userStakeTokenAccount);
+
+      await program.methods
+          .claimRewards(claimAmount, [])
+          .accounts({
+            user: user.publicKey,
+            epoch: epochPda,
+            vesting: null,
+            mint: stakeMint,
+            userStakeTokenAccount: userStakeTokenAccount,
+          })
+          .rpc();
+
+      const configAfter = await program.account.config.fetch(configPda);
+      const supplyAfter = new anchor.BN((await getMint(provider.connection, stakeMint)).supply.toString());
+      const userStakeAfter = await getAccount(provider.connection, userStakeTokenAccount);
+
+      // floor(amount * supply / assets), backed by the claimed vault tokens
+      const expectedShares = claimAmount.mul(supplyBefore).div(configBefore.totalAssets);
+      assert.equal(
+          (userStakeAfter.amount - userStakeBefore.amount).toString(),
+          expectedShares.toString()
+      );
+      assert.equal(
+          configAfter.totalAssets.sub(configBefore.totalAssets).toString(),
+          claimAmount.toString()
+      );
+
+      // assets per share never drops: A' / S' >= A / S, and rises by less than one share's rounding
+      const priceDelta = configAfter.totalAssets.mul(supplyBefore).sub(configBefore.totalAssets.mul(supplyAfter));
+      assert.isFalse(priceDelta.isNeg());
+      assert.isTrue(priceDelta.lt(configBefore.totalAssets));
+    });
+
+    it("Rejects unfunded rewards epochs in exchange-rate mode", async () => {
+      await program.methods
+          .setFundedRewards(false)
+          .accounts({
+            programData: programData,
+            signer: user.publicKey,
+          })
+          .rpc();
+
+      try {
+        await createRewardsEpoch({index: await nextEpochIndex(), total: new anchor.BN(100), funded: false});
+        assert.fail("Should have failed because claims must be backed by vault tokens");
+      } catch (error) {
+        assert.include(error.toString(), "RewardsFundingRequired");
+      }
+
+      await program.methods
+          .setFundedRewards(true)
+          .accounts({
+            programData: programData,
+            signer: user.publicKey,
+          })
+          .rpc();
+    });
+
+    it("Refuses to leave exchange-rate mode while the rate is above par", async () => {
+      try {
+        await program.methods
+            .setExchangeRateMode(false)
+            .accounts({
+              mint: stakeMint,
+              programData: programData,
+              signer: user.publicKey,
+            })
+            .rpc();
+        assert.fail("Should have failed because the rate is above par");
+      } catch (error) {
+        assert.include(error.toString(), "InvalidExchangeRate");
+      }
+    });
+  });
 });